@@ -0,0 +1,441 @@
+use sea_orm::prelude::DateTime;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    answers::AnswerModel,
+    attributes::AttributeModel,
+    attributesvalues::AttributeValueModel,
+    clauses::ClauseModel,
+    object_attribute_attributevalue::ObjectAttributeAttributeValueModel,
+    objects::ObjectModel,
+    questions::QuestionModel,
+    rule_attribute_attributevalue::RuleAttributeAttributeValueModel,
+    rule_question_answer::RuleQuestionAnswerModel,
+    rules::RuleModel,
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+    systems::SystemModel,
+    test_cases::TestCaseModel,
+};
+
+// Модели сущностей не десериализуют id, а bincode не умеет пропускать поля,
+// поэтому в резервной копии хранятся их полные копии
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SystemBackupModel {
+    pub system: BackupSystemModel,
+    pub objects: Vec<BackupObjectModel>,
+    pub object_attribute_attributevalue: Vec<BackupObjectAttributeAttributeValueModel>,
+    pub attributes: Vec<BackupAttributeModel>,
+    pub attributes_values: Vec<BackupAttributeValueModel>,
+    pub rules: Vec<BackupRuleModel>,
+    pub rule_attribute_attributevalue: Vec<BackupRuleAttributeAttributeValueModel>,
+    pub clauses: Vec<BackupClauseModel>,
+    pub questions: Vec<BackupQuestionModel>,
+    pub answers: Vec<BackupAnswerModel>,
+    pub rule_question_answer: Vec<BackupRuleQuestionAnswerModel>,
+    pub test_cases: Vec<BackupTestCaseModel>,
+    pub media: Vec<BackupMediaModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupSystemModel {
+    pub id: i32,
+    pub user_id: i32,
+    pub about: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub name: String,
+    pub private: bool,
+    pub image_uri: Option<String>,
+    pub stars: i32,
+}
+
+impl From<SystemModel> for BackupSystemModel {
+    fn from(model: SystemModel) -> Self {
+        BackupSystemModel {
+            id: model.id,
+            user_id: model.user_id,
+            about: model.about,
+            created_at: model.created_at,
+            updated_at: model.updated_at,
+            name: model.name,
+            private: model.private,
+            image_uri: model.image_uri,
+            stars: model.stars,
+        }
+    }
+}
+
+impl From<BackupSystemModel> for SystemModel {
+    fn from(backup: BackupSystemModel) -> Self {
+        SystemModel {
+            id: backup.id,
+            user_id: backup.user_id,
+            about: backup.about,
+            created_at: backup.created_at,
+            updated_at: backup.updated_at,
+            name: backup.name,
+            private: backup.private,
+            image_uri: backup.image_uri,
+            stars: backup.stars,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupObjectModel {
+    pub id: i32,
+    pub system_id: i32,
+    pub name: String,
+}
+
+impl From<ObjectModel> for BackupObjectModel {
+    fn from(model: ObjectModel) -> Self {
+        BackupObjectModel {
+            id: model.id,
+            system_id: model.system_id,
+            name: model.name,
+        }
+    }
+}
+
+impl From<BackupObjectModel> for ObjectModel {
+    fn from(backup: BackupObjectModel) -> Self {
+        ObjectModel {
+            id: backup.id,
+            system_id: backup.system_id,
+            name: backup.name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupObjectAttributeAttributeValueModel {
+    pub id: i32,
+    pub object_id: i32,
+    pub attribute_value_id: i32,
+    pub attribute_id: i32,
+}
+
+impl From<ObjectAttributeAttributeValueModel> for BackupObjectAttributeAttributeValueModel {
+    fn from(model: ObjectAttributeAttributeValueModel) -> Self {
+        BackupObjectAttributeAttributeValueModel {
+            id: model.id,
+            object_id: model.object_id,
+            attribute_value_id: model.attribute_value_id,
+            attribute_id: model.attribute_id,
+        }
+    }
+}
+
+impl From<BackupObjectAttributeAttributeValueModel> for ObjectAttributeAttributeValueModel {
+    fn from(backup: BackupObjectAttributeAttributeValueModel) -> Self {
+        ObjectAttributeAttributeValueModel {
+            id: backup.id,
+            object_id: backup.object_id,
+            attribute_value_id: backup.attribute_value_id,
+            attribute_id: backup.attribute_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupAttributeModel {
+    pub id: i32,
+    pub system_id: i32,
+    pub name: String,
+}
+
+impl From<AttributeModel> for BackupAttributeModel {
+    fn from(model: AttributeModel) -> Self {
+        BackupAttributeModel {
+            id: model.id,
+            system_id: model.system_id,
+            name: model.name,
+        }
+    }
+}
+
+impl From<BackupAttributeModel> for AttributeModel {
+    fn from(backup: BackupAttributeModel) -> Self {
+        AttributeModel {
+            id: backup.id,
+            system_id: backup.system_id,
+            name: backup.name,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupAttributeValueModel {
+    pub id: i32,
+    pub attribute_id: i32,
+    pub value: String,
+}
+
+impl From<AttributeValueModel> for BackupAttributeValueModel {
+    fn from(model: AttributeValueModel) -> Self {
+        BackupAttributeValueModel {
+            id: model.id,
+            attribute_id: model.attribute_id,
+            value: model.value,
+        }
+    }
+}
+
+impl From<BackupAttributeValueModel> for AttributeValueModel {
+    fn from(backup: BackupAttributeValueModel) -> Self {
+        AttributeValueModel {
+            id: backup.id,
+            attribute_id: backup.attribute_id,
+            value: backup.value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupRuleModel {
+    pub id: i32,
+    pub system_id: i32,
+    pub attribute_rule: bool,
+    pub certainty_factor: f64,
+    // bincode не поддерживает serde_json::Value, дерево условий хранится текстом
+    pub condition: Option<String>,
+}
+
+impl From<RuleModel> for BackupRuleModel {
+    fn from(model: RuleModel) -> Self {
+        BackupRuleModel {
+            id: model.id,
+            system_id: model.system_id,
+            attribute_rule: model.attribute_rule,
+            certainty_factor: model.certainty_factor,
+            condition: model.condition.map(|condition| condition.to_string()),
+        }
+    }
+}
+
+impl From<BackupRuleModel> for RuleModel {
+    fn from(backup: BackupRuleModel) -> Self {
+        RuleModel {
+            id: backup.id,
+            system_id: backup.system_id,
+            attribute_rule: backup.attribute_rule,
+            certainty_factor: backup.certainty_factor,
+            condition: backup
+                .condition
+                .and_then(|condition| serde_json::from_str(&condition).ok()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupRuleAttributeAttributeValueModel {
+    pub id: i32,
+    pub attribute_value_id: i32,
+    pub rule_id: i32,
+    pub attribute_id: i32,
+}
+
+impl From<RuleAttributeAttributeValueModel> for BackupRuleAttributeAttributeValueModel {
+    fn from(model: RuleAttributeAttributeValueModel) -> Self {
+        BackupRuleAttributeAttributeValueModel {
+            id: model.id,
+            attribute_value_id: model.attribute_value_id,
+            rule_id: model.rule_id,
+            attribute_id: model.attribute_id,
+        }
+    }
+}
+
+impl From<BackupRuleAttributeAttributeValueModel> for RuleAttributeAttributeValueModel {
+    fn from(backup: BackupRuleAttributeAttributeValueModel) -> Self {
+        RuleAttributeAttributeValueModel {
+            id: backup.id,
+            attribute_value_id: backup.attribute_value_id,
+            rule_id: backup.rule_id,
+            attribute_id: backup.attribute_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupClauseModel {
+    pub id: i32,
+    pub rule_id: i32,
+    pub compared_value: String,
+    pub logical_group: String,
+    pub operator: Operatorenum,
+    pub question_id: i32,
+    pub value_type: Valuetypeenum,
+}
+
+impl From<ClauseModel> for BackupClauseModel {
+    fn from(model: ClauseModel) -> Self {
+        BackupClauseModel {
+            id: model.id,
+            rule_id: model.rule_id,
+            compared_value: model.compared_value,
+            logical_group: model.logical_group,
+            operator: model.operator,
+            question_id: model.question_id,
+            value_type: model.value_type,
+        }
+    }
+}
+
+impl From<BackupClauseModel> for ClauseModel {
+    fn from(backup: BackupClauseModel) -> Self {
+        ClauseModel {
+            id: backup.id,
+            rule_id: backup.rule_id,
+            compared_value: backup.compared_value,
+            logical_group: backup.logical_group,
+            operator: backup.operator,
+            question_id: backup.question_id,
+            value_type: backup.value_type,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupQuestionModel {
+    pub id: i32,
+    pub system_id: i32,
+    pub body: String,
+    pub with_chooses: bool,
+}
+
+impl From<QuestionModel> for BackupQuestionModel {
+    fn from(model: QuestionModel) -> Self {
+        BackupQuestionModel {
+            id: model.id,
+            system_id: model.system_id,
+            body: model.body,
+            with_chooses: model.with_chooses,
+        }
+    }
+}
+
+impl From<BackupQuestionModel> for QuestionModel {
+    fn from(backup: BackupQuestionModel) -> Self {
+        QuestionModel {
+            id: backup.id,
+            system_id: backup.system_id,
+            body: backup.body,
+            with_chooses: backup.with_chooses,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupAnswerModel {
+    pub id: i32,
+    pub question_id: i32,
+    pub body: String,
+}
+
+impl From<AnswerModel> for BackupAnswerModel {
+    fn from(model: AnswerModel) -> Self {
+        BackupAnswerModel {
+            id: model.id,
+            question_id: model.question_id,
+            body: model.body,
+        }
+    }
+}
+
+impl From<BackupAnswerModel> for AnswerModel {
+    fn from(backup: BackupAnswerModel) -> Self {
+        AnswerModel {
+            id: backup.id,
+            question_id: backup.question_id,
+            body: backup.body,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupRuleQuestionAnswerModel {
+    pub id: i32,
+    pub answer_id: i32,
+    pub rule_id: i32,
+    pub question_id: i32,
+}
+
+impl From<RuleQuestionAnswerModel> for BackupRuleQuestionAnswerModel {
+    fn from(model: RuleQuestionAnswerModel) -> Self {
+        BackupRuleQuestionAnswerModel {
+            id: model.id,
+            answer_id: model.answer_id,
+            rule_id: model.rule_id,
+            question_id: model.question_id,
+        }
+    }
+}
+
+impl From<BackupRuleQuestionAnswerModel> for RuleQuestionAnswerModel {
+    fn from(backup: BackupRuleQuestionAnswerModel) -> Self {
+        RuleQuestionAnswerModel {
+            id: backup.id,
+            answer_id: backup.answer_id,
+            rule_id: backup.rule_id,
+            question_id: backup.question_id,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupTestCaseModel {
+    pub id: i32,
+    pub system_id: i32,
+    pub name: String,
+    pub answers: String,
+    pub expected_object_ids: String,
+    pub expected_attribute_value_ids: String,
+    pub created_at: DateTime,
+}
+
+impl From<TestCaseModel> for BackupTestCaseModel {
+    fn from(model: TestCaseModel) -> Self {
+        BackupTestCaseModel {
+            id: model.id,
+            system_id: model.system_id,
+            name: model.name,
+            answers: model.answers.to_string(),
+            expected_object_ids: model.expected_object_ids.to_string(),
+            expected_attribute_value_ids: model.expected_attribute_value_ids.to_string(),
+            created_at: model.created_at,
+        }
+    }
+}
+
+impl From<BackupTestCaseModel> for TestCaseModel {
+    fn from(backup: BackupTestCaseModel) -> Self {
+        let parse = |raw: &str| serde_json::from_str(raw).unwrap_or_default();
+        TestCaseModel {
+            id: backup.id,
+            system_id: backup.system_id,
+            name: backup.name,
+            answers: parse(&backup.answers),
+            expected_object_ids: parse(&backup.expected_object_ids),
+            expected_attribute_value_ids: parse(&backup.expected_attribute_value_ids),
+            created_at: backup.created_at,
+        }
+    }
+}
+
+// Владелец файла в копии. bincode пишет номер варианта, поэтому новые варианты
+// (например, картинки вопросов и объектов) добавляются только в конец
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupMediaOwner {
+    System,
+}
+
+// owner_id - id владельца внутри копии, file_name - исходное имя файла
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupMediaModel {
+    pub owner: BackupMediaOwner,
+    pub owner_id: i32,
+    pub file_name: String,
+    pub content: Vec<u8>,
+}
@@ -0,0 +1,209 @@
+use sea_orm::prelude::DateTime;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    backup::{
+        BackupAnswerModel, BackupAttributeModel, BackupAttributeValueModel, BackupClauseModel,
+        BackupObjectAttributeAttributeValueModel, BackupObjectModel, BackupQuestionModel,
+        BackupRuleAttributeAttributeValueModel, BackupRuleModel, BackupRuleQuestionAnswerModel,
+        BackupSystemModel, SystemBackupModel,
+    },
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+};
+
+// Раскладки резервных копий, записанных до появления заголовка. Версия 1 поднимается
+// до версии 2, версия 2 - сразу до SystemBackupModel:
+// 1 - до колонки stars у систем
+// 2 - модели сущностей как есть
+// 3 - SystemBackupModel, с этой версии перед данными пишется заголовок
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupSystemModelV1 {
+    pub id: i32,
+    pub user_id: i32,
+    pub about: Option<String>,
+    pub created_at: DateTime,
+    pub updated_at: DateTime,
+    pub name: String,
+    pub private: bool,
+    pub image_uri: Option<String>,
+}
+
+impl From<BackupSystemModelV1> for BackupSystemModel {
+    fn from(legacy: BackupSystemModelV1) -> Self {
+        BackupSystemModel {
+            id: legacy.id,
+            user_id: legacy.user_id,
+            about: legacy.about,
+            created_at: legacy.created_at,
+            updated_at: legacy.updated_at,
+            name: legacy.name,
+            private: legacy.private,
+            image_uri: legacy.image_uri,
+            stars: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupRuleModelV1 {
+    pub id: i32,
+    pub system_id: i32,
+    pub attribute_rule: bool,
+}
+
+// Правила без дерева условий вычисляются по logical_group
+impl From<BackupRuleModelV1> for BackupRuleModel {
+    fn from(legacy: BackupRuleModelV1) -> Self {
+        BackupRuleModel {
+            id: legacy.id,
+            system_id: legacy.system_id,
+            attribute_rule: legacy.attribute_rule,
+            certainty_factor: 1.0,
+            condition: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BackupClauseModelV1 {
+    pub id: i32,
+    pub rule_id: i32,
+    pub compared_value: String,
+    pub logical_group: String,
+    pub operator: Operatorenum,
+    pub question_id: i32,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SystemBackupModelV1 {
+    pub system: BackupSystemModelV1,
+    pub objects: Vec<BackupObjectModel>,
+    pub object_attribute_attributevalue: Vec<BackupObjectAttributeAttributeValueModel>,
+    pub attributes: Vec<BackupAttributeModel>,
+    pub attributes_values: Vec<BackupAttributeValueModel>,
+    pub rules: Vec<BackupRuleModelV1>,
+    pub rule_attribute_attributevalue: Vec<BackupRuleAttributeAttributeValueModel>,
+    pub clauses: Vec<BackupClauseModelV1>,
+    pub questions: Vec<BackupQuestionModel>,
+    pub answers: Vec<BackupAnswerModel>,
+    pub rule_question_answer: Vec<BackupRuleQuestionAnswerModel>,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct SystemBackupModelV2 {
+    pub system: BackupSystemModel,
+    pub objects: Vec<BackupObjectModel>,
+    pub object_attribute_attributevalue: Vec<BackupObjectAttributeAttributeValueModel>,
+    pub attributes: Vec<BackupAttributeModel>,
+    pub attributes_values: Vec<BackupAttributeValueModel>,
+    pub rules: Vec<BackupRuleModelV1>,
+    pub rule_attribute_attributevalue: Vec<BackupRuleAttributeAttributeValueModel>,
+    pub clauses: Vec<BackupClauseModelV1>,
+    pub questions: Vec<BackupQuestionModel>,
+    pub answers: Vec<BackupAnswerModel>,
+    pub rule_question_answer: Vec<BackupRuleQuestionAnswerModel>,
+}
+
+impl From<SystemBackupModelV1> for SystemBackupModelV2 {
+    fn from(legacy: SystemBackupModelV1) -> Self {
+        SystemBackupModelV2 {
+            system: legacy.system.into(),
+            objects: legacy.objects,
+            object_attribute_attributevalue: legacy.object_attribute_attributevalue,
+            attributes: legacy.attributes,
+            attributes_values: legacy.attributes_values,
+            rules: legacy.rules,
+            rule_attribute_attributevalue: legacy.rule_attribute_attributevalue,
+            clauses: legacy.clauses,
+            questions: legacy.questions,
+            answers: legacy.answers,
+            rule_question_answer: legacy.rule_question_answer,
+        }
+    }
+}
+
+// Тип значения определяется так же, как в миграции m20241023_120000_add_value_type_to_clauses
+fn typed_clause(
+    legacy: BackupClauseModelV1,
+    questions: &[BackupQuestionModel],
+    answers: &[BackupAnswerModel],
+) -> BackupClauseModel {
+    let value = legacy.compared_value.trim();
+    let with_chooses = questions
+        .iter()
+        .any(|question| question.id == legacy.question_id && question.with_chooses);
+    let equality = matches!(
+        legacy.operator,
+        Operatorenum::Equal | Operatorenum::NotEqual
+    );
+    let question_answers = || {
+        answers
+            .iter()
+            .filter(|answer| answer.question_id == legacy.question_id)
+    };
+    let answer = (with_chooses && equality)
+        .then(|| {
+            question_answers()
+                .find(|answer| answer.body.trim() == value)
+                .or_else(|| question_answers().find(|answer| answer.id.to_string() == value))
+        })
+        .flatten();
+    let is_digits =
+        |digits: &str| !digits.is_empty() && digits.chars().all(|symbol| symbol.is_ascii_digit());
+    let unsigned = value.strip_prefix('-').unwrap_or(value);
+    let is_decimal = match unsigned.split_once(['.', ',']) {
+        Some((whole, fraction)) => is_digits(whole) && is_digits(fraction),
+        None => is_digits(unsigned),
+    };
+
+    let (value_type, compared_value) = if let Some(answer) = answer {
+        (Valuetypeenum::Answer, answer.id.to_string())
+    } else if is_digits(unsigned) && unsigned.len() <= 18 {
+        (Valuetypeenum::Integer, value.to_string())
+    } else if is_decimal {
+        (Valuetypeenum::Decimal, value.replace(',', "."))
+    } else if equality && ["true", "false"].contains(&value.to_lowercase().as_str()) {
+        (Valuetypeenum::Boolean, value.to_lowercase())
+    } else {
+        (Valuetypeenum::Text, legacy.compared_value.clone())
+    };
+
+    BackupClauseModel {
+        id: legacy.id,
+        rule_id: legacy.rule_id,
+        compared_value,
+        logical_group: legacy.logical_group,
+        operator: legacy.operator,
+        question_id: legacy.question_id,
+        value_type,
+    }
+}
+
+// В копиях без файлов картинка системы известна только по image_uri,
+// при восстановлении она копируется с диска, если файл еще существует
+impl From<SystemBackupModelV2> for SystemBackupModel {
+    fn from(legacy: SystemBackupModelV2) -> Self {
+        let clauses = legacy
+            .clauses
+            .into_iter()
+            .map(|clause| typed_clause(clause, &legacy.questions, &legacy.answers))
+            .collect();
+
+        SystemBackupModel {
+            system: legacy.system,
+            objects: legacy.objects,
+            object_attribute_attributevalue: legacy.object_attribute_attributevalue,
+            attributes: legacy.attributes,
+            attributes_values: legacy.attributes_values,
+            rules: legacy.rules.into_iter().map(Into::into).collect(),
+            rule_attribute_attributevalue: legacy.rule_attribute_attributevalue,
+            clauses,
+            questions: legacy.questions,
+            answers: legacy.answers,
+            rule_question_answer: legacy.rule_question_answer,
+            test_cases: Vec::new(),
+            media: Vec::new(),
+        }
+    }
+}
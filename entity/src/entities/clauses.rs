@@ -1,6 +1,6 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
-use super::sea_orm_active_enums::Operatorenum;
+use super::sea_orm_active_enums::{Operatorenum, Valuetypeenum};
 use sea_orm::{entity::prelude::*, ActiveValue::NotSet, IntoActiveModel, Set, Unchanged};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
@@ -18,6 +18,8 @@ pub struct Model {
     pub logical_group: String,
     pub operator: Operatorenum,
     pub question_id: i32,
+    #[serde(default)]
+    pub value_type: Valuetypeenum,
 }
 
 pub use Model as ClauseModel;
@@ -29,6 +31,7 @@ pub struct UpdateClauseModel {
     pub logical_group: Option<String>,
     pub operator: Option<Operatorenum>,
     pub question_id: Option<i32>,
+    pub value_type: Option<Valuetypeenum>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
@@ -37,6 +40,8 @@ pub struct NewClauseWithoutRule {
     pub logical_group: String,
     pub operator: Operatorenum,
     pub question_id: i32,
+    #[serde(default)]
+    pub value_type: Valuetypeenum,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -87,6 +92,7 @@ impl IntoActiveModel<ActiveModel> for UpdateClauseModel {
             question_id: self
                 .question_id
                 .map_or(NotSet, |question_id| Set(question_id)),
+            value_type: self.value_type.map_or(NotSet, Set),
             ..Default::default()
         }
     }
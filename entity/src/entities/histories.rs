@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use utoipa::ToSchema;
 
-use super::systems::SystemModel;
+use super::{sea_orm_active_enums::Consultationmodeenum, systems::SystemModel};
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, DeriveEntityModel, Eq, ToSchema)]
 #[schema(as = HistoryModel)]
@@ -19,8 +19,19 @@ pub struct Model {
     pub system_id: i32,
     pub user_id: i32,
     pub answered_questions: String,
+    #[serde(default)]
+    #[schema(value_type = Option<Vec<Object>>)]
+    pub answers: Option<Value>,
     #[schema(value_type=HashMap<String, u8>)]
     pub results: Value,
+    #[serde(default)]
+    pub mode: Consultationmodeenum,
+    #[serde(default)]
+    pub goal_object_id: Option<i32>,
+    #[serde(default)]
+    pub goal_attribute_value_id: Option<i32>,
+    #[serde(skip)]
+    pub explanation: Option<Value>,
     #[serde(skip_deserializing)]
     pub started_at: DateTime,
     #[serde(skip_deserializing)]
@@ -36,6 +47,9 @@ pub struct HistoryWithSystem {
     pub answered_questions: String,
     #[schema(value_type=HashMap<String, u8>)]
     pub results: Value,
+    pub mode: Consultationmodeenum,
+    pub goal_object_id: Option<i32>,
+    pub goal_attribute_value_id: Option<i32>,
     pub started_at: NaiveDateTime,
     pub finished_at: NaiveDateTime,
 }
@@ -11,7 +11,9 @@ pub struct Model {
     #[schema(read_only)]
     #[serde(skip_deserializing)]
     pub id: i32,
+    // При создании лайка берется из сессии
     #[sea_orm(primary_key, auto_increment = false)]
+    #[serde(default)]
     pub user_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
     pub system_id: i32,
@@ -1,9 +1,13 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.15
 
+pub mod access_tokens;
 pub mod answers;
 pub mod attributes;
 pub mod attributesvalues;
+pub mod backup;
+pub mod backup_legacy;
 pub mod clauses;
+pub mod consultations;
 pub mod histories;
 pub mod likes;
 pub mod object_attribute_attributevalue;
@@ -13,5 +17,8 @@ pub mod rule_attribute_attributevalue;
 pub mod rule_question_answer;
 pub mod rules;
 pub mod sea_orm_active_enums;
+pub mod sessions;
 pub mod systems;
+pub mod test_cases;
+pub mod two_factor_challenges;
 pub mod users;
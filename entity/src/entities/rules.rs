@@ -2,7 +2,14 @@
 
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use serde_json::Value;
+use utoipa::{
+    openapi::{
+        schema::{ArrayBuilder, ObjectBuilder, OneOfBuilder, Schema},
+        Ref, RefOr,
+    },
+    PartialSchema, ToSchema,
+};
 
 use super::{
     clauses::{ClauseModel, NewClauseWithoutRule},
@@ -12,7 +19,7 @@ use super::{
     rule_question_answer::{NewRuleQuestionAnswerWithoutRuleModel, RuleQuestionAnswerModel},
 };
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, DeriveEntityModel, Eq, ToSchema)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, DeriveEntityModel, ToSchema)]
 #[schema(as = RuleModel)]
 #[sea_orm(table_name = "rules")]
 pub struct Model {
@@ -22,15 +29,200 @@ pub struct Model {
     pub id: i32,
     pub system_id: i32,
     pub attribute_rule: bool,
+    pub certainty_factor: f64,
+    #[schema(value_type = Option<ConditionNode>)]
+    pub condition: Option<Value>,
 }
 
 pub use Model as RuleModel;
 
+pub fn default_certainty_factor() -> f64 {
+    1.0
+}
+
+// Дерево условий правила. Лист ссылается на id условия (clauses)
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ConditionNode {
+    And(Vec<ConditionNode>),
+    Or(Vec<ConditionNode>),
+    Not(Box<ConditionNode>),
+    Clause(i32),
+}
+
+// Дерево условий создаваемого правила. Лист - индекс в массиве clauses этого правила,
+// id условий появляются только после их вставки
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum NewConditionNode {
+    And(Vec<NewConditionNode>),
+    Or(Vec<NewConditionNode>),
+    Not(Box<NewConditionNode>),
+    Clause(usize),
+}
+
+// Схема описана вручную: utoipa уходит в бесконечную рекурсию на рекурсивном enum
+fn condition_schema(name: &str, leaf: RefOr<Schema>) -> RefOr<Schema> {
+    let node = || Ref::from_schema_name(name);
+    let variant = |name: &str, schema: RefOr<Schema>| {
+        ObjectBuilder::new()
+            .property(name, schema)
+            .required(name)
+            .build()
+    };
+
+    OneOfBuilder::new()
+        .item(variant("And", ArrayBuilder::new().items(node()).into()))
+        .item(variant("Or", ArrayBuilder::new().items(node()).into()))
+        .item(variant("Not", node().into()))
+        .item(variant("Clause", leaf))
+        .into()
+}
+
+impl PartialSchema for ConditionNode {
+    fn schema() -> RefOr<Schema> {
+        condition_schema("ConditionNode", i32::schema())
+    }
+}
+
+impl ToSchema for ConditionNode {}
+
+impl PartialSchema for NewConditionNode {
+    fn schema() -> RefOr<Schema> {
+        condition_schema("NewConditionNode", usize::schema())
+    }
+}
+
+impl ToSchema for NewConditionNode {}
+
+impl NewConditionNode {
+    // Заменяет индексы на id вставленных условий; None, если индекс вне clause_ids
+    pub fn resolve(&self, clause_ids: &[i32]) -> Option<ConditionNode> {
+        let nodes = |nodes: &[NewConditionNode]| {
+            nodes
+                .iter()
+                .map(|node| node.resolve(clause_ids))
+                .collect::<Option<Vec<_>>>()
+        };
+
+        Some(match self {
+            NewConditionNode::And(children) => ConditionNode::And(nodes(children)?),
+            NewConditionNode::Or(children) => ConditionNode::Or(nodes(children)?),
+            NewConditionNode::Not(node) => ConditionNode::Not(Box::new(node.resolve(clause_ids)?)),
+            NewConditionNode::Clause(index) => ConditionNode::Clause(*clause_ids.get(*index)?),
+        })
+    }
+
+    // Обратное преобразование: id условий заменяются их позициями
+    pub fn from_condition<F>(condition: &ConditionNode, index_of: &F) -> Option<Self>
+    where
+        F: Fn(i32) -> Option<usize>,
+    {
+        let nodes = |nodes: &[ConditionNode]| {
+            nodes
+                .iter()
+                .map(|node| Self::from_condition(node, index_of))
+                .collect::<Option<Vec<_>>>()
+        };
+
+        Some(match condition {
+            ConditionNode::And(children) => NewConditionNode::And(nodes(children)?),
+            ConditionNode::Or(children) => NewConditionNode::Or(nodes(children)?),
+            ConditionNode::Not(node) => {
+                NewConditionNode::Not(Box::new(Self::from_condition(node, index_of)?))
+            }
+            ConditionNode::Clause(clause_id) => NewConditionNode::Clause(index_of(*clause_id)?),
+        })
+    }
+}
+
+impl ConditionNode {
+    // Прежнее соглашение: условия одной logical_group через И, группы через ИЛИ
+    pub fn from_groups(clauses: &[ClauseModel]) -> Self {
+        let mut groups: Vec<(&str, Vec<ConditionNode>)> = Vec::new();
+        clauses.iter().for_each(|clause| {
+            let leaf = ConditionNode::Clause(clause.id);
+            match groups
+                .iter_mut()
+                .find(|(group, _)| *group == clause.logical_group)
+            {
+                Some((_, nodes)) => nodes.push(leaf),
+                None => groups.push((&clause.logical_group, vec![leaf])),
+            }
+        });
+
+        ConditionNode::Or(
+            groups
+                .into_iter()
+                .map(|(_, nodes)| ConditionNode::And(nodes))
+                .collect(),
+        )
+    }
+
+    pub fn clause_ids(&self) -> Vec<i32> {
+        match self {
+            ConditionNode::And(nodes) | ConditionNode::Or(nodes) => {
+                nodes.iter().flat_map(|node| node.clause_ids()).collect()
+            }
+            ConditionNode::Not(node) => node.clause_ids(),
+            ConditionNode::Clause(clause_id) => vec![*clause_id],
+        }
+    }
+
+    // None, если какой-либо лист не удалось сопоставить
+    pub fn map_clauses<F>(&self, map: &F) -> Option<Self>
+    where
+        F: Fn(i32) -> Option<i32>,
+    {
+        Some(match self {
+            ConditionNode::And(nodes) => ConditionNode::And(
+                nodes
+                    .iter()
+                    .map(|node| node.map_clauses(map))
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            ConditionNode::Or(nodes) => ConditionNode::Or(
+                nodes
+                    .iter()
+                    .map(|node| node.map_clauses(map))
+                    .collect::<Option<Vec<_>>>()?,
+            ),
+            ConditionNode::Not(node) => ConditionNode::Not(Box::new(node.map_clauses(map)?)),
+            ConditionNode::Clause(clause_id) => ConditionNode::Clause(map(*clause_id)?),
+        })
+    }
+
+    // Убирает листья удаленных условий и опустевшие узлы
+    pub fn prune(&self, clause_ids: &[i32]) -> Option<Self> {
+        match self {
+            ConditionNode::And(nodes) | ConditionNode::Or(nodes) => {
+                let nodes: Vec<ConditionNode> = nodes
+                    .iter()
+                    .filter_map(|node| node.prune(clause_ids))
+                    .collect();
+                if nodes.is_empty() {
+                    None
+                } else if matches!(self, ConditionNode::And(_)) {
+                    Some(ConditionNode::And(nodes))
+                } else {
+                    Some(ConditionNode::Or(nodes))
+                }
+            }
+            ConditionNode::Not(node) => node
+                .prune(clause_ids)
+                .map(|node| ConditionNode::Not(Box::new(node))),
+            ConditionNode::Clause(clause_id) => {
+                clause_ids.contains(clause_id).then_some(self.clone())
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
 pub struct RuleWithClausesAndEffects {
     pub id: i32,
     pub system_id: i32,
     pub attribute_rule: bool,
+    pub certainty_factor: f64,
+    pub condition: Option<ConditionNode>,
     pub clauses: Vec<ClauseModel>,
     pub rule_question_answer_ids: Vec<RuleQuestionAnswerModel>,
     pub rule_attribute_attributevalue_ids: Vec<RuleAttributeAttributeValueModel>,
@@ -40,6 +232,9 @@ pub struct RuleWithClausesAndEffects {
 pub struct NewRuleWithClausesAndEffects {
     pub system_id: i32,
     pub attribute_rule: bool,
+    #[serde(default = "default_certainty_factor")]
+    pub certainty_factor: f64,
+    pub condition: Option<NewConditionNode>,
     pub clauses: Vec<NewClauseWithoutRule>,
     pub rule_question_answer_ids: Vec<NewRuleQuestionAnswerWithoutRuleModel>,
     pub rule_attribute_attributevalue_ids: Vec<NewRuleAttributeAttributeValueWithoutRuleModel>,
@@ -152,3 +152,183 @@ impl ValueType for Operatorenum {
         }
     }
 }
+
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, EnumIter, ToSchema,
+)]
+pub enum Consultationmodeenum {
+    #[default]
+    Forward,
+    Backward,
+}
+
+#[derive(Iden)]
+enum ConsultationmodeenumIden {
+    Consultationmodeenum,
+}
+
+#[derive(Iden)]
+enum ConsultationmodeenumVariants {
+    Forward,
+    Backward,
+}
+
+impl Consultationmodeenum {
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "FORWARD" => Some(Consultationmodeenum::Forward),
+            "BACKWARD" => Some(Consultationmodeenum::Backward),
+            _ => None,
+        }
+    }
+}
+
+impl From<Consultationmodeenum> for String {
+    fn from(mode: Consultationmodeenum) -> Self {
+        match mode {
+            Consultationmodeenum::Forward => "FORWARD".to_string(),
+            Consultationmodeenum::Backward => "BACKWARD".to_string(),
+        }
+    }
+}
+
+impl From<Consultationmodeenum> for Value {
+    fn from(mode: Consultationmodeenum) -> Self {
+        let string_value: String = mode.into();
+        Value::String(Some(Box::new(string_value)))
+    }
+}
+
+impl TryGetable for Consultationmodeenum {
+    fn try_get_by<I: sea_orm::ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let value: String = res.try_get_by(index)?;
+        Consultationmodeenum::from_db(&value).ok_or(TryGetError::DbErr(DbErr::Query(
+            RuntimeErr::SqlxError(sqlx::error::Error::TypeNotFound { type_name: value }),
+        )))
+    }
+}
+
+impl ValueType for Consultationmodeenum {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        match v {
+            Value::String(Some(s)) => Consultationmodeenum::from_db(&s).ok_or(ValueTypeErr),
+            _ => Err(ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "consultationmodeenum".to_string()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::String
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Enum {
+            name: SeaRc::new(ConsultationmodeenumIden::Consultationmodeenum),
+            variants: vec![
+                SeaRc::new(ConsultationmodeenumVariants::Forward),
+                SeaRc::new(ConsultationmodeenumVariants::Backward),
+            ],
+        }
+    }
+}
+
+// По умолчанию TEXT, как у колонки clauses.value_type в миграции
+#[derive(
+    Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, EnumIter, ToSchema,
+)]
+pub enum Valuetypeenum {
+    Integer,
+    Decimal,
+    Boolean,
+    Answer,
+    #[default]
+    Text,
+}
+
+#[derive(Iden)]
+enum ValuetypeenumIden {
+    Valuetypeenum,
+}
+
+#[derive(Iden)]
+enum ValuetypeenumVariants {
+    Integer,
+    Decimal,
+    Boolean,
+    Answer,
+    Text,
+}
+
+impl Valuetypeenum {
+    fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "INTEGER" => Some(Valuetypeenum::Integer),
+            "DECIMAL" => Some(Valuetypeenum::Decimal),
+            "BOOLEAN" => Some(Valuetypeenum::Boolean),
+            "ANSWER" => Some(Valuetypeenum::Answer),
+            "TEXT" => Some(Valuetypeenum::Text),
+            _ => None,
+        }
+    }
+}
+
+impl From<Valuetypeenum> for String {
+    fn from(value_type: Valuetypeenum) -> Self {
+        match value_type {
+            Valuetypeenum::Integer => "INTEGER".to_string(),
+            Valuetypeenum::Decimal => "DECIMAL".to_string(),
+            Valuetypeenum::Boolean => "BOOLEAN".to_string(),
+            Valuetypeenum::Answer => "ANSWER".to_string(),
+            Valuetypeenum::Text => "TEXT".to_string(),
+        }
+    }
+}
+
+impl From<Valuetypeenum> for Value {
+    fn from(value_type: Valuetypeenum) -> Self {
+        let string_value: String = value_type.into();
+        Value::String(Some(Box::new(string_value)))
+    }
+}
+
+impl TryGetable for Valuetypeenum {
+    fn try_get_by<I: sea_orm::ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let value: String = res.try_get_by(index)?;
+        Valuetypeenum::from_db(&value).ok_or(TryGetError::DbErr(DbErr::Query(
+            RuntimeErr::SqlxError(sqlx::error::Error::TypeNotFound { type_name: value }),
+        )))
+    }
+}
+
+impl ValueType for Valuetypeenum {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        match v {
+            Value::String(Some(s)) => Valuetypeenum::from_db(&s).ok_or(ValueTypeErr),
+            _ => Err(ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "valuetypeenum".to_string()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::String
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Enum {
+            name: SeaRc::new(ValuetypeenumIden::Valuetypeenum),
+            variants: vec![
+                SeaRc::new(ValuetypeenumVariants::Integer),
+                SeaRc::new(ValuetypeenumVariants::Decimal),
+                SeaRc::new(ValuetypeenumVariants::Boolean),
+                SeaRc::new(ValuetypeenumVariants::Answer),
+                SeaRc::new(ValuetypeenumVariants::Text),
+            ],
+        }
+    }
+}
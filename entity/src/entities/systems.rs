@@ -7,16 +7,8 @@ use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
 use super::{
-    answers::AnswerModel,
-    attributes::AttributeModel,
-    attributesvalues::AttributeValueModel,
-    clauses::ClauseModel,
-    object_attribute_attributevalue::ObjectAttributeAttributeValueModel,
-    objects::{ObjectModel, ObjectWithAttributesValuesModel},
-    questions::{QuestionModel, QuestionWithAnswersModel},
-    rule_attribute_attributevalue::RuleAttributeAttributeValueModel,
-    rule_question_answer::RuleQuestionAnswerModel,
-    rules::{RuleModel, RuleWithClausesAndEffects},
+    objects::ObjectWithAttributesValuesModel, questions::QuestionWithAnswersModel,
+    rules::RuleWithClausesAndEffects,
 };
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, DeriveEntityModel, Eq, ToSchema)]
@@ -90,25 +82,12 @@ pub struct TestSystemModel {
     pub objects: Vec<ObjectWithAttributesValuesModel>,
 }
 
-#[derive(Deserialize, Serialize, ToSchema, Debug)]
-pub struct SystemBackupModel {
-    pub system: Model,
-    pub objects: Vec<ObjectModel>,
-    pub object_attribute_attributevalue: Vec<ObjectAttributeAttributeValueModel>,
-    pub attributes: Vec<AttributeModel>,
-    pub attributes_values: Vec<AttributeValueModel>,
-    pub rules: Vec<RuleModel>,
-    pub rule_attribute_attributevalue: Vec<RuleAttributeAttributeValueModel>,
-    pub clauses: Vec<ClauseModel>,
-    pub questions: Vec<QuestionModel>,
-    pub answers: Vec<AnswerModel>,
-    pub rule_question_answer: Vec<RuleQuestionAnswerModel>,
-}
-
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::attributes::Entity")]
     Attributes,
+    #[sea_orm(has_many = "super::consultations::Entity")]
+    Consultations,
     #[sea_orm(has_many = "super::histories::Entity")]
     Histories,
     #[sea_orm(has_many = "super::objects::Entity")]
@@ -119,6 +98,8 @@ pub enum Relation {
     Likes,
     #[sea_orm(has_many = "super::rules::Entity")]
     Rules,
+    #[sea_orm(has_many = "super::test_cases::Entity")]
+    TestCases,
     #[sea_orm(
         belongs_to = "super::users::Entity",
         from = "Column::UserId",
@@ -135,6 +116,12 @@ impl Related<super::attributes::Entity> for Entity {
     }
 }
 
+impl Related<super::consultations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Consultations.def()
+    }
+}
+
 impl Related<super::histories::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Histories.def()
@@ -165,6 +152,12 @@ impl Related<super::rules::Entity> for Entity {
     }
 }
 
+impl Related<super::test_cases::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TestCases.def()
+    }
+}
+
 impl Related<super::users::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Users.def()
@@ -30,6 +30,22 @@ pub struct Model {
     pub verification_code: Option<String>,
     #[serde(skip_deserializing)]
     pub password_reset_at: Option<NaiveDateTime>,
+    // Секрет TOTP в base32. Заполняется при подключении, работает после подтверждения кодом
+    #[serde(skip)]
+    pub totp_secret: Option<String>,
+    #[serde(skip_deserializing)]
+    pub totp_enabled: bool,
+    // Последний принятый шаг TOTP, чтобы один код нельзя было использовать дважды
+    #[serde(skip)]
+    pub totp_last_step: Option<i64>,
+    // Хэши неиспользованных кодов восстановления
+    #[serde(skip)]
+    pub totp_recovery_codes: Json,
+    // Неверные коды подряд; после TWO_FACTOR_MAX_ATTEMPTS ввод кодов блокируется до totp_locked_until
+    #[serde(skip)]
+    pub totp_failed_attempts: i32,
+    #[serde(skip)]
+    pub totp_locked_until: Option<NaiveDateTime>,
 }
 
 pub use Model as UserModel;
@@ -73,6 +89,8 @@ pub struct ResetPasswordModel {
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::consultations::Entity")]
+    Consultations,
     #[sea_orm(has_many = "super::histories::Entity")]
     Histories,
     #[sea_orm(has_many = "super::likes::Entity")]
@@ -81,6 +99,12 @@ pub enum Relation {
     Systems,
 }
 
+impl Related<super::consultations::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Consultations.def()
+    }
+}
+
 impl Related<super::histories::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Histories.def()
@@ -6,6 +6,17 @@ mod m20240705_091653_create_likes_table;
 mod m20240705_113436_update_likes;
 mod m20240705_114013_update_likes;
 mod m20240705_114453_update_likes;
+mod m20241018_120000_create_consultations_table;
+mod m20241020_120000_add_mode_to_consultations;
+mod m20241021_120000_add_explanation_to_histories;
+mod m20241022_120000_add_certainty_factor_to_rules;
+mod m20241023_120000_add_value_type_to_clauses;
+mod m20241024_120000_add_condition_to_rules;
+mod m20241025_120000_create_test_cases_table;
+mod m20241026_120000_add_answers_to_histories;
+mod m20241027_120000_create_sessions_table;
+mod m20241028_120000_create_access_tokens_table;
+mod m20241029_120000_add_two_factor_to_users;
 
 pub struct Migrator;
 
@@ -19,6 +30,17 @@ impl MigratorTrait for Migrator {
             Box::new(m20240705_113436_update_likes::Migration),
             Box::new(m20240705_114013_update_likes::Migration),
             Box::new(m20240705_114453_update_likes::Migration),
+            Box::new(m20241018_120000_create_consultations_table::Migration),
+            Box::new(m20241020_120000_add_mode_to_consultations::Migration),
+            Box::new(m20241021_120000_add_explanation_to_histories::Migration),
+            Box::new(m20241022_120000_add_certainty_factor_to_rules::Migration),
+            Box::new(m20241023_120000_add_value_type_to_clauses::Migration),
+            Box::new(m20241024_120000_add_condition_to_rules::Migration),
+            Box::new(m20241025_120000_create_test_cases_table::Migration),
+            Box::new(m20241026_120000_add_answers_to_histories::Migration),
+            Box::new(m20241027_120000_create_sessions_table::Migration),
+            Box::new(m20241028_120000_create_access_tokens_table::Migration),
+            Box::new(m20241029_120000_add_two_factor_to_users::Migration),
         ]
     }
 }
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE SEQUENCE \"public\".\"consultations_id_seq\"
+            INCREMENT 1
+            MINVALUE 1
+            MAXVALUE 2147483647
+            START 1
+            CACHE 1;
+
+            CREATE TABLE \"public\".\"consultations\" (
+            \"id\" int4 NOT NULL DEFAULT nextval('consultations_id_seq'::regclass),
+            \"system_id\" int4 NOT NULL,
+            \"user_id\" int4 NOT NULL,
+            \"answers\" json NOT NULL DEFAULT '[]'::json,
+            \"finished\" bool NOT NULL DEFAULT false,
+            \"history_id\" int4,
+            \"started_at\" timestamp(6) NOT NULL DEFAULT now(),
+            PRIMARY KEY (\"id\"),
+            CONSTRAINT \"systems_consultations_fkey\" FOREIGN KEY (\"system_id\") REFERENCES \"public\".\"systems\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION,
+            CONSTRAINT \"users_consultations_fkey\" FOREIGN KEY (\"user_id\") REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION,
+            CONSTRAINT \"histories_consultations_fkey\" FOREIGN KEY (\"history_id\") REFERENCES \"public\".\"histories\" (\"id\") ON DELETE SET NULL ON UPDATE NO ACTION
+            )
+            ;
+
+            ALTER SEQUENCE \"public\".\"consultations_id_seq\"
+            OWNED BY \"public\".\"consultations\".\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE TYPE \"public\".\"consultationmodeenum\" AS ENUM (
+            'FORWARD',
+            'BACKWARD'
+            );
+
+            ALTER TABLE \"public\".\"consultations\"
+            ADD COLUMN \"mode\" \"public\".\"consultationmodeenum\" NOT NULL DEFAULT 'FORWARD',
+            ADD COLUMN \"goal_object_id\" int4,
+            ADD COLUMN \"goal_attribute_value_id\" int4;
+
+            ALTER TABLE \"public\".\"histories\"
+            ADD COLUMN \"mode\" \"public\".\"consultationmodeenum\" NOT NULL DEFAULT 'FORWARD',
+            ADD COLUMN \"goal_object_id\" int4,
+            ADD COLUMN \"goal_attribute_value_id\" int4;
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,21 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            ALTER TABLE \"public\".\"rules\"
+            ADD COLUMN \"certainty_factor\" float8 NOT NULL DEFAULT 1.0,
+            ADD CONSTRAINT \"rules_certainty_factor_check\" CHECK (\"certainty_factor\" BETWEEN -1.0 AND 1.0);
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
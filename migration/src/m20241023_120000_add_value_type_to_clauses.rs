@@ -0,0 +1,63 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE TYPE \"public\".\"valuetypeenum\" AS ENUM (
+            'INTEGER',
+            'DECIMAL',
+            'BOOLEAN',
+            'ANSWER',
+            'TEXT'
+            );
+
+            ALTER TABLE \"public\".\"clauses\"
+            ADD COLUMN \"value_type\" \"public\".\"valuetypeenum\" NOT NULL DEFAULT 'TEXT';
+
+            UPDATE \"public\".\"clauses\" AS c
+            SET \"value_type\" = 'ANSWER', \"compared_value\" = a.\"id\"::text
+            FROM \"public\".\"answers\" AS a
+            JOIN \"public\".\"questions\" AS q ON q.\"id\" = a.\"question_id\"
+            WHERE a.\"question_id\" = c.\"question_id\"
+            AND q.\"with_chooses\"
+            AND c.\"operator\" IN ('EQUAL', 'NOT_EQUAL')
+            AND trim(a.\"body\") = trim(c.\"compared_value\");
+
+            UPDATE \"public\".\"clauses\" AS c
+            SET \"value_type\" = 'ANSWER', \"compared_value\" = a.\"id\"::text
+            FROM \"public\".\"answers\" AS a
+            JOIN \"public\".\"questions\" AS q ON q.\"id\" = a.\"question_id\"
+            WHERE c.\"value_type\" = 'TEXT'
+            AND a.\"question_id\" = c.\"question_id\"
+            AND q.\"with_chooses\"
+            AND c.\"operator\" IN ('EQUAL', 'NOT_EQUAL')
+            AND a.\"id\"::text = trim(c.\"compared_value\");
+
+            UPDATE \"public\".\"clauses\"
+            SET \"value_type\" = 'INTEGER', \"compared_value\" = trim(\"compared_value\")
+            WHERE \"value_type\" = 'TEXT'
+            AND trim(\"compared_value\") ~ '^-?[0-9]{1,18}$';
+
+            UPDATE \"public\".\"clauses\"
+            SET \"value_type\" = 'DECIMAL', \"compared_value\" = replace(trim(\"compared_value\"), ',', '.')
+            WHERE \"value_type\" = 'TEXT'
+            AND trim(\"compared_value\") ~ '^-?[0-9]+([.,][0-9]+)?$';
+
+            UPDATE \"public\".\"clauses\"
+            SET \"value_type\" = 'BOOLEAN', \"compared_value\" = lower(trim(\"compared_value\"))
+            WHERE \"value_type\" = 'TEXT'
+            AND \"operator\" IN ('EQUAL', 'NOT_EQUAL')
+            AND lower(trim(\"compared_value\")) IN ('true', 'false');
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            ALTER TABLE \"public\".\"rules\"
+            ADD COLUMN \"condition\" json;
+
+            WITH \"groups\" AS (
+                SELECT \"rule_id\", min(\"id\") AS \"first_id\",
+                json_build_object('And', json_agg(json_build_object('Clause', \"id\") ORDER BY \"id\")) AS \"node\"
+                FROM \"public\".\"clauses\"
+                GROUP BY \"rule_id\", \"logical_group\"
+            )
+            UPDATE \"public\".\"rules\" AS r
+            SET \"condition\" = g.\"node\"
+            FROM (
+                SELECT \"rule_id\", json_build_object('Or', json_agg(\"node\" ORDER BY \"first_id\")) AS \"node\"
+                FROM \"groups\"
+                GROUP BY \"rule_id\"
+            ) AS g
+            WHERE g.\"rule_id\" = r.\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
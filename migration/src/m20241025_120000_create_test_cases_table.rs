@@ -0,0 +1,40 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE SEQUENCE \"public\".\"test_cases_id_seq\"
+            INCREMENT 1
+            MINVALUE 1
+            MAXVALUE 2147483647
+            START 1
+            CACHE 1;
+
+            CREATE TABLE \"public\".\"test_cases\" (
+            \"id\" int4 NOT NULL DEFAULT nextval('test_cases_id_seq'::regclass),
+            \"system_id\" int4 NOT NULL,
+            \"name\" varchar(128) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"answers\" json NOT NULL DEFAULT '[]'::json,
+            \"expected_object_ids\" json NOT NULL DEFAULT '[]'::json,
+            \"expected_attribute_value_ids\" json NOT NULL DEFAULT '[]'::json,
+            \"created_at\" timestamp(6) NOT NULL DEFAULT now(),
+            PRIMARY KEY (\"id\"),
+            CONSTRAINT \"systems_test_cases_fkey\" FOREIGN KEY (\"system_id\") REFERENCES \"public\".\"systems\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION
+            )
+            ;
+
+            ALTER SEQUENCE \"public\".\"test_cases_id_seq\"
+            OWNED BY \"public\".\"test_cases\".\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
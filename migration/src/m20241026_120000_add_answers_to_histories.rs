@@ -0,0 +1,24 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            ALTER TABLE \"public\".\"histories\"
+            ADD COLUMN \"answers\" json;
+
+            UPDATE \"public\".\"histories\"
+            SET \"answers\" = \"explanation\"->'given_answers'
+            WHERE \"explanation\" IS NOT NULL;
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE SEQUENCE \"public\".\"sessions_id_seq\"
+            INCREMENT 1
+            MINVALUE 1
+            MAXVALUE 2147483647
+            START 1
+            CACHE 1;
+
+            CREATE TABLE \"public\".\"sessions\" (
+            \"id\" int4 NOT NULL DEFAULT nextval('sessions_id_seq'::regclass),
+            \"user_id\" int4 NOT NULL,
+            \"token_hash\" varchar(64) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"user_agent\" varchar(512) COLLATE \"pg_catalog\".\"default\",
+            \"ip\" varchar(45) COLLATE \"pg_catalog\".\"default\",
+            \"created_at\" timestamp(6) NOT NULL DEFAULT now(),
+            \"expires_at\" timestamp(6) NOT NULL,
+            PRIMARY KEY (\"id\"),
+            CONSTRAINT \"sessions_token_hash_key\" UNIQUE (\"token_hash\"),
+            CONSTRAINT \"users_sessions_fkey\" FOREIGN KEY (\"user_id\") REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION
+            )
+            ;
+
+            ALTER SEQUENCE \"public\".\"sessions_id_seq\"
+            OWNED BY \"public\".\"sessions\".\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            CREATE SEQUENCE \"public\".\"access_tokens_id_seq\"
+            INCREMENT 1
+            MINVALUE 1
+            MAXVALUE 2147483647
+            START 1
+            CACHE 1;
+
+            CREATE TABLE \"public\".\"access_tokens\" (
+            \"id\" int4 NOT NULL DEFAULT nextval('access_tokens_id_seq'::regclass),
+            \"user_id\" int4 NOT NULL,
+            \"name\" varchar(128) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"prefix\" varchar(32) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"token_hash\" varchar(256) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"scopes\" json NOT NULL DEFAULT '[]'::json,
+            \"created_at\" timestamp(6) NOT NULL DEFAULT now(),
+            \"last_used_at\" timestamp(6),
+            PRIMARY KEY (\"id\"),
+            CONSTRAINT \"access_tokens_prefix_key\" UNIQUE (\"prefix\"),
+            CONSTRAINT \"users_access_tokens_fkey\" FOREIGN KEY (\"user_id\") REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION
+            )
+            ;
+
+            ALTER SEQUENCE \"public\".\"access_tokens_id_seq\"
+            OWNED BY \"public\".\"access_tokens\".\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
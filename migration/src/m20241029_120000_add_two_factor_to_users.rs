@@ -0,0 +1,46 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        db.execute_unprepared(
+            "
+            ALTER TABLE \"public\".\"users\"
+            ADD COLUMN \"totp_secret\" varchar(64) COLLATE \"pg_catalog\".\"default\",
+            ADD COLUMN \"totp_enabled\" bool NOT NULL DEFAULT false,
+            ADD COLUMN \"totp_last_step\" int8,
+            ADD COLUMN \"totp_recovery_codes\" json NOT NULL DEFAULT '[]'::json,
+            ADD COLUMN \"totp_failed_attempts\" int4 NOT NULL DEFAULT 0,
+            ADD COLUMN \"totp_locked_until\" timestamp(6);
+
+            CREATE SEQUENCE \"public\".\"two_factor_challenges_id_seq\"
+            INCREMENT 1
+            MINVALUE 1
+            MAXVALUE 2147483647
+            START 1
+            CACHE 1;
+
+            CREATE TABLE \"public\".\"two_factor_challenges\" (
+            \"id\" int4 NOT NULL DEFAULT nextval('two_factor_challenges_id_seq'::regclass),
+            \"user_id\" int4 NOT NULL,
+            \"token\" varchar(64) COLLATE \"pg_catalog\".\"default\" NOT NULL,
+            \"expires_at\" timestamp(6) NOT NULL,
+            PRIMARY KEY (\"id\"),
+            CONSTRAINT \"two_factor_challenges_token_key\" UNIQUE (\"token\"),
+            CONSTRAINT \"users_two_factor_challenges_fkey\" FOREIGN KEY (\"user_id\") REFERENCES \"public\".\"users\" (\"id\") ON DELETE CASCADE ON UPDATE NO ACTION
+            )
+            ;
+
+            ALTER SEQUENCE \"public\".\"two_factor_challenges_id_seq\"
+            OWNED BY \"public\".\"two_factor_challenges\".\"id\";
+            ",
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,18 +1,44 @@
+use crate::utils::clock::Clock;
+use chrono::DateTime;
 use tower_cookies::Key;
 
+// Ключи шифрования резервных копий. Новые копии шифруются ключом current_key_id,
+// остальные ключи нужны только для восстановления копий, сделанных до ротации.
+// legacy_nonce - общий nonce копий, записанных до появления конверта с ключом
+#[derive(Debug, Clone)]
+pub struct BackupKeyring {
+    pub current_key_id: String,
+    pub keys: Vec<(String, String)>,
+    pub legacy_nonce: Option<String>,
+}
+
+impl BackupKeyring {
+    pub fn current_key(&self) -> &str {
+        self.key(&self.current_key_id)
+            .expect("current backup key must be in the keyring")
+    }
+
+    pub fn key(&self, key_id: &str) -> Option<&str> {
+        self.keys
+            .iter()
+            .find(|(id, _)| id == key_id)
+            .map(|(_, key)| key.as_str())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Config {
     pub database_url: String,
     pub frontend_origin: String,
 
     pub cookie_key: Key,
-    pub crypto_key: String,
-    pub nonce_key: String,
+    pub backup_keyring: BackupKeyring,
     pub smtp_host: String,
     pub smtp_port: u16,
     pub smtp_user: String,
     pub smtp_pass: String,
     pub smtp_from: String,
+    pub clock: Clock,
 }
 
 impl Config {
@@ -23,7 +49,40 @@ impl Config {
 
         let cookie_key = std::env::var("COOKIE_KEY").expect("COOKIE_KEY must be set");
         let crypto_key = std::env::var("CRYPTO_KEY").expect("CRYPTO_KEY must be set");
-        let nonce_key = std::env::var("NONCE_KEY").expect("NONCE_KEY must be set");
+        let crypto_key_id = std::env::var("CRYPTO_KEY_ID").unwrap_or("default".to_string());
+        // Старые ключи в формате "id:ключ,id:ключ"
+        let crypto_keyring = std::env::var("CRYPTO_KEYRING").unwrap_or_default();
+        let nonce_key = std::env::var("NONCE_KEY").ok();
+
+        let mut keys = vec![(crypto_key_id.clone(), crypto_key)];
+        crypto_keyring
+            .split(',')
+            .filter(|entry| !entry.trim().is_empty())
+            .for_each(|entry| {
+                let (key_id, key) = entry
+                    .trim()
+                    .split_once(':')
+                    .expect("CRYPTO_KEYRING entries must look like id:key");
+                assert!(
+                    !keys.iter().any(|(id, _)| id == key_id),
+                    "CRYPTO_KEYRING has a duplicate key id {}",
+                    key_id
+                );
+                keys.push((key_id.to_string(), key.to_string()));
+            });
+        assert!(
+            keys.iter().all(|(_, key)| key.len() == 32),
+            "backup keys must be 32 bytes long"
+        );
+        assert!(
+            keys.iter()
+                .all(|(id, _)| !id.is_empty() && id.len() <= u8::MAX as usize),
+            "backup key ids must be 1-255 bytes long"
+        );
+        assert!(
+            nonce_key.as_ref().is_none_or(|nonce| nonce.len() == 12),
+            "NONCE_KEY must be 12 bytes long"
+        );
 
         let smtp_host = std::env::var("SMTP_HOST").expect("SMTP_HOST must be set");
         let smtp_port = std::env::var("SMTP_PORT").expect("SMTP_PORT must be set");
@@ -31,17 +90,34 @@ impl Config {
         let smtp_pass = std::env::var("SMTP_PASS").expect("SMTP_PASS must be set");
         let smtp_from = std::env::var("SMTP_FROM").expect("SMTP_FROM must be set");
 
+        // Замороженные часы (unix-время в секундах) только для отладочной сборки,
+        // в релизе коды 2FA всегда считаются от системного времени
+        let clock = match std::env::var("FIXED_CLOCK").ok() {
+            Some(timestamp) if cfg!(debug_assertions) => Clock::Fixed(
+                timestamp
+                    .parse::<i64>()
+                    .ok()
+                    .and_then(|timestamp| DateTime::from_timestamp(timestamp, 0))
+                    .expect("FIXED_CLOCK must be a unix timestamp"),
+            ),
+            _ => Clock::System,
+        };
+
         Config {
             database_url,
             frontend_origin,
             cookie_key: Key::from(cookie_key.as_bytes()),
-            crypto_key,
-            nonce_key,
+            backup_keyring: BackupKeyring {
+                current_key_id: crypto_key_id,
+                keys,
+                legacy_nonce: nonce_key,
+            },
             smtp_host,
             smtp_pass,
             smtp_user,
             smtp_port: smtp_port.parse::<u16>().unwrap(),
             smtp_from,
+            clock,
         }
     }
 }
@@ -1,47 +1,24 @@
-use axum::http::Method;
-
-pub struct UriInfo<'a> {
-    pub uri: &'a str,
-    pub method: Method,
-}
-
 pub const COOKIE_NAME: &str = "session_id";
 pub const IMAGE_DIR: &str = "./images";
-pub const URI_WITHOUT_AUTH: [UriInfo; 9] = [
-    UriInfo {
-        uri: r"\/api\/v1\/user\/login",
-        method: Method::POST,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/systems",
-        method: Method::GET,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/user\/logout",
-        method: Method::POST,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/user\/registration",
-        method: Method::POST,
-    },
-    UriInfo {
-        uri: r"\/swagger-ui\/api-docs\/openapi.json",
-        method: Method::GET,
-    },
-    UriInfo {
-        uri: r"/api/v1/systems/\d+/test",
-        method: Method::GET,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/user\/verifyemail\/[a-zA-Z0-9]+",
-        method: Method::POST,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/user\/forgotpassword+",
-        method: Method::POST,
-    },
-    UriInfo {
-        uri: r"\/api\/v1\/user\/resetpassword\/[a-zA-Z0-9]+",
-        method: Method::POST,
-    },
-];
+pub const BACKUP_PASSPHRASE_HEADER: &str = "x-backup-passphrase";
+pub const MIN_BACKUP_PASSPHRASE_LEN: usize = 8;
+// Предел для старых копий, зашифрованных целиком: их можно открыть, только прочитав в память
+pub const MAX_BACKUP_SIZE: usize = 64 * 1024 * 1024;
+// Потоковая копия в память целиком не читается, ограничен только объем данных в ней
+pub const MAX_STREAMED_BACKUP_SIZE: u64 = 1024 * 1024 * 1024;
+pub const BACKUP_CHUNK_SIZE: usize = 64 * 1024;
+// Сколько частей копии может ждать в канале между кодированием и сетью
+pub const BACKUP_STREAM_BUFFER: usize = 16;
+pub const BACKUP_FILE_EXTENSION: &str = "esbk";
+pub const SESSION_LIFETIME_DAYS: i64 = 2;
+pub const SESSION_TOKEN_LEN: usize = 64;
+pub const ACCESS_TOKEN_PREFIX: &str = "esat";
+pub const ACCESS_TOKEN_ID_LEN: usize = 12;
+pub const ACCESS_TOKEN_SECRET_LEN: usize = 40;
+pub const TOTP_ISSUER: &str = "ExpertSystem";
+pub const TOTP_SECRET_LEN: usize = 20;
+pub const TOTP_RECOVERY_CODES: usize = 10;
+pub const TOTP_RECOVERY_CODE_LEN: usize = 10;
+pub const TWO_FACTOR_CHALLENGE_MINUTES: i64 = 5;
+pub const TWO_FACTOR_MAX_ATTEMPTS: i32 = 5;
+pub const TWO_FACTOR_LOCK_MINUTES: i64 = 15;
@@ -1,11 +1,11 @@
 #[cfg(not(debug_assertions))]
 use axum::routing::get;
-use axum::{middleware as axum_middleware, Router};
+use axum::Router;
 use config::Config;
-use constants::IMAGE_DIR;
+use constants::{BACKUP_PASSPHRASE_HEADER, IMAGE_DIR};
 use dotenv::dotenv;
 use http::{header, HeaderName, HeaderValue, Method};
-use middleware::{auth, handler_404};
+use middleware::handler_404;
 
 use migration::{Migrator, MigratorTrait};
 use routes::{
@@ -87,11 +87,13 @@ async fn main() {
         ])
         .allow_headers([
             header::CONTENT_TYPE,
+            header::AUTHORIZATION,
             header::SET_COOKIE,
             header::ACCEPT,
             header::X_CONTENT_TYPE_OPTIONS,
+            HeaderName::from_static(BACKUP_PASSPHRASE_HEADER),
         ])
-        .expose_headers([page_header])
+        .expose_headers([page_header, header::CONTENT_DISPOSITION])
         .allow_credentials(true);
 
     let mut app = Router::new()
@@ -119,7 +121,6 @@ async fn main() {
                 .nest("/rule-question-answer", rule_question_answer_routes())
                 .nest("/likes", like_routes()),
         )
-        .layer(axum_middleware::from_fn_with_state(state.clone(), auth))
         .nest_service("/api/v1/images", ServeDir::new(IMAGE_DIR))
         .with_state(state)
         .layer(CookieManagerLayer::new())
@@ -139,5 +140,10 @@ async fn main() {
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8000));
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
 }
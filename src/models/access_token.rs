@@ -0,0 +1,51 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Токен дает только права своих областей, токен без областей не создается
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub enum TokenScope {
+    // Только GET-запросы
+    #[serde(rename = "read-only")]
+    ReadOnly,
+    // Любые запросы к системам и их содержимому
+    #[serde(rename = "systems:write")]
+    SystemsWrite,
+    // Выгрузка и восстановление резервных копий
+    #[serde(rename = "backup")]
+    Backup,
+}
+
+impl TokenScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read-only",
+            TokenScope::SystemsWrite => "systems:write",
+            TokenScope::Backup => "backup",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewAccessTokenModel {
+    pub name: String,
+    #[serde(default)]
+    pub scopes: Vec<TokenScope>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AccessTokenInfoModel {
+    pub id: i32,
+    pub name: String,
+    pub prefix: String,
+    pub scopes: Vec<TokenScope>,
+    pub created_at: NaiveDateTime,
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+// Сам токен возвращается только при создании, в базе хранится его хэш
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreatedAccessTokenModel {
+    pub token: String,
+    pub access_token: AccessTokenInfoModel,
+}
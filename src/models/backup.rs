@@ -0,0 +1,9 @@
+use utoipa::ToSchema;
+
+// Форма восстановления для документации, сам файл читается из запроса потоком
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct BackupUploadModel {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+}
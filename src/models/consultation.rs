@@ -0,0 +1,15 @@
+use entity::{consultations::ConsultationModel, questions::QuestionWithAnswersModel};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::inference::{GoalStatus, InferenceResultModel};
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ConsultationStepModel {
+    pub consultation: ConsultationModel,
+    pub question: Option<QuestionWithAnswersModel>,
+    pub goal_status: Option<GoalStatus>,
+    pub answered: usize,
+    pub total: usize,
+    pub result: InferenceResultModel,
+}
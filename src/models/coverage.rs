@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleCoverageModel {
+    pub rule_id: i32,
+    pub fire_count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AnswerCountModel {
+    pub answer_id: Option<i32>,
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct QuestionCoverageModel {
+    pub question_id: i32,
+    pub answered: usize,
+    pub answers: Vec<AnswerCountModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct CoverageModel {
+    pub replayed_histories: usize,
+    pub skipped_histories: usize,
+    pub rules: Vec<RuleCoverageModel>,
+    pub questions: Vec<QuestionCoverageModel>,
+    pub never_fired_rule_ids: Vec<i32>,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DecisionNodeModel {
+    pub id: usize,
+    pub question_id: Option<i32>,
+    pub label: String,
+    pub object_ids: Vec<i32>,
+    pub attribute_value_ids: Vec<i32>,
+    pub dead_end: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DecisionEdgeModel {
+    pub from_node_id: usize,
+    pub to_node_id: usize,
+    pub answer_id: Option<i32>,
+    pub value: String,
+    pub label: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DecisionTreeModel {
+    pub root_node_id: usize,
+    pub nodes: Vec<DecisionNodeModel>,
+    pub edges: Vec<DecisionEdgeModel>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DecisionTreeFormat {
+    #[default]
+    Json,
+    Dot,
+}
@@ -0,0 +1,18 @@
+use entity::rules::NewRuleWithClausesAndEffects;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InducedAttributeModel {
+    pub attribute_id: i32,
+    pub information_gain: f64,
+    pub question_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InductionModel {
+    pub attributes: Vec<InducedAttributeModel>,
+    pub rules: Vec<NewRuleWithClausesAndEffects>,
+    pub unmatched_attribute_ids: Vec<i32>,
+    pub indistinguishable_object_ids: Vec<Vec<i32>>,
+}
@@ -0,0 +1,102 @@
+use entity::sea_orm_active_enums::{Operatorenum, Valuetypeenum};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GivenAnswerModel {
+    pub question_id: i32,
+    pub answer_id: Option<i32>,
+    pub value: Option<String>,
+    pub certainty_factor: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub enum GoalStatus {
+    Open,
+    Confirmed,
+    RuledOut,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DerivedAnswerModel {
+    pub question_id: i32,
+    pub answer_id: i32,
+    pub rule_id: i32,
+    pub certainty_factor: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct DerivedAttributeValueModel {
+    pub attribute_id: i32,
+    pub attribute_value_id: i32,
+    pub rule_id: i32,
+    pub certainty_factor: f64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ObjectScoreModel {
+    pub object_id: i32,
+    pub name: String,
+    pub matched: usize,
+    pub total: usize,
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InferenceResultModel {
+    pub fired_rules: Vec<i32>,
+    pub derived_answers: Vec<DerivedAnswerModel>,
+    pub attribute_values: Vec<DerivedAttributeValueModel>,
+    pub objects: Vec<ObjectScoreModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SatisfiedClauseModel {
+    pub clause_id: i32,
+    pub question_id: i32,
+    pub operator: Operatorenum,
+    pub compared_value: String,
+    pub value_type: Valuetypeenum,
+    pub logical_group: String,
+    pub answer_id: Option<i32>,
+    pub value: String,
+    pub certainty_factor: f64,
+    pub negated: bool,
+    pub derived_by_rule_id: Option<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleTraceModel {
+    pub rule_id: i32,
+    pub certainty_factor: f64,
+    pub premise_certainty_factor: f64,
+    pub satisfied_clauses: Vec<SatisfiedClauseModel>,
+    pub derived_answers: Vec<DerivedAnswerModel>,
+    pub attribute_values: Vec<DerivedAttributeValueModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ObjectValueTraceModel {
+    pub attribute_id: i32,
+    pub attribute_value_id: i32,
+    pub matched: bool,
+    pub rule_id: Option<i32>,
+    pub certainty_factor: Option<f64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ObjectTraceModel {
+    pub object_id: i32,
+    pub name: String,
+    pub matched: usize,
+    pub total: usize,
+    pub percent: u8,
+    pub values: Vec<ObjectValueTraceModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ExplanationModel {
+    pub given_answers: Vec<GivenAnswerModel>,
+    pub rules: Vec<RuleTraceModel>,
+    pub objects: Vec<ObjectTraceModel>,
+}
@@ -0,0 +1,132 @@
+use entity::{
+    rules::NewConditionNode,
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+pub const INTERCHANGE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum InterchangeFormat {
+    #[default]
+    Json,
+    Yaml,
+}
+
+// Переносимое описание системы. Вместо id используются ключи, уникальные внутри документа:
+// ответы и значения атрибутов ссылаются на вопрос и атрибут через "ключ/ключ ответа",
+// остальные сущности ссылаются друг на друга по этим ключам
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SystemDocumentModel {
+    pub format_version: u32,
+    pub name: String,
+    pub about: Option<String>,
+    #[serde(default)]
+    pub private: bool,
+    #[serde(default)]
+    pub questions: Vec<QuestionDocumentModel>,
+    #[serde(default)]
+    pub attributes: Vec<AttributeDocumentModel>,
+    #[serde(default)]
+    pub objects: Vec<ObjectDocumentModel>,
+    #[serde(default)]
+    pub rules: Vec<RuleDocumentModel>,
+    #[serde(default)]
+    pub test_cases: Vec<TestCaseDocumentModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct QuestionDocumentModel {
+    pub key: String,
+    pub body: String,
+    pub with_chooses: bool,
+    #[serde(default)]
+    pub answers: Vec<AnswerDocumentModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AnswerDocumentModel {
+    pub key: String,
+    pub body: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AttributeDocumentModel {
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub values: Vec<AttributeValueDocumentModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AttributeValueDocumentModel {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ObjectDocumentModel {
+    pub key: String,
+    pub name: String,
+    #[serde(default)]
+    pub attribute_values: Vec<String>,
+}
+
+fn default_certainty_factor() -> f64 {
+    1.0
+}
+
+// Листья condition - индексы в clauses, как при создании правила. Дерево хранится как Value:
+// serde_yaml записывает варианты enum тегами, а в документе они должны выглядеть как в JSON
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RuleDocumentModel {
+    pub attribute_rule: bool,
+    #[serde(default = "default_certainty_factor")]
+    pub certainty_factor: f64,
+    #[serde(default)]
+    #[schema(value_type = Option<NewConditionNode>)]
+    pub condition: Option<Value>,
+    #[serde(default)]
+    pub clauses: Vec<ClauseDocumentModel>,
+    #[serde(default)]
+    pub answers: Vec<String>,
+    #[serde(default)]
+    pub attribute_values: Vec<String>,
+}
+
+fn default_logical_group() -> String {
+    "1".to_string()
+}
+
+// Для value_type = Answer в value записывается ключ ответа
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ClauseDocumentModel {
+    pub question: String,
+    pub operator: Operatorenum,
+    pub value_type: Valuetypeenum,
+    pub value: String,
+    #[serde(default = "default_logical_group")]
+    pub logical_group: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TestCaseDocumentModel {
+    pub name: String,
+    #[serde(default)]
+    pub answers: Vec<GivenAnswerDocumentModel>,
+    #[serde(default)]
+    pub expected_objects: Vec<String>,
+    #[serde(default)]
+    pub expected_attribute_values: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct GivenAnswerDocumentModel {
+    pub question: String,
+    pub answer: Option<String>,
+    pub value: Option<String>,
+    pub certainty_factor: Option<f64>,
+}
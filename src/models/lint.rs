@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub enum LintIssueCode {
+    UnusedQuestion,
+    ForeignQuestion,
+    ForeignAnswer,
+    UnusedAttributeValue,
+    EmptyObject,
+    ContradictoryRules,
+    UnreachableRule,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct LintIssueModel {
+    pub code: LintIssueCode,
+    pub message: String,
+    pub rule_ids: Vec<i32>,
+    pub clause_id: Option<i32>,
+    pub question_id: Option<i32>,
+    pub answer_id: Option<i32>,
+    pub attribute_value_id: Option<i32>,
+    pub object_id: Option<i32>,
+}
+
+impl LintIssueModel {
+    pub fn new(code: LintIssueCode, message: &str) -> Self {
+        LintIssueModel {
+            code,
+            message: message.to_string(),
+            rule_ids: Vec::new(),
+            clause_id: None,
+            question_id: None,
+            answer_id: None,
+            attribute_value_id: None,
+            object_id: None,
+        }
+    }
+}
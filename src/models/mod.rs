@@ -1 +1,13 @@
+pub mod access_token;
+pub mod backup;
+pub mod consultation;
+pub mod coverage;
+pub mod decision_tree;
 pub mod email;
+pub mod induction;
+pub mod inference;
+pub mod interchange;
+pub mod lint;
+pub mod session;
+pub mod test_case;
+pub mod two_factor;
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::inference::{GivenAnswerModel, ObjectScoreModel};
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewTestCaseModel {
+    pub name: String,
+    pub answers: Vec<GivenAnswerModel>,
+    pub expected_object_ids: Vec<i32>,
+    pub expected_attribute_value_ids: Vec<i32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TestCaseResultModel {
+    pub test_case_id: i32,
+    pub name: String,
+    pub passed: bool,
+    pub missing_object_ids: Vec<i32>,
+    pub unexpected_object_ids: Vec<i32>,
+    pub missing_attribute_value_ids: Vec<i32>,
+    pub unexpected_attribute_value_ids: Vec<i32>,
+    pub objects: Vec<ObjectScoreModel>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TestRunModel {
+    pub passed: usize,
+    pub failed: usize,
+    pub results: Vec<TestCaseResultModel>,
+}
@@ -0,0 +1,54 @@
+use chrono::NaiveDateTime;
+use entity::users::Model as UserModel;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Ответ на вход с паролем, когда у пользователя включена 2FA: сессия еще не создана,
+// вход завершается кодом из приложения или кодом восстановления
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorChallengeModel {
+    pub two_factor_required: bool,
+    pub challenge: String,
+    pub expires_at: NaiveDateTime,
+}
+
+pub enum LoginResultModel {
+    Authorized(Box<UserModel>),
+    TwoFactorRequired(TwoFactorChallengeModel),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorLoginModel {
+    pub challenge: String,
+    pub code: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorPasswordModel {
+    pub password: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorCodeModel {
+    pub code: String,
+}
+
+// Отключение 2FA и выпуск новых кодов восстановления требуют и пароль, и код
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorVerifyModel {
+    pub password: String,
+    pub code: String,
+}
+
+// Секрет и коды восстановления показываются один раз, при подключении
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct TwoFactorEnrollmentModel {
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryCodesModel {
+    pub recovery_codes: Vec<String>,
+}
@@ -1,3 +1,4 @@
+use crate::models::{decision_tree::DecisionTreeFormat, interchange::InterchangeFormat};
 use serde::Deserialize;
 use utoipa::IntoParams;
 
@@ -64,3 +65,13 @@ pub struct RuleListPagination {
 pub struct LikeListPagination {
     pub user_id: i32,
 }
+
+#[derive(Deserialize, IntoParams)]
+pub struct DecisionTreeQuery {
+    pub format: Option<DecisionTreeFormat>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct InterchangeQuery {
+    pub format: Option<InterchangeFormat>,
+}
@@ -4,6 +4,10 @@ use crate::{
     services::answer::{
         create_answer, get_answers, multiple_delete_answers, multiple_update_answers,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -27,15 +31,26 @@ use entity::answers::{AnswerModel, UpdateAnswerModel};
             example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Answers", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn answer_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(answer_info): Json<Vec<AnswerModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = answer_info
+        .iter()
+        .map(|answer| Resource::Question(answer.question_id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_answer(&state.db_sea, answer_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -60,11 +75,12 @@ pub async fn answer_create(
     params(
         AnswerListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn answer_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<AnswerListPagination>,
 ) -> impl IntoResponse {
     match get_answers(&state.db_sea, pagination.question_id).await {
@@ -88,15 +104,23 @@ pub async fn answer_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Answers", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Answers not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn answer_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(answer_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = answer_info.iter().copied().map(Resource::Answer).collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_answers(&state.db_sea, answer_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -118,15 +142,26 @@ pub async fn answer_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update Answers", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Answers not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn answer_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(answer_info): Json<Vec<UpdateAnswerModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = answer_info
+        .iter()
+        .map(|answer| Resource::Answer(answer.id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_answers(&state.db_sea, answer_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -4,6 +4,10 @@ use crate::{
     services::attribute::{
         create_attributes, get_attributes, multiple_delete_attributes, multiple_update_attributes,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -26,15 +30,26 @@ use entity::attributes::{AttributeWithAttributeValuesModel, NewAttributeWithAttr
         (status = 401, description = "Unauthorized to create Attributes and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Attributes and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_info): Json<Vec<NewAttributeWithAttributeValuesModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_info
+        .iter()
+        .map(|attribute| Resource::System(attribute.system_id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_attributes(&state.db_sea, attribute_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -58,11 +73,12 @@ pub async fn attribute_create(
     params(
         AttributeListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<AttributeListPagination>,
 ) -> impl IntoResponse {
     match get_attributes(&state.db_sea, pagination.system_id).await {
@@ -85,15 +101,27 @@ pub async fn attribute_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Attributes and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Answers not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_info
+        .iter()
+        .copied()
+        .map(Resource::Attribute)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_attributes(&state.db_sea, attribute_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -114,15 +142,26 @@ pub async fn attribute_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update Attributes and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Attributes and their dependences not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_info): Json<Vec<UpdateAttributeModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_info
+        .iter()
+        .map(|attribute| Resource::Attribute(attribute.id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_attributes(&state.db_sea, attribute_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -5,6 +5,10 @@ use crate::{
         create_attributes_values, get_attribute_values, multiple_delete_attributes_values,
         multiple_update_attributes_values,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -27,15 +31,26 @@ use entity::attributesvalues::{AttributeValueModel, UpdateAttributeValueModel};
         (status = 401, description = "Unauthorized to create AttributeValues", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create AttributeValues", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_value_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_value_info): Json<Vec<AttributeValueModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_value_info
+        .iter()
+        .map(|attribute_value| Resource::Attribute(attribute_value.attribute_id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_attributes_values(&state.db_sea, attribute_value_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -59,11 +74,12 @@ pub async fn attribute_value_create(
     params(
         AttributeValueListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_value_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<AttributeValueListPagination>,
 ) -> impl IntoResponse {
     match get_attribute_values(&state.db_sea, pagination.attribute_id).await {
@@ -86,15 +102,27 @@ pub async fn attribute_value_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete AttributeValues", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "AttributeValues not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_value_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_value_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_value_info
+        .iter()
+        .copied()
+        .map(Resource::AttributeValue)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_attributes_values(&state.db_sea, attribute_value_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -115,14 +143,25 @@ pub async fn attribute_value_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update AttributeValues", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "AttributeValues not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 pub async fn attribute_value_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_value_info): Json<Vec<UpdateAttributeValueModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_value_info
+        .iter()
+        .map(|attribute_value| Resource::AttributeValue(attribute_value.id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_attributes_values(&state.db_sea, attribute_value_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -1,9 +1,15 @@
+use std::iter;
+
 use crate::{
     error::CustomErrors,
     pagination::ClauseListPagination,
     services::clause::{
         create_clauses, get_clauses, multiple_delete_clauses, multiple_update_clauses,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 
@@ -27,15 +33,31 @@ use entity::clauses::{ClauseModel, UpdateClauseModel};
         (status = 401, description = "Unauthorized to create Clauses", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Clauses", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn clause_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(clause_info): Json<Vec<ClauseModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = clause_info
+        .iter()
+        .flat_map(|clause| {
+            [
+                Resource::Rule(clause.rule_id),
+                Resource::Question(clause.question_id),
+            ]
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_clauses(&state.db_sea, clause_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -59,11 +81,12 @@ pub async fn clause_create(
     params(
         ClauseListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn clause_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<ClauseListPagination>,
 ) -> impl IntoResponse {
     match get_clauses(&state.db_sea, pagination.rule_id).await {
@@ -86,15 +109,23 @@ pub async fn clause_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Clauses", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Clauses not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn clause_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(clause_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = clause_info.iter().copied().map(Resource::Clause).collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_clauses(&state.db_sea, clause_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -115,15 +146,29 @@ pub async fn clause_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update Clauses", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Clauses not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn clause_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(clause_info): Json<Vec<UpdateClauseModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = clause_info
+        .iter()
+        .flat_map(|clause| {
+            iter::once(Resource::Clause(clause.id))
+                .chain(clause.question_id.map(Resource::Question))
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_clauses(&state.db_sea, clause_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
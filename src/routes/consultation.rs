@@ -0,0 +1,157 @@
+use crate::{
+    error::CustomErrors,
+    models::{consultation::ConsultationStepModel, inference::GivenAnswerModel},
+    services::consultation::{
+        answer_consultation, create_consultation, finish_consultation, get_consultation,
+    },
+    utils::auth::CurrentUser,
+    AppState,
+};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use entity::consultations::NewConsultationModel;
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/consultations",
+    context_path ="/api/v1",
+    request_body(content = Option<NewConsultationModel>, description = "Consultation mode and goal, forward chaining by default"),
+    responses(
+        (status = 200, description = "Consultation create successfully", body = ConsultationStepModel),
+        (status = 401, description = "Unauthorized to create Consultation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to consult a private System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
+        (status = 404, description = "System not found")
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn consultation_create(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    consultation_info: Option<Json<NewConsultationModel>>,
+) -> impl IntoResponse {
+    let consultation_info = consultation_info
+        .map(|Json(consultation_info)| consultation_info)
+        .unwrap_or_default();
+
+    create_consultation(&state.db_sea, system_id, user.id, consultation_info)
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/consultations/{consultation_id}",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Current Consultation step", body = ConsultationStepModel),
+        (status = 401, description = "Unauthorized to retrive Consultation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 404, description = "Consultation not found")
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        ("consultation_id" = u32, Path, description = "Consultation database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn consultation_retrieve(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((system_id, consultation_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    get_consultation(&state.db_sea, system_id, consultation_id, user.id)
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/consultations/{consultation_id}/answers",
+    context_path ="/api/v1",
+    request_body = GivenAnswerModel,
+    responses(
+        (status = 200, description = "Answer accepted, next Consultation step", body = ConsultationStepModel),
+        (status = 401, description = "Unauthorized to answer Consultation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 404, description = "Consultation, Question or Answer not found"),
+        (status = 409, description = "Consultation already finished", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::CONFLICT,
+            error: "Консультация уже завершена".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        ("consultation_id" = u32, Path, description = "Consultation database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn consultation_answer(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((system_id, consultation_id)): Path<(i32, i32)>,
+    Json(answer): Json<GivenAnswerModel>,
+) -> impl IntoResponse {
+    answer_consultation(&state.db_sea, system_id, consultation_id, user.id, answer)
+        .await
+        .map(Json)
+}
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/consultations/{consultation_id}/finish",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Consultation finished and saved to History", body = ConsultationStepModel),
+        (status = 401, description = "Unauthorized to finish Consultation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 404, description = "Consultation not found")
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        ("consultation_id" = u32, Path, description = "Consultation database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn consultation_finish(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((system_id, consultation_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    finish_consultation(&state.db_sea, system_id, consultation_id, user.id)
+        .await
+        .map(Json)
+}
+
+pub fn consultation_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(consultation_create))
+        .route("/:consultation_id", get(consultation_retrieve))
+        .route("/:consultation_id/answers", post(consultation_answer))
+        .route("/:consultation_id/finish", post(consultation_finish))
+}
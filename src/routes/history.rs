@@ -1,7 +1,9 @@
 use crate::{
     error::CustomErrors,
+    models::inference::ExplanationModel,
     pagination::HistoryListPagination,
-    services::history::{create_history, delete_history, get_histories},
+    services::history::{create_history, delete_history, get_histories, get_history_explanation},
+    utils::auth::CurrentUser,
     AppState,
 };
 use axum::{
@@ -9,7 +11,7 @@ use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::IntoResponse,
-    routing::{delete, post},
+    routing::{delete, get, post},
     Json, Router,
 };
 use entity::histories::{HistoryModel, HistoryWithSystem};
@@ -24,22 +26,25 @@ use entity::histories::{HistoryModel, HistoryWithSystem};
         (status = 401, description = "Unauthorized to create Histories", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
-        }))
+        })),
+        (status = 403, description = "Forbidden to create History for a private System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
+        (status = 404, description = "System not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
+#[deprecated(note = "историю записывает завершение консультации")]
 pub async fn history_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(history_info): Json<HistoryModel>,
 ) -> impl IntoResponse {
-    match create_history(&state.db_sea, history_info).await {
-        Ok(result) => Ok(Json(result)),
-        Err(err) => Err(CustomErrors::SeaORMError {
-            error: err,
-            message: None,
-        }),
-    }
+    create_history(&state.db_sea, user.id, history_info)
+        .await
+        .map(Json)
 }
 
 #[utoipa::path(
@@ -56,11 +61,12 @@ pub async fn history_create(
     params(
         HistoryListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn history_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<HistoryListPagination>,
 ) -> impl IntoResponse {
     match get_histories(&state.db_sea, pagination.system, pagination.user).await {
@@ -87,14 +93,15 @@ pub async fn history_list(
     params(
         ("id" = i32, Path, description = "History database id")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn history_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Path(history_id): Path<i32>,
 ) -> impl IntoResponse {
-    match delete_history(&state.db_sea, history_id).await {
+    match delete_history(&state.db_sea, user.id, history_id).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
             error: err,
@@ -103,8 +110,43 @@ pub async fn history_delete(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/histories/{id}/explanation",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "How the History results were reached", body = ExplanationModel),
+        (status = 401, description = "Unauthorized to retrive History explanation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to retrive another user's History explanation", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
+        (status = 404, description = "History not found")
+    ),
+    params(
+        ("id" = i32, Path, description = "History database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn history_explanation(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(history_id): Path<i32>,
+) -> impl IntoResponse {
+    get_history_explanation(&state.db_sea, user.id, history_id)
+        .await
+        .map(Json)
+}
+
+// POST /histories оставлен для прежнего фронтенда
+#[allow(deprecated)]
 pub fn history_routes() -> Router<AppState> {
     Router::new()
         .route("/", post(history_create).get(history_list))
         .route("/:system_id", delete(history_delete))
+        .route("/:history_id/explanation", get(history_explanation))
 }
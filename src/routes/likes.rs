@@ -2,6 +2,7 @@ use crate::{
     error::CustomErrors,
     pagination::LikeListPagination,
     services::likes::{create_like, delete_like, get_likes},
+    utils::auth::CurrentUser,
     AppState,
 };
 
@@ -27,14 +28,15 @@ use entity::likes::LikesModel;
             error: "Not authorized".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn like_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(like_info): Json<LikesModel>,
 ) -> impl IntoResponse {
-    match create_like(&state.db_sea, like_info).await {
+    match create_like(&state.db_sea, user.id, like_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
             error: err,
@@ -58,11 +60,12 @@ pub async fn like_create(
     params(
         LikeListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn like_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<LikeListPagination>,
 ) -> impl IntoResponse {
     match get_likes(&state.db_sea, pagination.user_id).await {
@@ -87,14 +90,15 @@ pub async fn like_list(
         })),
         (status = 404, description = "Like not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn like_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Path(like_id): Path<i32>,
 ) -> impl IntoResponse {
-    match delete_like(&state.db_sea, like_id).await {
+    match delete_like(&state.db_sea, user.id, like_id).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
             error: err,
@@ -2,6 +2,7 @@ pub mod answer;
 pub mod attribute;
 pub mod attribute_value;
 pub mod clause;
+pub mod consultation;
 pub mod history;
 pub mod likes;
 pub mod object;
@@ -11,4 +12,5 @@ pub mod rule;
 pub mod rule_attribute_attributevalue;
 pub mod rule_question_answer;
 pub mod system;
+pub mod test_case;
 pub mod user;
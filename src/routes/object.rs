@@ -1,9 +1,15 @@
+use std::iter;
+
 use crate::{
     error::CustomErrors,
     pagination::ObjectListPagination,
     services::object::{
         create_objects, get_objects, multiple_delete_objects, multiple_update_objects,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -26,15 +32,37 @@ use entity::objects::{NewObjectWithAttributesValueIdsModel, UpdateObjectModel, O
         (status = 401, description = "Unauthorized to create Objects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Objects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn object_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(object_info): Json<Vec<NewObjectWithAttributesValueIdsModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = object_info
+        .iter()
+        .flat_map(|object| {
+            let links = object
+                .object_attribute_attributevalue_ids
+                .iter()
+                .flat_map(|ids| {
+                    [
+                        Resource::Attribute(ids.attribute_id),
+                        Resource::AttributeValue(ids.attribute_value_id),
+                    ]
+                });
+            iter::once(Resource::System(object.system_id)).chain(links)
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_objects(&state.db_sea, object_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -58,11 +86,12 @@ pub async fn object_create(
     params(
         ObjectListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn object_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<ObjectListPagination>,
 ) -> impl IntoResponse {
     match get_objects(&state.db_sea, pagination.system_id).await {
@@ -85,15 +114,23 @@ pub async fn object_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Objects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Objects not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn object_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(object_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = object_info.iter().copied().map(Resource::Object).collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_objects(&state.db_sea, object_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -114,15 +151,26 @@ pub async fn object_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update Objects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Objects and their dependences not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn object_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(object_info): Json<Vec<UpdateObjectModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = object_info
+        .iter()
+        .map(|object| Resource::Object(object.id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_objects(&state.db_sea, object_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
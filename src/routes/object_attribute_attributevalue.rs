@@ -3,6 +3,10 @@ use crate::{
     services::object_attribute_attributevalue::{
         create_attribute_values_objects, multiple_delete_attribute_values_objects,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 
@@ -26,15 +30,32 @@ use entity::object_attribute_attributevalue::ObjectAttributeAttributeValueModel;
         (status = 401, description = "Unauthorized to create AttributeValuesObjects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create AttributeValuesObjects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_values_objects_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_values_objects_info): Json<Vec<ObjectAttributeAttributeValueModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_values_objects_info
+        .iter()
+        .flat_map(|link| {
+            [
+                Resource::Object(link.object_id),
+                Resource::Attribute(link.attribute_id),
+                Resource::AttributeValue(link.attribute_value_id),
+            ]
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_attribute_values_objects(&state.db_sea, attribute_values_objects_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -55,15 +76,27 @@ pub async fn attribute_values_objects_create(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete AttributeValuesObjects and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "AttributeValuesObjects not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn attribute_values_objects_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(attribute_values_objects_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = attribute_values_objects_info
+        .iter()
+        .copied()
+        .map(Resource::ObjectAttributeAttributeValue)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_attribute_values_objects(&state.db_sea, attribute_values_objects_info)
         .await
     {
@@ -4,6 +4,10 @@ use crate::{
     services::question::{
         create_questions, get_questions, multiple_delete_questions, multiple_update_questions,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -26,15 +30,26 @@ use entity::questions::{NewQuestionWithAnswersModel, UpdateQuestionModel, Questi
         (status = 401, description = "Unauthorized to create Questions and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Questions and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn question_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(question_info): Json<Vec<NewQuestionWithAnswersModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = question_info
+        .iter()
+        .map(|question| Resource::System(question.system_id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_questions(&state.db_sea, question_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -58,11 +73,12 @@ pub async fn question_create(
     params(
         QuestionListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn question_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<QuestionListPagination>,
 ) -> impl IntoResponse {
     match get_questions(&state.db_sea, pagination.system_id).await {
@@ -85,15 +101,27 @@ pub async fn question_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Questions and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Questions not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn question_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(question_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = question_info
+        .iter()
+        .copied()
+        .map(Resource::Question)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_questions(&state.db_sea, question_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -114,15 +142,26 @@ pub async fn question_multiple_delete(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update Quetions and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Quetions and their dependences not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn question_multiple_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(question_info): Json<Vec<UpdateQuestionModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = question_info
+        .iter()
+        .map(|question| Resource::Question(question.id))
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_update_questions(&state.db_sea, question_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -1,15 +1,23 @@
+use std::iter;
+
 use crate::{
     error::CustomErrors,
     pagination::RuleListPagination,
-    services::rule::{create_rule, get_rules, multiple_delete_rules},
+    services::rule::{
+        create_rule, get_rules, get_rules_dsl, multiple_delete_rules, replace_rules_from_dsl,
+    },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
     debug_handler,
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
     response::IntoResponse,
-    routing::{delete, post},
+    routing::{delete, get, post},
     Json, Router,
 };
 use entity::rules::{NewRuleWithClausesAndEffects, RuleWithClausesAndEffects};
@@ -24,15 +32,50 @@ use entity::rules::{NewRuleWithClausesAndEffects, RuleWithClausesAndEffects};
         (status = 401, description = "Unauthorized to create Rule", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Rule", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_info): Json<Vec<NewRuleWithClausesAndEffects>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_info
+        .iter()
+        .flat_map(|rule| {
+            let questions = rule
+                .clauses
+                .iter()
+                .map(|clause| Resource::Question(clause.question_id));
+            let answers = rule.rule_question_answer_ids.iter().flat_map(|ids| {
+                [
+                    Resource::Question(ids.question_id),
+                    Resource::Answer(ids.answer_id),
+                ]
+            });
+            let attribute_values = rule
+                .rule_attribute_attributevalue_ids
+                .iter()
+                .flat_map(|ids| {
+                    [
+                        Resource::Attribute(ids.attribute_id),
+                        Resource::AttributeValue(ids.attribute_value_id),
+                    ]
+                });
+            iter::once(Resource::System(rule.system_id))
+                .chain(questions)
+                .chain(answers)
+                .chain(attribute_values)
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_rule(&state.db_sea, rule_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -56,11 +99,12 @@ pub async fn rule_create(
     params(
         RuleListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_list(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<RuleListPagination>,
 ) -> impl IntoResponse {
     match get_rules(&state.db_sea, pagination.system_id).await {
@@ -83,15 +127,23 @@ pub async fn rule_list(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete Rules and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "Rules not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_info.iter().copied().map(Resource::Rule).collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_rules(&state.db_sea, rule_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -101,6 +153,83 @@ pub async fn rule_multiple_delete(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/rules/dsl",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "System Rules written in the rule language", body = String, content_type = "text/plain"),
+        (status = 401, description = "Unauthorized to list Rules", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn rule_dsl_retrieve(
+    State(state): State<AppState>,
+    _user: CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    match get_rules_dsl(&state.db_sea, system_id).await {
+        Ok(result) => Ok((
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            result,
+        )),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/rules/dsl",
+    context_path ="/api/v1",
+    request_body(content = String, description = "All System Rules written in the rule language", content_type = "text/plain"),
+    responses(
+        (status = 200, description = "System Rules replaced with the parsed ones", body = [RuleWithClausesAndEffects]),
+        (status = 401, description = "Unauthorized to replace Rules", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to replace Rules", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn rule_dsl_update(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    source: String,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match replace_rules_from_dsl(&state.db_sea, system_id, &source).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+pub fn rule_dsl_routes() -> Router<AppState> {
+    Router::new().route("/dsl", get(rule_dsl_retrieve).post(rule_dsl_update))
+}
+
 pub fn rule_routes() -> Router<AppState> {
     Router::new()
         .route("/", post(rule_create).get(rule_list))
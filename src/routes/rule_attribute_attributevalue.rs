@@ -3,6 +3,10 @@ use crate::{
     services::rule_attribute_attributevalue::{
         create_rule_attribute_attributevalues, multiple_delete_rule_attribute_attributevalues,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -25,15 +29,32 @@ use entity::rule_attribute_attributevalue::RuleAttributeAttributeValueModel;
         (status = 401, description = "Unauthorized to create RuleAttributeAttributeValue and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create RuleAttributeAttributeValue and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_attribute_attributevalue_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_attribute_attributevalue_info): Json<Vec<RuleAttributeAttributeValueModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_attribute_attributevalue_info
+        .iter()
+        .flat_map(|link| {
+            [
+                Resource::Rule(link.rule_id),
+                Resource::Attribute(link.attribute_id),
+                Resource::AttributeValue(link.attribute_value_id),
+            ]
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_rule_attribute_attributevalues(&state.db_sea, rule_attribute_attributevalue_info)
         .await
     {
@@ -56,15 +77,27 @@ pub async fn rule_attribute_attributevalue_create(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete RuleAttributeAttributeValues and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "RuleAttributeAttributeValues not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_attribute_attributevalue_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_attribute_attributevalue_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_attribute_attributevalue_info
+        .iter()
+        .copied()
+        .map(Resource::RuleAttributeAttributeValue)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_rule_attribute_attributevalues(
         &state.db_sea,
         rule_attribute_attributevalue_info,
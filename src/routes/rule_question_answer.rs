@@ -3,6 +3,10 @@ use crate::{
     services::rule_question_answer::{
         create_rule_question_answers, multiple_delete_rule_question_answers,
     },
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
     AppState,
 };
 use axum::{
@@ -25,15 +29,32 @@ use entity::rule_question_answer::RuleQuestionAnswerModel;
         (status = 401, description = "Unauthorized to create RuleQuestionAnswers and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create RuleQuestionAnswers and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_question_answer_create(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_question_answer_info): Json<Vec<RuleQuestionAnswerModel>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_question_answer_info
+        .iter()
+        .flat_map(|link| {
+            [
+                Resource::Rule(link.rule_id),
+                Resource::Question(link.question_id),
+                Resource::Answer(link.answer_id),
+            ]
+        })
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match create_rule_question_answers(&state.db_sea, rule_question_answer_info).await {
         Ok(_) => Ok(()),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -54,15 +75,27 @@ pub async fn rule_question_answer_create(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete RuleQuestionAnswers and their dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "RuleQuestionAnswers not found")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn rule_question_answer_multiple_delete(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Json(rule_question_answer_info): Json<Vec<i32>>,
 ) -> impl IntoResponse {
+    let resources: Vec<Resource> = rule_question_answer_info
+        .iter()
+        .copied()
+        .map(Resource::RuleQuestionAnswer)
+        .collect();
+    authorize_owner(&state.db_sea, user.id, &resources).await?;
+
     match multiple_delete_rule_question_answers(&state.db_sea, rule_question_answer_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
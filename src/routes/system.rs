@@ -1,20 +1,43 @@
 use crate::{
+    constants::{
+        BACKUP_FILE_EXTENSION, BACKUP_PASSPHRASE_HEADER, BACKUP_STREAM_BUFFER, MAX_BACKUP_SIZE,
+    },
     error::CustomErrors,
-    pagination::{SystemListPagination, SystemStars},
+    models::{
+        backup::BackupUploadModel,
+        coverage::CoverageModel,
+        decision_tree::{DecisionTreeFormat, DecisionTreeModel},
+        induction::InductionModel,
+        inference::{GivenAnswerModel, InferenceResultModel},
+        interchange::{InterchangeFormat, SystemDocumentModel},
+        lint::LintIssueModel,
+    },
+    pagination::{DecisionTreeQuery, InterchangeQuery, SystemListPagination, SystemStars},
+    routes::{
+        consultation::consultation_routes, rule::rule_dsl_routes, test_case::test_case_routes,
+    },
     services::{
-        backup::{backup_from_system, system_from_backup},
+        backup::{backup_from_system, export_system, import_system, system_from_backup},
         system::{
-            create_system, delete_system, get_ready_to_start_system, get_system, get_systems,
-            update_stars, update_system,
+            compile_decision_tree, create_system, delete_system, evaluate_system,
+            get_ready_to_start_system, get_system, get_system_coverage, get_systems, induce_rules,
+            lint_system, update_stars, update_system,
         },
     },
-    utils::auth::{cookie_check, password_check},
+    utils::{
+        auth::{password_check, BackupUser, CurrentUser, OptionalUser},
+        decision_tree::decision_tree_to_dot,
+        interchange::{document_from_str, document_to_string},
+        policy::{authorize_owner, forbidden, Resource},
+        transfer::{attachment_disposition, read_limited, receiver_stream},
+    },
     AppState,
 };
 use axum::{
+    body::{Body, Bytes},
     debug_handler,
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
+    extract::{DefaultBodyLimit, FromRequest, Multipart, Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, post},
     Json, Router,
@@ -22,7 +45,11 @@ use axum::{
 use axum_typed_multipart::TypedMultipart;
 use entity::systems::{NewSystemMultipartModel, SystemDeleteModel, UpdateSystemMultipartModel, SystemModel};
 use entity::questions::QuestionWithAnswersModel;
-use tower_cookies::Cookies;
+use futures::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use tokio::sync::mpsc;
 
 #[utoipa::path(
     post,
@@ -40,11 +67,9 @@ use tower_cookies::Cookies;
 #[debug_handler]
 pub async fn system_create(
     State(state): State<AppState>,
-    cookie: Cookies,
+    CurrentUser(user): CurrentUser,
     TypedMultipart(system_info): TypedMultipart<NewSystemMultipartModel>,
 ) -> impl IntoResponse {
-    let user = cookie_check(&state.db_sea, cookie, &state.config.cookie_key).await?;
-
     match create_system(&state.db_sea, system_info, user.id).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -68,16 +93,19 @@ pub async fn system_create(
     params(
         SystemListPagination
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_list(
     State(state): State<AppState>,
-    cookie: Cookies,
+    OptionalUser(user): OptionalUser,
     Query(pagination): Query<SystemListPagination>,
 ) -> impl IntoResponse {
-    if let Some(_) = pagination.all_types {
-        let _ = cookie_check(&state.db_sea, cookie, &state.config.cookie_key).await?;
+    if pagination.all_types.is_some() && user.is_none() {
+        return Err(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        });
     }
 
     match get_systems(&state.db_sea, pagination).await {
@@ -107,11 +135,12 @@ pub async fn system_list(
     params(
         ("id" = u32, Path, description = "System database id")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_retrieve(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Path(system_id): Path<i32>,
 ) -> impl IntoResponse {
     match get_system(&state.db_sea, system_id).await {
@@ -137,7 +166,7 @@ pub async fn system_retrieve(
     params(
         ("id" = u32, Path, description = "System database id")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_start(
@@ -153,67 +182,377 @@ pub async fn system_start(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/inference",
+    context_path ="/api/v1",
+    request_body = [GivenAnswerModel],
+    responses(
+        (status = 200, description = "Inference result for given answers", body = InferenceResultModel),
+        (status = 401, description = "Unauthorized to retrive System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_inference(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    Json(given_answers): Json<Vec<GivenAnswerModel>>,
+) -> impl IntoResponse {
+    // Закрытую систему может запускать только ее владелец
+    let system =
+        get_system(&state.db_sea, system_id)
+            .await
+            .map_err(|err| CustomErrors::SeaORMError {
+                error: err,
+                message: None,
+            })?;
+    if system.private && system.user_id != user.id {
+        return Err(forbidden());
+    }
+
+    match evaluate_system(&state.db_sea, system_id, given_answers).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/lint",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Problems found in System knowledge base", body = [LintIssueModel]),
+        (status = 401, description = "Unauthorized to lint System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to lint System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_lint(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match lint_system(&state.db_sea, system_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/induced_rules",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Draft rules induced from System objects and their attribute values", body = InductionModel),
+        (status = 401, description = "Unauthorized to induce System rules", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to induce System rules", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_induced_rules(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match induce_rules(&state.db_sea, system_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/coverage",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Rule and question coverage replayed from System Histories", body = CoverageModel),
+        (status = 401, description = "Unauthorized to retrive System coverage", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to retrive System coverage", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_coverage(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match get_system_coverage(&state.db_sea, system_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/decision_tree",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "System compiled into a decision tree", body = DecisionTreeModel),
+        (status = 200, description = "Decision tree in Graphviz DOT format", body = String, content_type = "text/vnd.graphviz"),
+        (status = 401, description = "Unauthorized to retrive System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to compile System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        DecisionTreeQuery
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_decision_tree(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    Query(params): Query<DecisionTreeQuery>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match compile_decision_tree(&state.db_sea, system_id).await {
+        Ok(result) => match params.format.unwrap_or_default() {
+            DecisionTreeFormat::Json => Ok(Json(result).into_response()),
+            DecisionTreeFormat::Dot => Ok((
+                [(header::CONTENT_TYPE, "text/vnd.graphviz; charset=utf-8")],
+                decision_tree_to_dot(&result),
+            )
+                .into_response()),
+        },
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+fn backup_passphrase(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(BACKUP_PASSPHRASE_HEADER)
+        // to_str принимает только ASCII, а пароль может быть на любом языке
+        .and_then(|value| std::str::from_utf8(value.as_bytes()).ok())
+}
+
 #[utoipa::path(
     get,
     path = "/systems/{id}/backup",
     context_path ="/api/v1",
     responses(
-        (status = 200, description = "Matching System by query", body=Vec<u8>),
+        (status = 200, description = "Encrypted backup file", body = Vec<u8>, content_type = "application/octet-stream",
+            headers(("content-disposition" = String, description = "attachment with the system name and the date"))),
         (status = 401, description = "Unauthorized to retrive System", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to retrive System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
         }))
     ),
     params(
-        ("id" = u32, Path, description = "System database id")
+        ("id" = u32, Path, description = "System database id"),
+        ("x-backup-passphrase" = Option<String>, Header, description = "Encrypt with a key derived from this passphrase instead of the server key")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_backup(
     State(state): State<AppState>,
+    BackupUser(user): BackupUser,
     Path(system_id): Path<i32>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    match backup_from_system(
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    let (system_name, backup) = backup_from_system(
         &state.db_sea,
         system_id,
-        state.config.crypto_key.as_bytes(),
-        state.config.nonce_key.as_bytes(),
+        &state.config.backup_keyring,
+        backup_passphrase(&headers),
     )
-    .await
-    {
-        Ok(result) => Ok(Json(result)),
-        Err(err) => Err(err),
+    .await?;
+
+    let file_name = format!(
+        "{}-{}.{}",
+        system_name,
+        chrono::Utc::now().format("%Y-%m-%d"),
+        BACKUP_FILE_EXTENSION
+    );
+    Ok::<_, CustomErrors>((
+        [
+            (header::CONTENT_TYPE, "application/octet-stream".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                attachment_disposition(&file_name),
+            ),
+        ],
+        Body::from_stream(backup),
+    ))
+}
+
+// Файл принимается как multipart (поле file), как есть в теле запроса
+// или массивом байтов в JSON, как его отдавали раньше. Первые два варианта
+// передаются дальше потоком, массив в JSON читается целиком и ограничен MAX_BACKUP_SIZE
+async fn backup_upload(
+    state: &AppState,
+    request: Request,
+) -> Result<BoxStream<'static, Result<Bytes, CustomErrors>>, CustomErrors> {
+    let content_type = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+        .to_lowercase();
+    let upload_error = |error: String| CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error,
+    };
+
+    if content_type.starts_with("multipart/form-data") {
+        let mut multipart = Multipart::from_request(request, state)
+            .await
+            .map_err(|err| upload_error(err.body_text()))?;
+        // Поле заимствует multipart, поэтому его части пересылает отдельная задача
+        let (sender, receiver) = mpsc::channel(BACKUP_STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let mut field = match multipart.next_field().await {
+                    Ok(Some(field)) if field.name() == Some("file") => field,
+                    Ok(Some(_)) => continue,
+                    Ok(None) => {
+                        let error = "Нет поля file с резервной копией".to_string();
+                        let _ = sender.send(Err(upload_error(error))).await;
+                        return;
+                    }
+                    Err(err) => {
+                        let _ = sender.send(Err(upload_error(err.body_text()))).await;
+                        return;
+                    }
+                };
+                loop {
+                    let chunk = match field.chunk().await {
+                        Ok(Some(chunk)) => Ok(chunk),
+                        Ok(None) => return,
+                        Err(err) => Err(upload_error(err.body_text())),
+                    };
+                    let failed = chunk.is_err();
+                    if sender.send(chunk).await.is_err() || failed {
+                        return;
+                    }
+                }
+            }
+        });
+        return Ok(receiver_stream(receiver).boxed());
+    }
+
+    let body = request.into_body().into_data_stream();
+    if content_type.starts_with("application/json") {
+        let data = read_limited(body, MAX_BACKUP_SIZE).await?;
+        let data: Vec<u8> = serde_json::from_slice(&data)
+            .map_err(|_| upload_error("Ожидался массив байтов резервной копии".to_string()))?;
+        return Ok(stream::once(async { Ok(Bytes::from(data)) }).boxed());
     }
+    Ok(body
+        .map(move |chunk| chunk.map_err(|_| upload_error("Ошибка при загрузке файла".to_string())))
+        .boxed())
 }
 
 #[utoipa::path(
     post,
     path = "/systems/restore",
     context_path ="/api/v1",
-    request_body(content =Vec<u8>, content_type = "application/json"),
+    request_body(content(
+        (BackupUploadModel = "multipart/form-data"),
+        (Vec<u8> = "application/octet-stream"),
+        (Vec<u8> = "application/json")
+    )),
     responses(
         (status = 200, description = "Sususfully restore", body = SystemModel),
         (status = 401, description = "Unauthorized to retrive System", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
-        }))
+        })),
+        (status = 413, description = "Backup file is too large", body = CustomErrors)
+    ),
+    params(
+        ("x-backup-passphrase" = Option<String>, Header, description = "Passphrase of a passphrase-protected backup")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_restore(
     State(state): State<AppState>,
-    cookie: Cookies,
-    Json(system_decode): Json<Vec<u8>>,
+    BackupUser(user): BackupUser,
+    headers: HeaderMap,
+    request: Request,
 ) -> impl IntoResponse {
+    let upload = backup_upload(&state, request).await?;
+
     match system_from_backup(
         &state.db_sea,
-        system_decode,
-        cookie,
-        &state.config.cookie_key,
-        state.config.crypto_key.as_bytes(),
-        state.config.nonce_key.as_bytes(),
+        upload,
+        user.id,
+        &state.config.backup_keyring,
+        backup_passphrase(&headers),
     )
     .await
     {
@@ -222,6 +561,76 @@ pub async fn system_restore(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/export",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "System in the portable JSON format", body = SystemDocumentModel),
+        (status = 200, description = "System in the portable YAML format", body = String, content_type = "application/yaml"),
+        (status = 401, description = "Unauthorized to export System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        InterchangeQuery
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_export(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    Query(params): Query<InterchangeQuery>,
+) -> impl IntoResponse {
+    let format = params.format.unwrap_or_default();
+
+    let document = export_system(&state.db_sea, system_id, user.id).await?;
+    let content_type = match format {
+        InterchangeFormat::Json => "application/json",
+        InterchangeFormat::Yaml => "application/yaml; charset=utf-8",
+    };
+    Ok::<_, CustomErrors>((
+        [(header::CONTENT_TYPE, content_type)],
+        document_to_string(&document, format)?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/systems/import",
+    context_path ="/api/v1",
+    request_body(content = SystemDocumentModel, description = "System in the portable JSON or YAML format", content_type = "application/json"),
+    responses(
+        (status = 200, description = "System imported successfully", body = SystemModel),
+        (status = 401, description = "Unauthorized to import System", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        InterchangeQuery
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn system_import(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Query(params): Query<InterchangeQuery>,
+    source: String,
+) -> impl IntoResponse {
+    let document = document_from_str(&source, params.format.unwrap_or_default())?;
+
+    match import_system(&state.db_sea, document, user.id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(err),
+    }
+}
+
 #[utoipa::path(
     patch,
     path = "/systems/{id}",
@@ -233,19 +642,26 @@ pub async fn system_restore(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to update System and it dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "System not found")
     ),
     params(
         ("id" = u32, Path, description = "System database id")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_partial_update(
     State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
     Path(system_id): Path<i32>,
     TypedMultipart(system_info): TypedMultipart<UpdateSystemMultipartModel>,
 ) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
     match update_system(&state.db_sea, system_id, system_info).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
@@ -266,27 +682,26 @@ pub async fn system_partial_update(
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })),
+        (status = 403, description = "Forbidden to delete System and it dependences", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        })),
         (status = 404, description = "System not found")
     ),
     params(
         ("id" = u32, Path, description = "System database id")
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_delete(
     State(state): State<AppState>,
-    cookie: Cookies,
+    CurrentUser(user): CurrentUser,
     Path(system_id): Path<i32>,
     Json(system_info): Json<SystemDeleteModel>,
 ) -> impl IntoResponse {
-    password_check(
-        &state.db_sea,
-        cookie,
-        &state.config.cookie_key,
-        &system_info.password,
-    )
-    .await?;
+    password_check(&user, &system_info.password)?;
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
 
     match delete_system(&state.db_sea, system_id).await {
         Ok(_) => Ok(()),
@@ -308,11 +723,12 @@ pub async fn system_delete(
             error: "Not authorized".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
 #[debug_handler]
 pub async fn system_stars(
     State(state): State<AppState>,
+    _user: CurrentUser,
     Query(pagination): Query<SystemStars>,
     Path(system_id): Path<i32>,
 ) -> impl IntoResponse {
@@ -335,7 +751,20 @@ pub fn system_routes() -> Router<AppState> {
                 .delete(system_delete),
         )
         .route("/:system_id/test", get(system_start))
+        .route("/:system_id/inference", post(system_inference))
+        .route("/:system_id/lint", get(system_lint))
+        .route("/:system_id/coverage", get(system_coverage))
+        .route("/:system_id/induced_rules", get(system_induced_rules))
+        .route("/:system_id/decision_tree", get(system_decision_tree))
         .route("/:system_id/backup", get(system_backup))
+        .route("/:system_id/export", get(system_export))
         .route("/:system_id/stars", post(system_stars))
-        .route("/restore", post(system_restore))
+        .route(
+            "/restore",
+            post(system_restore).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/import", post(system_import))
+        .nest("/:system_id/consultations", consultation_routes())
+        .nest("/:system_id/tests", test_case_routes())
+        .nest("/:system_id/rules", rule_dsl_routes())
 }
@@ -0,0 +1,185 @@
+use crate::{
+    error::CustomErrors,
+    models::test_case::{NewTestCaseModel, TestRunModel},
+    services::test_case::{create_test_case, delete_test_case, get_test_cases, run_test_cases},
+    utils::{
+        auth::CurrentUser,
+        policy::{authorize_owner, Resource},
+    },
+    AppState,
+};
+use axum::{
+    debug_handler,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, post},
+    Json, Router,
+};
+use entity::test_cases::TestCaseModel;
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/tests",
+    context_path ="/api/v1",
+    request_body = NewTestCaseModel,
+    responses(
+        (status = 200, description = "Test case create successfully", body = TestCaseModel),
+        (status = 401, description = "Unauthorized to create Test case", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to create Test case", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn test_case_create(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+    Json(test_case_info): Json<NewTestCaseModel>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match create_test_case(&state.db_sea, system_id, test_case_info).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/systems/{id}/tests",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "List System Test cases", body = [TestCaseModel]),
+        (status = 401, description = "Unauthorized to list Test cases", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to list Test cases", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn test_case_list(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match get_test_cases(&state.db_sea, system_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/systems/{id}/tests/{test_case_id}",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Test case deleted successfully", body = u64),
+        (status = 401, description = "Unauthorized to delete Test case", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to delete Test case", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id"),
+        ("test_case_id" = u32, Path, description = "Test case database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn test_case_delete(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path((system_id, test_case_id)): Path<(i32, i32)>,
+) -> impl IntoResponse {
+    authorize_owner(
+        &state.db_sea,
+        user.id,
+        &[
+            Resource::System(system_id),
+            Resource::TestCase(test_case_id),
+        ],
+    )
+    .await?;
+
+    match delete_test_case(&state.db_sea, system_id, test_case_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/systems/{id}/tests/run",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Results of running all System Test cases", body = TestRunModel),
+        (status = 401, description = "Unauthorized to run Test cases", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        })),
+        (status = 403, description = "Forbidden to run Test cases", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: "Действие доступно только владельцу системы".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "System database id")
+    ),
+    security(("Cookie" = []), ("Bearer" = []))
+)]
+#[debug_handler]
+pub async fn test_case_run(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    Path(system_id): Path<i32>,
+) -> impl IntoResponse {
+    authorize_owner(&state.db_sea, user.id, &[Resource::System(system_id)]).await?;
+
+    match run_test_cases(&state.db_sea, system_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+pub fn test_case_routes() -> Router<AppState> {
+    Router::new()
+        .route("/", post(test_case_create).get(test_case_list))
+        .route("/run", post(test_case_run))
+        .route("/:test_case_id", delete(test_case_delete))
+}
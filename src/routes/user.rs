@@ -1,25 +1,40 @@
 use crate::{
-    constants::COOKIE_NAME,
     error::CustomErrors,
-    services::user::{
-        create_user, forgot_password, get_user, login_user, reset_password, update_user,
-        verify_email,
+    models::{
+        access_token::{AccessTokenInfoModel, CreatedAccessTokenModel, NewAccessTokenModel},
+        session::{SessionClientModel, UserSessionModel},
+        two_factor::{
+            LoginResultModel, RecoveryCodesModel, TwoFactorChallengeModel, TwoFactorCodeModel,
+            TwoFactorEnrollmentModel, TwoFactorLoginModel, TwoFactorPasswordModel,
+            TwoFactorVerifyModel,
+        },
     },
-    utils::auth::password_check,
+    services::{
+        access_token::{create_access_token, delete_access_token, get_access_tokens},
+        session::{delete_session, get_sessions, logout_session},
+        two_factor::{
+            complete_two_factor_login, confirm_two_factor, disable_two_factor, enroll_two_factor,
+            regenerate_recovery_codes,
+        },
+        user::{
+            create_user, forgot_password, login_user, reset_password, update_user, verify_email,
+        },
+    },
+    utils::auth::{password_check, CurrentSession, CurrentUser},
     AppState,
 };
 use axum::{
     debug_handler,
     extract::{Path, State},
     http::StatusCode,
-    response::{IntoResponse, Json},
-    routing::{get, post},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, post},
     Router,
 };
 use entity::users::{
     ForgotPasswordModel, LoginUserModel, ResetPasswordModel, UpdateUserResponse, UserModel,
 };
-use tower_cookies::{Cookie, Cookies};
+use tower_cookies::Cookies;
 
 #[utoipa::path(
     post,
@@ -28,6 +43,7 @@ use tower_cookies::{Cookie, Cookies};
     request_body = LoginUserModel,
     responses(
         (status = 200, description = "User login successfully", body = UserModel),
+        (status = 202, description = "Password accepted, two-factor code required", body = TwoFactorChallengeModel),
         (status = 400, description = "Invalid credantials provided", body = CustomErrors, example = json!(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
@@ -38,9 +54,60 @@ use tower_cookies::{Cookie, Cookies};
 pub async fn user_login(
     State(state): State<AppState>,
     cookie: Cookies,
+    client: SessionClientModel,
     Json(user_info): Json<LoginUserModel>,
+) -> Result<Response, CustomErrors> {
+    match login_user(
+        &state.db_sea,
+        user_info,
+        client,
+        cookie,
+        &state.config.cookie_key,
+        &state.config.clock,
+    )
+    .await
+    {
+        Ok(LoginResultModel::Authorized(user)) => Ok(Json(user).into_response()),
+        Ok(LoginResultModel::TwoFactorRequired(challenge)) => {
+            Ok((StatusCode::ACCEPTED, Json(challenge)).into_response())
+        }
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/login/2fa",
+    context_path ="/api/v1",
+    request_body = TwoFactorLoginModel,
+    responses(
+        (status = 200, description = "User login successfully", body = UserModel),
+        (status = 400, description = "Invalid or expired two-factor code", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::BAD_REQUEST,
+            error: "Неверный код двухфакторной аутентификации".to_string(),
+        }))
+    )
+)]
+#[debug_handler]
+pub async fn user_login_two_factor(
+    State(state): State<AppState>,
+    cookie: Cookies,
+    client: SessionClientModel,
+    Json(login_info): Json<TwoFactorLoginModel>,
 ) -> impl IntoResponse {
-    match login_user(&state.db_sea, user_info, cookie, &state.config.cookie_key).await {
+    match complete_two_factor_login(
+        &state.db_sea,
+        login_info,
+        client,
+        cookie,
+        &state.config.cookie_key,
+        &state.config.clock,
+    )
+    .await
+    {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
             error: err,
@@ -58,10 +125,14 @@ pub async fn user_login(
     )
 )]
 #[debug_handler]
-pub async fn user_logout(cookie: Cookies) -> impl IntoResponse {
-    cookie.remove(Cookie::new(COOKIE_NAME, ""));
-
-    ()
+pub async fn user_logout(State(state): State<AppState>, cookie: Cookies) -> impl IntoResponse {
+    match logout_session(&state.db_sea, &cookie, &state.config.cookie_key).await {
+        Ok(_) => Ok(()),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
 }
 
 #[utoipa::path(
@@ -102,30 +173,11 @@ pub async fn user_registration(
             error: "Not authorized".to_string(),
         }))
     ),
-    security(("Cookie" = []))
+    security(("Cookie" = []), ("Bearer" = []))
 )]
-#[debug_handler]
-pub async fn user_get(State(state): State<AppState>, cookie: Cookies) -> impl IntoResponse {
-    let user_id = cookie
-        .private(&state.config.cookie_key)
-        .get(COOKIE_NAME)
-        .map(|res| res.value().to_owned().parse::<i32>())
-        .ok_or(CustomErrors::StringError {
-            status: StatusCode::UNAUTHORIZED,
-            error: "Пользователь не авторизован".to_string(),
-        })?
-        .map_err(|err| CustomErrors::StringError {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
-            error: err.to_string(),
-        })?;
-
-    match get_user(&state.db_sea, user_id).await {
-        Ok(result) => Ok(Json(result)),
-        Err(err) => Err(CustomErrors::SeaORMError {
-            error: err,
-            message: None,
-        }),
-    }
+#[debug_handler(state = AppState)]
+pub async fn user_get(CurrentUser(user): CurrentUser) -> impl IntoResponse {
+    Json(user)
 }
 
 #[utoipa::path(
@@ -144,18 +196,13 @@ pub async fn user_get(State(state): State<AppState>, cookie: Cookies) -> impl In
 #[debug_handler]
 pub async fn user_patch(
     State(state): State<AppState>,
-    cookie: Cookies,
+    CurrentUser(user_cookie): CurrentUser,
+    CurrentSession(session): CurrentSession,
     Json(user): Json<UpdateUserResponse>,
 ) -> impl IntoResponse {
-    let user_cookie = password_check(
-        &state.db_sea,
-        cookie,
-        &state.config.cookie_key,
-        &user.password,
-    )
-    .await?;
+    password_check(&user_cookie, &user.password)?;
 
-    match update_user(&state.db_sea, user, user_cookie.id).await {
+    match update_user(&state.db_sea, user, user_cookie.id, session.id).await {
         Ok(result) => Ok(Json(result)),
         Err(err) => Err(CustomErrors::SeaORMError {
             error: err,
@@ -180,11 +227,13 @@ pub async fn user_patch(
 pub async fn verify_email_handler(
     State(state): State<AppState>,
     cookie: Cookies,
+    client: SessionClientModel,
     Path(verification_code): Path<String>,
 ) -> impl IntoResponse {
     match verify_email(
         &state.db_sea,
         verification_code,
+        client,
         cookie,
         &state.config.cookie_key,
     )
@@ -251,11 +300,298 @@ pub async fn reset_password_hadler(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/user/sessions",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Active User sessions", body = [UserSessionModel]),
+        (status = 401, description = "Unauthorized to list User sessions", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_session_list(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    CurrentSession(session): CurrentSession,
+) -> impl IntoResponse {
+    match get_sessions(&state.db_sea, user.id, session.id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/user/sessions/{id}",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "User session revoked successfully", body = u64),
+        (status = 401, description = "Unauthorized to revoke User session", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "Session database id")
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_session_delete(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Path(session_id): Path<i32>,
+) -> impl IntoResponse {
+    match delete_session(&state.db_sea, user.id, session_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/tokens",
+    context_path ="/api/v1",
+    request_body = NewAccessTokenModel,
+    responses(
+        (status = 200, description = "Personal access token created, the token itself is shown only once", body = CreatedAccessTokenModel),
+        (status = 401, description = "Unauthorized to create personal access token", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_token_create(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Json(token_info): Json<NewAccessTokenModel>,
+) -> impl IntoResponse {
+    match create_access_token(&state.db_sea, user.id, token_info).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/tokens",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "User personal access tokens", body = [AccessTokenInfoModel]),
+        (status = 401, description = "Unauthorized to list personal access tokens", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_token_list(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+) -> impl IntoResponse {
+    match get_access_tokens(&state.db_sea, user.id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/user/tokens/{id}",
+    context_path ="/api/v1",
+    responses(
+        (status = 200, description = "Personal access token revoked successfully", body = u64),
+        (status = 401, description = "Unauthorized to revoke personal access token", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    params(
+        ("id" = u32, Path, description = "Personal access token database id")
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_token_delete(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Path(access_token_id): Path<i32>,
+) -> impl IntoResponse {
+    match delete_access_token(&state.db_sea, user.id, access_token_id).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/2fa",
+    context_path ="/api/v1",
+    request_body = TwoFactorPasswordModel,
+    responses(
+        (status = 200, description = "TOTP secret, provisioning URI and recovery codes, shown only once", body = TwoFactorEnrollmentModel),
+        (status = 401, description = "Unauthorized to enroll two-factor authentication", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_two_factor_enroll(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Json(info): Json<TwoFactorPasswordModel>,
+) -> impl IntoResponse {
+    password_check(&user, &info.password)?;
+
+    match enroll_two_factor(&state.db_sea, user).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/2fa/confirm",
+    context_path ="/api/v1",
+    request_body = TwoFactorCodeModel,
+    responses(
+        (status = 200, description = "Two-factor authentication enabled", body = UserModel),
+        (status = 401, description = "Unauthorized to confirm two-factor authentication", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_two_factor_confirm(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Json(info): Json<TwoFactorCodeModel>,
+) -> impl IntoResponse {
+    match confirm_two_factor(&state.db_sea, user, &info.code, &state.config.clock).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/user/2fa",
+    context_path ="/api/v1",
+    request_body = TwoFactorVerifyModel,
+    responses(
+        (status = 200, description = "Two-factor authentication disabled", body = UserModel),
+        (status = 401, description = "Unauthorized to disable two-factor authentication", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_two_factor_disable(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Json(info): Json<TwoFactorVerifyModel>,
+) -> impl IntoResponse {
+    password_check(&user, &info.password)?;
+
+    match disable_two_factor(&state.db_sea, user, &info.code, &state.config.clock).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/2fa/recovery-codes",
+    context_path ="/api/v1",
+    request_body = TwoFactorVerifyModel,
+    responses(
+        (status = 200, description = "New recovery codes, the old ones stop working", body = RecoveryCodesModel),
+        (status = 401, description = "Unauthorized to regenerate recovery codes", body = CustomErrors, example = json!(CustomErrors::StringError {
+            status: StatusCode::UNAUTHORIZED,
+            error: "Not authorized".to_string(),
+        }))
+    ),
+    security(("Cookie" = []))
+)]
+#[debug_handler]
+pub async fn user_two_factor_recovery_codes(
+    State(state): State<AppState>,
+    CurrentUser(user): CurrentUser,
+    _session: CurrentSession,
+    Json(info): Json<TwoFactorVerifyModel>,
+) -> impl IntoResponse {
+    password_check(&user, &info.password)?;
+
+    match regenerate_recovery_codes(&state.db_sea, user, &info.code, &state.config.clock).await {
+        Ok(result) => Ok(Json(result)),
+        Err(err) => Err(CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        }),
+    }
+}
+
 pub fn user_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(user_get).patch(user_patch))
+        .route("/sessions", get(user_session_list))
+        .route("/sessions/:session_id", delete(user_session_delete))
+        .route("/tokens", post(user_token_create).get(user_token_list))
+        .route("/tokens/:access_token_id", delete(user_token_delete))
         .route("/logout", post(user_logout))
         .route("/login", post(user_login))
+        .route("/login/2fa", post(user_login_two_factor))
+        .route(
+            "/2fa",
+            post(user_two_factor_enroll).delete(user_two_factor_disable),
+        )
+        .route("/2fa/confirm", post(user_two_factor_confirm))
+        .route("/2fa/recovery-codes", post(user_two_factor_recovery_codes))
         .route("/registration", post(user_registration))
         .route(
             "/verifyemail/:verification_code",
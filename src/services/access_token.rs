@@ -0,0 +1,176 @@
+use crate::{
+    constants::{ACCESS_TOKEN_ID_LEN, ACCESS_TOKEN_PREFIX, ACCESS_TOKEN_SECRET_LEN},
+    models::access_token::{
+        AccessTokenInfoModel, CreatedAccessTokenModel, NewAccessTokenModel, TokenScope,
+    },
+    utils::{
+        auth::{check_password, hash_password},
+        generate_random_string::generate_random_string,
+    },
+};
+use chrono::Utc;
+use entity::{
+    access_tokens::{
+        ActiveModel as AccessTokenActiveModel, Column as AccessTokenColumn,
+        Entity as AccessTokenEntity, Model as AccessTokenModel,
+    },
+    users::{Entity as UserEntity, Model as UserModel},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait, Unchanged,
+};
+use serde_json::json;
+
+// Нераспознанные области дают пустой список, то есть токен без прав
+pub fn token_scopes(access_token: &AccessTokenModel) -> Vec<TokenScope> {
+    serde_json::from_value(access_token.scopes.clone()).unwrap_or_default()
+}
+
+fn access_token_info(access_token: AccessTokenModel) -> AccessTokenInfoModel {
+    AccessTokenInfoModel {
+        scopes: token_scopes(&access_token),
+        id: access_token.id,
+        name: access_token.name,
+        prefix: access_token.prefix,
+        created_at: access_token.created_at,
+        last_used_at: access_token.last_used_at,
+    }
+}
+
+// Токен вида esat_<id>_<секрет>: по открытой части esat_<id> запись находится в базе,
+// весь токен проверяется по хэшу Argon2
+pub async fn create_access_token<C>(
+    db: &C,
+    user_id: i32,
+    token_info: NewAccessTokenModel,
+) -> Result<CreatedAccessTokenModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let name = token_info.name.trim().to_string();
+    if name.is_empty() || name.chars().count() > 128 {
+        return Err(DbErr::Custom(
+            "Название токена должно быть от 1 до 128 символов".to_string(),
+        ));
+    }
+    let mut scopes: Vec<TokenScope> = Vec::new();
+    for scope in token_info.scopes {
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+    if scopes.is_empty() {
+        return Err(DbErr::Custom(
+            "Укажите хотя бы одну область токена".to_string(),
+        ));
+    }
+
+    let prefix = format!(
+        "{}_{}",
+        ACCESS_TOKEN_PREFIX,
+        generate_random_string(ACCESS_TOKEN_ID_LEN)
+    );
+    let token = format!(
+        "{}_{}",
+        prefix,
+        generate_random_string(ACCESS_TOKEN_SECRET_LEN)
+    );
+
+    let access_token = AccessTokenActiveModel {
+        user_id: Set(user_id),
+        name: Set(name),
+        prefix: Set(prefix),
+        token_hash: Set(hash_password(&token)),
+        scopes: Set(json!(scopes)),
+        created_at: Set(Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(CreatedAccessTokenModel {
+        token,
+        access_token: access_token_info(access_token),
+    })
+}
+
+pub async fn get_access_tokens<C>(db: &C, user_id: i32) -> Result<Vec<AccessTokenInfoModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(AccessTokenEntity::find()
+        .filter(AccessTokenColumn::UserId.eq(user_id))
+        .order_by_desc(AccessTokenColumn::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(access_token_info)
+        .collect())
+}
+
+pub async fn delete_access_token<C>(
+    db: &C,
+    user_id: i32,
+    access_token_id: i32,
+) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let result = AccessTokenEntity::delete_many()
+        .filter(AccessTokenColumn::Id.eq(access_token_id))
+        .filter(AccessTokenColumn::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(DbErr::Custom("Токен не найден".to_string()));
+    }
+
+    Ok(result.rows_affected)
+}
+
+// Токены выдавались под старым паролем, после его смены они отзываются
+pub async fn delete_user_access_tokens<C>(db: &C, user_id: i32) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(AccessTokenEntity::delete_many()
+        .filter(AccessTokenColumn::UserId.eq(user_id))
+        .exec(db)
+        .await?
+        .rows_affected)
+}
+
+// Токен из заголовка Authorization вместе с владельцем. Отмечает время использования
+pub async fn find_access_token<C>(
+    db: &C,
+    token: &str,
+) -> Result<Option<(AccessTokenModel, UserModel)>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some((prefix, _)) = token.rsplit_once('_') else {
+        return Ok(None);
+    };
+    let Some((access_token, Some(user))) = AccessTokenEntity::find()
+        .filter(AccessTokenColumn::Prefix.eq(prefix))
+        .find_also_related(UserEntity)
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+    if check_password(token, &access_token.token_hash).is_err() {
+        return Ok(None);
+    }
+
+    let access_token = AccessTokenActiveModel {
+        id: Unchanged(access_token.id),
+        last_used_at: Set(Some(Utc::now().naive_utc())),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(Some((access_token, user)))
+}
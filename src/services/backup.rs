@@ -1,41 +1,60 @@
 use crate::{
+    config::BackupKeyring,
+    constants::{BACKUP_STREAM_BUFFER, MIN_BACKUP_PASSPHRASE_LEN},
     error::CustomErrors,
+    models::interchange::SystemDocumentModel,
     utils::{
-        auth::cookie_check,
+        backup_format::{read_backup, write_backup},
         copy::{
             copy_answers, copy_attribute_values, copy_attributes, copy_clauses,
             copy_object_attribute_attributevalues, copy_objects, copy_questions,
-            copy_rule_attribute_attributevalues, copy_rule_question_answers, copy_rules,
-            copy_system,
+            copy_rule_attribute_attributevalues, copy_rule_conditions, copy_rule_question_answers,
+            copy_rules, copy_system, copy_test_cases,
         },
-        crypto::{decrypt_data, encrypt_data},
+        crypto::{BackupDecryptor, BackupEncryptor},
+        interchange::{backup_from_document, document_from_backup},
+        media::{fresh_image_uri, read_image, remove_image, write_image},
+        policy::forbidden,
+        transfer::{receiver_stream, ChannelReader, ChannelWriter},
     },
 };
+use axum::body::Bytes;
 use entity::{
-    answers::Entity as AnswerEntity,
-    attributes::Entity as AttributeEntity,
-    attributesvalues::Entity as AttributeValueEntity,
-    clauses::Entity as ClauseEntity,
-    object_attribute_attributevalue::Entity as ObjectAttributeAttributeValueEntity,
-    objects::Entity as ObjectEntity,
-    questions::Entity as QuestionEntity,
-    rule_attribute_attributevalue::Entity as RuleAttributeAttributeValueEntity,
-    rule_question_answer::Entity as RuleQuestionAnswerEntity,
-    rules::Entity as RuleEntity,
-    systems::{Entity as SystemEntity, Model as SystemModel, SystemBackupModel},
+    answers::{Entity as AnswerEntity, Model as AnswerModel},
+    attributes::{Entity as AttributeEntity, Model as AttributeModel},
+    attributesvalues::{Entity as AttributeValueEntity, Model as AttributeValueModel},
+    backup::{BackupMediaModel, BackupMediaOwner, SystemBackupModel},
+    clauses::{Entity as ClauseEntity, Model as ClauseModel},
+    object_attribute_attributevalue::{
+        Entity as ObjectAttributeAttributeValueEntity, Model as ObjectAttributeAttributeValueModel,
+    },
+    objects::{Entity as ObjectEntity, Model as ObjectModel},
+    questions::{Entity as QuestionEntity, Model as QuestionModel},
+    rule_attribute_attributevalue::{
+        Entity as RuleAttributeAttributeValueEntity, Model as RuleAttributeAttributeValueModel,
+    },
+    rule_question_answer::{Entity as RuleQuestionAnswerEntity, Model as RuleQuestionAnswerModel},
+    rules::{Entity as RuleEntity, Model as RuleModel},
+    systems::{Entity as SystemEntity, Model as SystemModel},
+    test_cases::{Entity as TestCaseEntity, Model as TestCaseModel},
 };
+use futures::{Stream, StreamExt};
 use http::StatusCode;
 use sea_orm::{ConnectionTrait, EntityTrait, LoaderTrait, ModelTrait, TransactionTrait};
-use std::collections::HashMap;
-use tokio::try_join;
-use tower_cookies::{Cookies, Key};
+use std::{collections::HashMap, io, pin::pin};
+use tokio::{sync::mpsc, try_join};
 
-pub async fn backup_from_system<C>(
+fn into_models<B, M>(backups: Vec<B>) -> Vec<M>
+where
+    B: Into<M>,
+{
+    backups.into_iter().map(Into::into).collect()
+}
+
+pub async fn load_system_backup<C>(
     db: &C,
     system_id: i32,
-    crypto_key: &[u8],
-    nonce_key: &[u8],
-) -> Result<Vec<u8>, CustomErrors>
+) -> Result<SystemBackupModel, CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
 {
@@ -51,11 +70,12 @@ where
             error: "Система не найдена".to_string(),
         })?;
 
-    let (objects, attributes, rules, questions) = try_join!(
+    let (objects, attributes, rules, questions, test_cases) = try_join!(
         system.find_related(ObjectEntity).all(db),
         system.find_related(AttributeEntity).all(db),
         system.find_related(RuleEntity).all(db),
         system.find_related(QuestionEntity).all(db),
+        system.find_related(TestCaseEntity).all(db),
     )
     .map_err(|err| CustomErrors::SeaORMError {
         error: err,
@@ -82,71 +102,196 @@ where
         message: None,
     })?;
 
-    let struct_to_encrypt = SystemBackupModel {
-        system,
-        objects,
+    let image = match system.image_uri.as_deref() {
+        Some(image_uri) => read_image(image_uri).await,
+        None => None,
+    };
+    let media = image
+        .map(|(file_name, content)| BackupMediaModel {
+            owner: BackupMediaOwner::System,
+            owner_id: system.id,
+            file_name,
+            content,
+        })
+        .into_iter()
+        .collect();
+
+    Ok(SystemBackupModel {
+        system: system.into(),
+        objects: objects.into_iter().map(Into::into).collect(),
         object_attribute_attributevalue: object_attribute_values
             .into_iter()
-            .flat_map(|arr| arr)
+            .flatten()
+            .map(Into::into)
+            .collect(),
+        attributes: attributes.into_iter().map(Into::into).collect(),
+        attributes_values: attribute_values
+            .into_iter()
+            .flatten()
+            .map(Into::into)
             .collect(),
-        attributes,
-        attributes_values: attribute_values.into_iter().flat_map(|arr| arr).collect(),
-        rules,
+        rules: rules.into_iter().map(Into::into).collect(),
         rule_attribute_attributevalue: rule_attribute_values
             .into_iter()
-            .flat_map(|arr| arr)
+            .flatten()
+            .map(Into::into)
             .collect(),
-        clauses: clause_entities.into_iter().flat_map(|arr| arr).collect(),
-        questions,
-        answers: answers.into_iter().flat_map(|arr| arr).collect(),
+        clauses: clause_entities
+            .into_iter()
+            .flatten()
+            .map(Into::into)
+            .collect(),
+        questions: questions.into_iter().map(Into::into).collect(),
+        answers: answers.into_iter().flatten().map(Into::into).collect(),
         rule_question_answer: rule_question_answers
             .into_iter()
-            .flat_map(|arr| arr)
+            .flatten()
+            .map(Into::into)
             .collect(),
-    };
-    let encoded: Vec<u8> = bincode::serialize(&struct_to_encrypt).expect("serialize error");
+        test_cases: test_cases.into_iter().map(Into::into).collect(),
+        media,
+    })
+}
 
-    let encrypt_backup =
-        encrypt_data(crypto_key, nonce_key, &encoded).map_err(|err| CustomErrors::AesGsmError {
-            error: err,
-            message: Some("Ошибка в создании резервной копии".to_string()),
-        })?;
+// Возвращает имя системы для имени файла и поток зашифрованной копии. Копия кодируется
+// и шифруется частями в spawn_blocking по мере того, как клиент ее забирает
+pub async fn backup_from_system<C>(
+    db: &C,
+    system_id: i32,
+    keyring: &BackupKeyring,
+    passphrase: Option<&str>,
+) -> Result<(String, impl Stream<Item = io::Result<Bytes>>), CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if passphrase.is_some_and(|passphrase| passphrase.chars().count() < MIN_BACKUP_PASSPHRASE_LEN) {
+        return Err(CustomErrors::StringError {
+            status: StatusCode::BAD_REQUEST,
+            error: format!(
+                "Пароль резервной копии должен быть не короче {} символов",
+                MIN_BACKUP_PASSPHRASE_LEN
+            ),
+        });
+    }
 
-    Ok(encrypt_backup)
+    let struct_to_encrypt = load_system_backup(db, system_id).await?;
+    let system_name = struct_to_encrypt.system.name.clone();
+
+    let (sender, receiver) = mpsc::channel(BACKUP_STREAM_BUFFER);
+    let keyring = keyring.clone();
+    let passphrase = passphrase.map(str::to_string);
+    tokio::task::spawn_blocking(move || {
+        let writer = ChannelWriter::new(sender.clone());
+        let encryptor = match passphrase {
+            Some(passphrase) => BackupEncryptor::with_passphrase(&passphrase, writer),
+            None => BackupEncryptor::with_keyring(&keyring, writer),
+        };
+        let result = encryptor.and_then(|mut encryptor| {
+            write_backup(&struct_to_encrypt, &mut encryptor)?;
+            encryptor.finish().map(|_| ())
+        });
+        // Ошибка обрывает ответ, а у оборванной копии нет последней части
+        if let Err(err) = result {
+            let _ = sender.blocking_send(Err(err));
+        }
+    });
+
+    Ok((system_name, receiver_stream(receiver)))
 }
 
-pub async fn system_from_backup<C>(
+// Загрузка расшифровывается по мере поступления, а расшифрованные части разбирает
+// read_backup в spawn_blocking, поэтому файл целиком в памяти не держится
+pub async fn system_from_backup<C, S>(
     db: &C,
-    encrypted_system: Vec<u8>,
-    cookie: Cookies,
-    cookie_key: &Key,
-    crypto_key: &[u8],
-    nonce_key: &[u8],
+    upload: S,
+    user_id: i32,
+    keyring: &BackupKeyring,
+    passphrase: Option<&str>,
 ) -> Result<SystemModel, CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
+    S: Stream<Item = Result<Bytes, CustomErrors>>,
 {
-    let decoded_system = decrypt_data(crypto_key, nonce_key, &encrypted_system).map_err(|err| {
-        CustomErrors::AesGsmError {
-            error: err,
-            message: Some("Файл поврежден или изменен".to_string()),
+    let (sender, receiver) = mpsc::channel(BACKUP_STREAM_BUFFER);
+    let decoder = tokio::task::spawn_blocking(move || read_backup(ChannelReader::new(receiver)));
+
+    let mut decryptor = BackupDecryptor::new(keyring, passphrase);
+    let mut upload = pin!(upload);
+    let received = async {
+        while let Some(chunk) = upload.next().await {
+            let plaintext = decryptor.push(&chunk?)?;
+            // Разбор уже завершился ошибкой, ее и вернем
+            if !plaintext.is_empty() && sender.send(plaintext).await.is_err() {
+                return Ok(());
+            }
         }
-    })?;
+        let plaintext = decryptor.finish()?;
+        let _ = sender.send(plaintext).await;
+        Ok::<(), CustomErrors>(())
+    }
+    .await;
+    drop(sender);
 
-    let system_backup: SystemBackupModel =
-        bincode::deserialize(&decoded_system).map_err(|_| CustomErrors::StringError {
-            status: StatusCode::BAD_REQUEST,
-            error: "Ошибка декодирования".to_string(),
-        })?;
+    let decoded = decoder.await.map_err(|_| CustomErrors::StringError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        error: "Ошибка при разборе резервной копии".to_string(),
+    })?;
+    received?;
+    let mut system_backup = decoded?;
 
-    let user_cookie = cookie_check(db, cookie, cookie_key).await?;
-    if user_cookie.id != system_backup.system.user_id {
-        return Err(CustomErrors::StringError {
-            status: StatusCode::BAD_REQUEST,
-            error: "Чужая система".to_string(),
-        });
+    // Копию с паролем может восстановить любой, кто знает пароль - система
+    // создается в его аккаунте. Копия на ключе сервера остается только у владельца
+    if decryptor.is_passphrase_backup() {
+        system_backup.system.user_id = user_id;
+    } else if user_id != system_backup.system.user_id {
+        return Err(forbidden());
     }
 
+    restore_system_backup(db, system_backup).await
+}
+
+// Id в резервной копии используются только для связей, в базе создаются новые записи
+pub async fn restore_system_backup<C>(
+    db: &C,
+    system_backup: SystemBackupModel,
+) -> Result<SystemModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut system: SystemModel = system_backup.system.into();
+
+    // Картинка записывается в новый файл, чтобы копия не зависела от оригинала.
+    // В старых копиях файла нет - берем его с диска по image_uri, если он сохранился
+    let system_image = match system_backup
+        .media
+        .into_iter()
+        .find(|media| media.owner == BackupMediaOwner::System && media.owner_id == system.id)
+    {
+        Some(media) => Some((media.file_name, media.content)),
+        None => match system.image_uri.as_deref() {
+            Some(image_uri) => read_image(image_uri).await,
+            None => None,
+        },
+    };
+    system.image_uri = system_image
+        .as_ref()
+        .map(|(file_name, _)| fresh_image_uri(file_name));
+
+    let objects: Vec<ObjectModel> = into_models(system_backup.objects);
+    let object_attribute_attributevalue: Vec<ObjectAttributeAttributeValueModel> =
+        into_models(system_backup.object_attribute_attributevalue);
+    let attributes: Vec<AttributeModel> = into_models(system_backup.attributes);
+    let attributes_values: Vec<AttributeValueModel> = into_models(system_backup.attributes_values);
+    let rules: Vec<RuleModel> = into_models(system_backup.rules);
+    let rule_attribute_attributevalue: Vec<RuleAttributeAttributeValueModel> =
+        into_models(system_backup.rule_attribute_attributevalue);
+    let clauses: Vec<ClauseModel> = into_models(system_backup.clauses);
+    let questions: Vec<QuestionModel> = into_models(system_backup.questions);
+    let answers: Vec<AnswerModel> = into_models(system_backup.answers);
+    let rule_question_answer: Vec<RuleQuestionAnswerModel> =
+        into_models(system_backup.rule_question_answer);
+    let test_cases: Vec<TestCaseModel> = into_models(system_backup.test_cases);
+
     let txn = db.begin().await.map_err(|err| CustomErrors::SeaORMError {
         error: err,
         message: None,
@@ -159,67 +304,103 @@ where
     let mut attributevalue_map: HashMap<i32, i32> = HashMap::new();
     let mut answer_map: HashMap<i32, i32> = HashMap::new();
 
-    let new_system = copy_system(&txn, &system_backup.system).await?;
+    let new_system = copy_system(&txn, &system).await?;
     let new_system_id = new_system.id;
 
     try_join!(
-        copy_questions(
-            &txn,
-            new_system_id,
-            &system_backup.questions,
-            &mut question_map
-        ),
-        copy_attributes(
-            &txn,
-            new_system_id,
-            &system_backup.attributes,
-            &mut attribute_map
-        ),
-        copy_objects(&txn, new_system_id, &system_backup.objects, &mut object_map),
-        copy_rules(&txn, new_system_id, &system_backup.rules, &mut rule_map)
+        copy_questions(&txn, new_system_id, &questions, &mut question_map),
+        copy_attributes(&txn, new_system_id, &attributes, &mut attribute_map),
+        copy_objects(&txn, new_system_id, &objects, &mut object_map),
+        copy_rules(&txn, new_system_id, &rules, &mut rule_map)
     )?;
 
     try_join!(
         copy_attribute_values(
             &txn,
-            &system_backup.attributes_values,
+            &attributes_values,
             &attribute_map,
             &mut attributevalue_map,
         ),
-        copy_answers(&txn, &system_backup.answers, &question_map, &mut answer_map)
+        copy_answers(&txn, &answers, &question_map, &mut answer_map)
     )?;
 
-    try_join!(
-        copy_clauses(&txn, &system_backup.clauses, &rule_map, &question_map),
+    let (new_clauses, _, _, _) = try_join!(
+        copy_clauses(&txn, &clauses, &rule_map, &question_map, &answer_map),
         copy_rule_attribute_attributevalues(
             &txn,
-            &system_backup.rule_attribute_attributevalue,
+            &rule_attribute_attributevalue,
             &rule_map,
             &attribute_map,
             &attributevalue_map,
         ),
         copy_rule_question_answers(
             &txn,
-            &system_backup.rule_question_answer,
+            &rule_question_answer,
             &rule_map,
             &answer_map,
             &question_map,
         ),
         copy_object_attribute_attributevalues(
             &txn,
-            &system_backup.object_attribute_attributevalue,
+            &object_attribute_attributevalue,
             &object_map,
             &attribute_map,
             &attributevalue_map,
         )
     )?;
 
-    txn.commit()
-        .await
-        .map_err(|err| CustomErrors::SeaORMError {
+    copy_rule_conditions(&txn, &rules, &clauses, &new_clauses, &rule_map).await?;
+    copy_test_cases(
+        &txn,
+        new_system_id,
+        &test_cases,
+        &question_map,
+        &answer_map,
+        &object_map,
+        &attributevalue_map,
+    )
+    .await?;
+
+    if let (Some(image_uri), Some((_, content))) = (&system.image_uri, &system_image) {
+        write_image(image_uri, content).await?;
+    }
+
+    if let Err(err) = txn.commit().await {
+        if let Some(image_uri) = &system.image_uri {
+            remove_image(image_uri).await;
+        }
+        return Err(CustomErrors::SeaORMError {
             error: err,
             message: None,
-        })?;
+        });
+    }
 
     return Ok(new_system);
 }
+
+pub async fn export_system<C>(
+    db: &C,
+    system_id: i32,
+    user_id: i32,
+) -> Result<SystemDocumentModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let backup = load_system_backup(db, system_id).await?;
+    if backup.system.private && backup.system.user_id != user_id {
+        return Err(forbidden());
+    }
+
+    Ok(document_from_backup(&backup))
+}
+
+pub async fn import_system<C>(
+    db: &C,
+    document: SystemDocumentModel,
+    user_id: i32,
+) -> Result<SystemModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    restore_system_backup(db, backup_from_document(document, user_id)?).await
+}
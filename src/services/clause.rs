@@ -1,12 +1,93 @@
-use entity::clauses::{
-    ActiveModel as ClauseActiveModel, Column as ClauseColumn, Entity as ClauseEntity,
-    Model as ClauseModel, UpdateClauseModel,
+use crate::services::rule::check_rules_question_cycles;
+use entity::{
+    answers::{Column as AnswerColumn, Entity as AnswerEntity},
+    clauses::{
+        ActiveModel as ClauseActiveModel, Column as ClauseColumn, Entity as ClauseEntity,
+        Model as ClauseModel, UpdateClauseModel,
+    },
+    rules::{ActiveModel as RuleActiveModel, ConditionNode, Entity as RuleEntity},
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
 };
-use futures::future::try_join_all;
+use futures::{future::try_join_all, stream, StreamExt, TryStreamExt};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
-    QueryFilter, Set, TransactionTrait,
+    PaginatorTrait, QueryFilter, Set, TransactionTrait, Unchanged,
 };
+use serde_json::json;
+
+// Возвращает сравниваемое значение в каноническом виде для его типа
+pub fn parse_compared_value(
+    value_type: Valuetypeenum,
+    operator: &Operatorenum,
+    compared_value: &str,
+) -> Result<String, String> {
+    let compared_value = compared_value.trim();
+    let numeric_operator = matches!(
+        operator,
+        Operatorenum::Above
+            | Operatorenum::Below
+            | Operatorenum::NoLessThan
+            | Operatorenum::NoMoreThan
+    );
+
+    match value_type {
+        Valuetypeenum::Integer => compared_value
+            .parse::<i64>()
+            .map(|value| value.to_string())
+            .map_err(|_| format!("Значение \"{}\" не является целым числом", compared_value)),
+        Valuetypeenum::Decimal => compared_value
+            .replace(',', ".")
+            .parse::<f64>()
+            .ok()
+            .filter(|value| value.is_finite())
+            .map(|value| value.to_string())
+            .ok_or(format!(
+                "Значение \"{}\" не является числом",
+                compared_value
+            )),
+        _ if numeric_operator => Err(format!(
+            "Оператор {:?} применим только к числовым значениям",
+            operator
+        )),
+        Valuetypeenum::Boolean => match compared_value.to_lowercase().as_str() {
+            "true" => Ok("true".to_string()),
+            "false" => Ok("false".to_string()),
+            _ => Err(format!(
+                "Значение \"{}\" не является логическим (true/false)",
+                compared_value
+            )),
+        },
+        Valuetypeenum::Answer => compared_value
+            .parse::<i32>()
+            .map(|value| value.to_string())
+            .map_err(|_| format!("Значение \"{}\" не является id ответа", compared_value)),
+        Valuetypeenum::Text => Ok(compared_value.to_string()),
+    }
+}
+
+async fn check_clause<C>(db: &C, clause: &mut ClauseModel) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    clause.compared_value =
+        parse_compared_value(clause.value_type, &clause.operator, &clause.compared_value)
+            .map_err(DbErr::Custom)?;
+
+    if clause.value_type == Valuetypeenum::Answer {
+        let answers = AnswerEntity::find_by_id(clause.compared_value.parse::<i32>().unwrap_or(-1))
+            .filter(AnswerColumn::QuestionId.eq(clause.question_id))
+            .count(db)
+            .await?;
+        if answers == 0 {
+            return Err(DbErr::Custom(format!(
+                "Ответ {} не относится к вопросу {}",
+                clause.compared_value, clause.question_id
+            )));
+        }
+    }
+
+    Ok(())
+}
 
 pub async fn get_clauses<C>(db: &C, rule_id: i32) -> Result<Vec<ClauseModel>, DbErr>
 where
@@ -21,41 +102,152 @@ where
     Ok(clauses)
 }
 
-pub async fn create_clauses<C>(
+// Новые условия правила с деревом условий добавляются к корню через И
+async fn attach_to_conditions<C>(db: &C, clauses: &[ClauseModel]) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut rule_ids: Vec<i32> = clauses.iter().map(|clause| clause.rule_id).collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    for rule_id in rule_ids {
+        let Some(condition) = RuleEntity::find_by_id(rule_id)
+            .one(db)
+            .await?
+            .and_then(|rule| rule.condition)
+            .and_then(|condition| serde_json::from_value::<ConditionNode>(condition).ok())
+        else {
+            continue;
+        };
+
+        let leaves = clauses
+            .iter()
+            .filter(|clause| clause.rule_id == rule_id)
+            .map(|clause| ConditionNode::Clause(clause.id));
+        let condition = match condition {
+            ConditionNode::And(mut nodes) => {
+                nodes.extend(leaves);
+                ConditionNode::And(nodes)
+            }
+            condition => ConditionNode::And(std::iter::once(condition).chain(leaves).collect()),
+        };
+
+        RuleActiveModel {
+            id: Unchanged(rule_id),
+            condition: Set(Some(json!(condition))),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Вставка без проверки циклов, для вызова из транзакции, которая проверит их сама
+pub async fn insert_clauses<C>(
     db: &C,
     clause_info: Vec<ClauseModel>,
 ) -> Result<Vec<ClauseModel>, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    let new_clauses = clause_info.into_iter().map(|new_clause| {
+    // Вставка по одному в порядке входного массива: create_rule сопоставляет
+    // листья дерева условий с позициями в результате
+    let new_clauses = stream::iter(clause_info).then(|mut new_clause| async move {
+        check_clause(db, &mut new_clause).await?;
+
         let model = ClauseActiveModel {
             rule_id: Set(new_clause.rule_id),
             compared_value: Set(new_clause.compared_value),
             logical_group: Set(new_clause.logical_group),
             operator: Set(new_clause.operator),
             question_id: Set(new_clause.question_id),
+            value_type: Set(new_clause.value_type),
             ..Default::default()
         };
-        model.insert(db)
+        model.insert(db).await
     });
 
-    let mut result = try_join_all(new_clauses).await?;
+    let result: Vec<ClauseModel> = new_clauses.try_collect().await?;
 
-    result.sort_by_key(|clause| clause.id);
+    attach_to_conditions(db, &result).await?;
+
+    Ok(result)
+}
+
+pub async fn create_clauses<C>(
+    db: &C,
+    clause_info: Vec<ClauseModel>,
+) -> Result<Vec<ClauseModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+
+    let result = insert_clauses(&txn, clause_info).await?;
+    let rule_ids: Vec<i32> = result.iter().map(|clause| clause.rule_id).collect();
+    check_rules_question_cycles(&txn, &rule_ids).await?;
+
+    txn.commit().await?;
 
     Ok(result)
 }
 
+// Вместе с условиями из деревьев их правил убираются листья, иначе дерево со ссылкой
+// на удаленное условие не переносится при копировании и выгрузке
 pub async fn multiple_delete_clauses<C>(db: &C, clauses_ids: Vec<i32>) -> Result<u64, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    Ok(ClauseEntity::delete_many()
+    let txn = db.begin().await?;
+
+    let mut rule_ids: Vec<i32> = ClauseEntity::find()
+        .filter(ClauseColumn::Id.is_in(clauses_ids.clone()))
+        .all(&txn)
+        .await?
+        .into_iter()
+        .map(|clause| clause.rule_id)
+        .collect();
+    rule_ids.sort();
+    rule_ids.dedup();
+
+    let rows_affected = ClauseEntity::delete_many()
         .filter(ClauseColumn::Id.is_in(clauses_ids))
-        .exec(db)
+        .exec(&txn)
         .await?
-        .rows_affected)
+        .rows_affected;
+
+    for rule_id in rule_ids {
+        let Some(condition) = RuleEntity::find_by_id(rule_id)
+            .one(&txn)
+            .await?
+            .and_then(|rule| rule.condition)
+            .and_then(|condition| serde_json::from_value::<ConditionNode>(condition).ok())
+        else {
+            continue;
+        };
+        let live_clause_ids: Vec<i32> = get_clauses(&txn, rule_id)
+            .await?
+            .iter()
+            .map(|clause| clause.id)
+            .collect();
+
+        RuleActiveModel {
+            id: Unchanged(rule_id),
+            condition: Set(condition
+                .prune(&live_clause_ids)
+                .map(|condition| json!(condition))),
+            ..Default::default()
+        }
+        .update(&txn)
+        .await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(rows_affected)
 }
 
 pub async fn multiple_update_clauses<C>(
@@ -65,12 +257,55 @@ pub async fn multiple_update_clauses<C>(
 where
     C: ConnectionTrait + TransactionTrait,
 {
+    let txn = db.begin().await?;
+    let shared_txn = &txn;
+
+    // Смена вопроса в условии меняет зависимости между вопросами
+    let moved_clause_ids: Vec<i32> = clauses_info
+        .iter()
+        .filter(|clause| clause.question_id.is_some())
+        .map(|clause| clause.id)
+        .collect();
+    let moved_rule_ids: Vec<i32> = ClauseEntity::find()
+        .filter(ClauseColumn::Id.is_in(moved_clause_ids))
+        .all(shared_txn)
+        .await?
+        .into_iter()
+        .map(|clause| clause.rule_id)
+        .collect();
+
     let new_clauses = clauses_info
         .into_iter()
-        .map(|clause_for_update| clause_for_update.into_active_model().update(db));
+        .map(|clause_for_update| async move {
+            let mut clause = ClauseEntity::find_by_id(clause_for_update.id)
+                .one(shared_txn)
+                .await?
+                .ok_or(DbErr::Custom("Условие не найдено".to_string()))?;
+            if let Some(compared_value) = &clause_for_update.compared_value {
+                clause.compared_value = compared_value.clone();
+            }
+            if let Some(operator) = &clause_for_update.operator {
+                clause.operator = operator.clone();
+            }
+            if let Some(question_id) = clause_for_update.question_id {
+                clause.question_id = question_id;
+            }
+            if let Some(value_type) = clause_for_update.value_type {
+                clause.value_type = value_type;
+            }
+            check_clause(shared_txn, &mut clause).await?;
+
+            let mut model = clause_for_update.into_active_model();
+            model.compared_value = Set(clause.compared_value);
+            model.update(shared_txn).await
+        });
 
     let mut result = try_join_all(new_clauses).await?;
     result.sort_by_key(|clause| clause.id);
 
+    check_rules_question_cycles(shared_txn, &moved_rule_ids).await?;
+
+    txn.commit().await?;
+
     Ok(result)
 }
@@ -0,0 +1,384 @@
+use crate::{
+    error::CustomErrors,
+    models::{
+        consultation::ConsultationStepModel,
+        inference::{GivenAnswerModel, GoalStatus},
+    },
+    services::{
+        attribute::get_attributes,
+        system::{get_ready_to_start_system, get_system},
+    },
+    utils::{inference::InferenceEngine, policy::forbidden},
+};
+use chrono::Local;
+use entity::{
+    consultations::{
+        ActiveModel as ConsultationActiveModel, Column as ConsultationColumn,
+        Entity as ConsultationEntity, Model as ConsultationModel, NewConsultationModel,
+    },
+    histories::ActiveModel as HistoryActiveModel,
+    sea_orm_active_enums::Consultationmodeenum,
+};
+use http::StatusCode;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QuerySelect,
+    Set, TransactionTrait, Unchanged,
+};
+use serde_json::{json, Map, Value};
+
+fn db_error(err: DbErr) -> CustomErrors {
+    CustomErrors::SeaORMError {
+        error: err,
+        message: None,
+    }
+}
+
+fn not_found(error: &str) -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::NOT_FOUND,
+        error: error.to_string(),
+    }
+}
+
+fn bad_request(error: &str) -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error: error.to_string(),
+    }
+}
+
+// Консультация чужого пользователя не отличается от несуществующей.
+// Для изменения строка блокируется до конца транзакции (SELECT ... FOR UPDATE),
+// поэтому параллельные ответы и завершения одной консультации идут по очереди
+async fn find_consultation<C>(
+    db: &C,
+    system_id: i32,
+    consultation_id: i32,
+    user_id: i32,
+    for_update: bool,
+) -> Result<ConsultationModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut query = ConsultationEntity::find_by_id(consultation_id)
+        .filter(ConsultationColumn::SystemId.eq(system_id))
+        .filter(ConsultationColumn::UserId.eq(user_id));
+    if for_update {
+        query = query.lock_exclusive();
+    }
+
+    query
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| not_found("Консультация не найдена"))
+}
+
+fn given_answers(consultation: &ConsultationModel) -> Result<Vec<GivenAnswerModel>, DbErr> {
+    serde_json::from_value(consultation.answers.clone())
+        .map_err(|err| DbErr::Custom(err.to_string()))
+}
+
+fn consultation_step(
+    engine: &InferenceEngine,
+    consultation: ConsultationModel,
+) -> Result<ConsultationStepModel, DbErr> {
+    let given = given_answers(&consultation)?;
+    let memory = engine.run(engine.given_facts(&given));
+
+    let (question, goal_status) = match consultation.mode {
+        Consultationmodeenum::Forward => (engine.next_question(&memory), None),
+        Consultationmodeenum::Backward => {
+            let goal = engine.goal_values(
+                consultation.goal_object_id,
+                consultation.goal_attribute_value_id,
+            );
+            match engine.goal_status(&goal, &memory) {
+                GoalStatus::Open => (
+                    engine.next_goal_question(&goal, &memory),
+                    Some(GoalStatus::Open),
+                ),
+                status => (None, Some(status)),
+            }
+        }
+    };
+    let question = if consultation.finished {
+        None
+    } else {
+        question.cloned()
+    };
+
+    Ok(ConsultationStepModel {
+        consultation,
+        question,
+        goal_status,
+        answered: given.len(),
+        total: engine.questions.len(),
+        result: engine.result(memory),
+    })
+}
+
+async fn finish<C>(
+    db: &C,
+    engine: &InferenceEngine,
+    consultation: ConsultationModel,
+) -> Result<ConsultationModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let given = given_answers(&consultation)?;
+    let result = engine.infer(&given);
+
+    let results: Map<String, Value> = result
+        .objects
+        .into_iter()
+        .map(|object| (object.name, json!(object.percent)))
+        .collect();
+
+    let txn = db.begin().await?;
+
+    let history = HistoryActiveModel {
+        system_id: Set(consultation.system_id),
+        user_id: Set(consultation.user_id),
+        answered_questions: Set(format!("{}/{}", given.len(), engine.questions.len())),
+        answers: Set(Some(json!(given))),
+        results: Set(Value::Object(results)),
+        mode: Set(consultation.mode),
+        goal_object_id: Set(consultation.goal_object_id),
+        goal_attribute_value_id: Set(consultation.goal_attribute_value_id),
+        explanation: Set(Some(json!(engine.explain(&given)))),
+        started_at: Set(consultation.started_at),
+        finished_at: Set(Local::now().naive_local()),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+
+    let finished_consultation = ConsultationActiveModel {
+        id: Unchanged(consultation.id),
+        finished: Set(true),
+        history_id: Set(Some(history.id)),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await?;
+
+    txn.commit().await?;
+
+    Ok(finished_consultation)
+}
+
+async fn step_or_finish<C>(
+    db: &C,
+    engine: &InferenceEngine,
+    consultation: ConsultationModel,
+) -> Result<ConsultationStepModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let step = consultation_step(engine, consultation)?;
+    if step.question.is_some() || step.consultation.finished {
+        return Ok(step);
+    }
+
+    let finished_consultation = finish(db, engine, step.consultation).await?;
+    consultation_step(engine, finished_consultation)
+}
+
+async fn check_goal<C>(
+    db: &C,
+    engine: &InferenceEngine,
+    system_id: i32,
+    consultation_info: &NewConsultationModel,
+) -> Result<(), CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    match (
+        consultation_info.mode,
+        consultation_info.goal_object_id,
+        consultation_info.goal_attribute_value_id,
+    ) {
+        (Consultationmodeenum::Forward, None, None) => Ok(()),
+        (Consultationmodeenum::Forward, _, _) => {
+            Err(bad_request("Цель указывается только для обратного вывода"))
+        }
+        (Consultationmodeenum::Backward, Some(object_id), None) => {
+            if engine.objects.iter().any(|object| object.id == object_id) {
+                Ok(())
+            } else {
+                Err(not_found("Объект не найден"))
+            }
+        }
+        (Consultationmodeenum::Backward, None, Some(attribute_value_id)) => {
+            let attributes = get_attributes(db, system_id).await.map_err(db_error)?;
+            if attributes
+                .iter()
+                .flat_map(|attribute| attribute.values.iter())
+                .any(|value| value.id == attribute_value_id)
+            {
+                Ok(())
+            } else {
+                Err(not_found("Значение атрибута не найдено"))
+            }
+        }
+        (Consultationmodeenum::Backward, _, _) => Err(bad_request(
+            "Для обратного вывода укажите либо объект, либо значение атрибута",
+        )),
+    }
+}
+
+async fn ready_engine<C>(db: &C, system_id: i32) -> Result<InferenceEngine, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(InferenceEngine::from(
+        get_ready_to_start_system(db, system_id)
+            .await
+            .map_err(db_error)?,
+    ))
+}
+
+// Закрытую систему проходит только ее владелец
+pub async fn create_consultation<C>(
+    db: &C,
+    system_id: i32,
+    user_id: i32,
+    consultation_info: NewConsultationModel,
+) -> Result<ConsultationStepModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let system = get_system(db, system_id).await.map_err(|err| match err {
+        DbErr::Custom(error) => not_found(&error),
+        err => db_error(err),
+    })?;
+    if system.private && system.user_id != user_id {
+        return Err(forbidden());
+    }
+    let engine = ready_engine(db, system_id).await?;
+    check_goal(db, &engine, system_id, &consultation_info).await?;
+
+    let consultation = ConsultationActiveModel {
+        system_id: Set(system_id),
+        user_id: Set(user_id),
+        answers: Set(json!([])),
+        mode: Set(consultation_info.mode),
+        goal_object_id: Set(consultation_info.goal_object_id),
+        goal_attribute_value_id: Set(consultation_info.goal_attribute_value_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(db_error)?;
+
+    step_or_finish(db, &engine, consultation)
+        .await
+        .map_err(db_error)
+}
+
+pub async fn get_consultation<C>(
+    db: &C,
+    system_id: i32,
+    consultation_id: i32,
+    user_id: i32,
+) -> Result<ConsultationStepModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let consultation = find_consultation(db, system_id, consultation_id, user_id, false).await?;
+    let engine = ready_engine(db, system_id).await?;
+
+    consultation_step(&engine, consultation).map_err(db_error)
+}
+
+pub async fn answer_consultation<C>(
+    db: &C,
+    system_id: i32,
+    consultation_id: i32,
+    user_id: i32,
+    answer: GivenAnswerModel,
+) -> Result<ConsultationStepModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await.map_err(db_error)?;
+
+    let consultation = find_consultation(&txn, system_id, consultation_id, user_id, true).await?;
+    if consultation.finished {
+        return Err(CustomErrors::StringError {
+            status: StatusCode::CONFLICT,
+            error: "Консультация уже завершена".to_string(),
+        });
+    }
+
+    let engine = ready_engine(&txn, system_id).await?;
+    let question = engine
+        .questions
+        .iter()
+        .find(|question| question.id == answer.question_id)
+        .ok_or_else(|| not_found("Вопрос не найден"))?;
+    match (answer.answer_id, &answer.value) {
+        (Some(answer_id), _) => {
+            if !question.answers.iter().any(|option| option.id == answer_id) {
+                return Err(not_found("Ответ не найден"));
+            }
+        }
+        (None, Some(_)) => (),
+        (None, None) => return Err(bad_request("Ответ не указан")),
+    }
+    if answer
+        .certainty_factor
+        .is_some_and(|certainty_factor| !(0.0..=1.0).contains(&certainty_factor))
+    {
+        return Err(bad_request(
+            "Коэффициент уверенности ответа должен быть от 0 до 1",
+        ));
+    }
+
+    let mut given = given_answers(&consultation).map_err(db_error)?;
+    given.retain(|given_answer| given_answer.question_id != answer.question_id);
+    given.push(answer);
+
+    let updated_consultation = ConsultationActiveModel {
+        id: Unchanged(consultation.id),
+        answers: Set(json!(given)),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await
+    .map_err(db_error)?;
+
+    let step = step_or_finish(&txn, &engine, updated_consultation)
+        .await
+        .map_err(db_error)?;
+    txn.commit().await.map_err(db_error)?;
+
+    Ok(step)
+}
+
+// Повторное завершение возвращает тот же результат и второй записи истории не создает
+pub async fn finish_consultation<C>(
+    db: &C,
+    system_id: i32,
+    consultation_id: i32,
+    user_id: i32,
+) -> Result<ConsultationStepModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await.map_err(db_error)?;
+
+    let consultation = find_consultation(&txn, system_id, consultation_id, user_id, true).await?;
+    let engine = ready_engine(&txn, system_id).await?;
+    let consultation = if consultation.finished {
+        consultation
+    } else {
+        finish(&txn, &engine, consultation)
+            .await
+            .map_err(db_error)?
+    };
+    txn.commit().await.map_err(db_error)?;
+
+    consultation_step(&engine, consultation).map_err(db_error)
+}
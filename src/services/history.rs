@@ -1,12 +1,21 @@
+use crate::{
+    error::CustomErrors,
+    models::inference::{ExplanationModel, GivenAnswerModel},
+    utils::policy::{forbidden, resolve_owners, Resource},
+};
 use chrono::Local;
 use entity::{
-    histories::{Entity as HistoryEntity, HistoryWithSystem, Model as HistoryModel},
+    histories::{
+        ActiveModel as HistoryActiveModel, Column as HistoryColumn, Entity as HistoryEntity,
+        HistoryWithSystem, Model as HistoryModel,
+    },
     systems::{Column as SystemColumn, Entity as SystemEntity},
     users::{Column as UserColumn, Entity as UserEntity},
 };
+use http::StatusCode;
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::NotSet, ColumnTrait, ConnectionTrait, DbErr, EntityTrait,
-    IntoActiveModel, QueryFilter, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
 };
 
 pub async fn get_histories<C>(
@@ -41,6 +50,9 @@ where
                 system,
                 answered_questions: history.answered_questions,
                 results: history.results,
+                mode: history.mode,
+                goal_object_id: history.goal_object_id,
+                goal_attribute_value_id: history.goal_attribute_value_id,
                 started_at: history.started_at,
                 finished_at: history.finished_at,
             })
@@ -52,40 +64,123 @@ where
     Ok(result)
 }
 
+// Устаревший путь: прежний фронтенд сохраняет историю сам. Пользователь берется из сессии,
+// объяснение не принимается - его записывает только завершение консультации
 pub async fn create_history<C>(
     db: &C,
+    user_id: i32,
     history_info: HistoryModel,
-) -> Result<HistoryWithSystem, DbErr>
+) -> Result<HistoryWithSystem, CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    let mut new_history_active_model = history_info.into_active_model();
-    new_history_active_model.id = NotSet;
-    let new_history = new_history_active_model.insert(db).await?;
+    let db_error = |err: DbErr| CustomErrors::SeaORMError {
+        error: err,
+        message: None,
+    };
 
-    let system = SystemEntity::find_by_id(new_history.system_id)
+    let system = SystemEntity::find_by_id(history_info.system_id)
         .one(db)
-        .await?
-        .ok_or(DbErr::Custom("Ошибка создания записи истории".to_string()))?;
+        .await
+        .map_err(db_error)?
+        .ok_or(CustomErrors::StringError {
+            status: StatusCode::NOT_FOUND,
+            error: "Система не найдена".to_string(),
+        })?;
+    if system.private && system.user_id != user_id {
+        return Err(forbidden());
+    }
+    if let Some(answers) = &history_info.answers {
+        serde_json::from_value::<Vec<GivenAnswerModel>>(answers.clone()).map_err(|_| {
+            CustomErrors::StringError {
+                status: StatusCode::BAD_REQUEST,
+                error: "Некорректный список ответов".to_string(),
+            }
+        })?;
+    }
+
+    let now = Local::now().naive_local();
+    let history = HistoryActiveModel {
+        system_id: Set(system.id),
+        user_id: Set(user_id),
+        answered_questions: Set(history_info.answered_questions),
+        answers: Set(history_info.answers),
+        results: Set(history_info.results),
+        mode: Set(history_info.mode),
+        goal_object_id: Set(history_info.goal_object_id),
+        goal_attribute_value_id: Set(history_info.goal_attribute_value_id),
+        explanation: Set(None),
+        started_at: Set(now),
+        finished_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .map_err(db_error)?;
 
-    let result = HistoryWithSystem {
-        id: new_history.id,
+    Ok(HistoryWithSystem {
+        id: history.id,
         system,
-        answered_questions: new_history.answered_questions,
-        results: new_history.results,
-        started_at: Local::now().naive_local(),
-        finished_at: Local::now().naive_local(),
+        answered_questions: history.answered_questions,
+        results: history.results,
+        mode: history.mode,
+        goal_object_id: history.goal_object_id,
+        goal_attribute_value_id: history.goal_attribute_value_id,
+        started_at: history.started_at,
+        finished_at: history.finished_at,
+    })
+}
+
+// Объяснение видят только проходивший консультацию и владелец системы
+pub async fn get_history_explanation<C>(
+    db: &C,
+    user_id: i32,
+    history_id: i32,
+) -> Result<ExplanationModel, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let db_error = |err: DbErr| CustomErrors::SeaORMError {
+        error: err,
+        message: None,
     };
 
-    Ok(result)
+    let history = HistoryEntity::find_by_id(history_id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or(CustomErrors::StringError {
+            status: StatusCode::NOT_FOUND,
+            error: "Запись истории не найдена".to_string(),
+        })?;
+    if history.user_id != user_id
+        && !resolve_owners(db, &[Resource::System(history.system_id)])
+            .await?
+            .contains(&user_id)
+    {
+        return Err(forbidden());
+    }
+
+    let explanation = history.explanation.ok_or(db_error(DbErr::Custom(
+        "Для записи истории нет объяснения вывода".to_string(),
+    )))?;
+
+    serde_json::from_value(explanation).map_err(|err| db_error(DbErr::Custom(err.to_string())))
 }
 
-pub async fn delete_history<C>(db: &C, history_id: i32) -> Result<u64, DbErr>
+// Удалить запись истории может только тот, кто проходил консультацию
+pub async fn delete_history<C>(db: &C, user_id: i32, history_id: i32) -> Result<u64, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    Ok(HistoryEntity::delete_by_id(history_id)
+    let result = HistoryEntity::delete_many()
+        .filter(HistoryColumn::Id.eq(history_id))
+        .filter(HistoryColumn::UserId.eq(user_id))
         .exec(db)
-        .await?
-        .rows_affected)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(DbErr::Custom("Запись истории не найдена".to_string()));
+    }
+
+    Ok(result.rows_affected)
 }
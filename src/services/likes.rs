@@ -18,13 +18,18 @@ where
         .await?)
 }
 
-pub async fn create_like<C>(db: &C, like_info: LikesModel) -> Result<LikesModel, DbErr>
+// Лайк всегда ставится от имени текущего пользователя, user_id из запроса не используется
+pub async fn create_like<C>(
+    db: &C,
+    user_id: i32,
+    like_info: LikesModel,
+) -> Result<LikesModel, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
     let model = LikesActiveModel {
         id: NotSet,
-        user_id: Set(like_info.user_id),
+        user_id: Set(user_id),
         system_id: Set(like_info.system_id),
         ..Default::default()
     };
@@ -40,25 +45,25 @@ where
     Ok(result)
 }
 
-pub async fn delete_like<C>(db: &C, like_id: i32) -> Result<u64, DbErr>
+pub async fn delete_like<C>(db: &C, user_id: i32, like_id: i32) -> Result<u64, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    let like = LikesEntity::find()
+    let like_model = LikesEntity::find()
         .filter(LikesColumn::Id.eq(like_id))
+        .filter(LikesColumn::UserId.eq(user_id))
         .one(db)
-        .await?;
+        .await?
+        .ok_or(DbErr::Custom("Лайк не найден".to_string()))?;
 
-    if let Some(like_model) = like {
-        like_model.clone().delete(db).await?;
+    like_model.clone().delete(db).await?;
 
-        db.execute(Statement::from_sql_and_values(
-            DatabaseBackend::Postgres,
-            "UPDATE \"public\".\"systems\" SET stars = stars - 1 WHERE id = $1;",
-            [like_model.system_id.into()],
-        ))
-        .await?;
-    }
+    db.execute(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "UPDATE \"public\".\"systems\" SET stars = stars - 1 WHERE id = $1;",
+        [like_model.system_id.into()],
+    ))
+    .await?;
 
     Ok(like_id as u64)
 }
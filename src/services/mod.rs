@@ -1,8 +1,10 @@
+pub mod access_token;
 pub mod answer;
 pub mod attribute;
 pub mod attribute_value;
 pub mod backup;
 pub mod clause;
+pub mod consultation;
 pub mod history;
 pub mod likes;
 pub mod object;
@@ -11,5 +13,8 @@ pub mod question;
 pub mod rule;
 pub mod rule_attribute_attributevalue;
 pub mod rule_question_answer;
+pub mod session;
 pub mod system;
+pub mod test_case;
+pub mod two_factor;
 pub mod user;
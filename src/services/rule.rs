@@ -1,6 +1,18 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
-use crate::services::rule_attribute_attributevalue::create_rule_attribute_attributevalues;
+use crate::{
+    services::{
+        attribute::get_attributes, question::get_questions,
+        rule_attribute_attributevalue::create_rule_attribute_attributevalues, system::get_system,
+    },
+    utils::{
+        rule_dsl::{RuleDslParser, RuleDslPrinter},
+        topological_sort::topological_sort,
+    },
+};
 use entity::{
     clauses::{Entity as ClauseEntity, Model as ClauseModel},
     rule_attribute_attributevalue::{
@@ -8,19 +20,121 @@ use entity::{
     },
     rule_question_answer::{Entity as RuleQuestionAnswerEntity, Model as RuleQuestionAnswerModel},
     rules::{
-        ActiveModel as RuleActiveModel, Column as RuleColumn, Entity as RuleEntity,
-        NewRuleWithClausesAndEffects, RuleWithClausesAndEffects,
+        ActiveModel as RuleActiveModel, Column as RuleColumn, ConditionNode, Entity as RuleEntity,
+        Model as RuleModel, NewConditionNode, NewRuleWithClausesAndEffects,
+        RuleWithClausesAndEffects,
     },
 };
 use futures::future::try_join_all;
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, LoaderTrait, QueryFilter,
-    Set, TransactionTrait,
+    Set, TransactionTrait, Unchanged,
 };
+use serde_json::json;
 
 use tokio::try_join;
 
-use super::{clause::create_clauses, rule_question_answer::create_rule_question_answers};
+use super::{clause::insert_clauses, rule_question_answer::insert_rule_question_answers};
+
+// Дерево без листей удаленных условий; для правил без дерева - по logical_group
+pub fn rule_condition(rule: &RuleModel, clauses: &[ClauseModel]) -> Option<ConditionNode> {
+    let clause_ids: Vec<i32> = clauses.iter().map(|clause| clause.id).collect();
+
+    match rule
+        .condition
+        .clone()
+        .and_then(|condition| serde_json::from_value::<ConditionNode>(condition).ok())
+    {
+        Some(condition) => condition.prune(&clause_ids),
+        None => ConditionNode::from_groups(clauses).prune(&clause_ids),
+    }
+}
+
+// Условия вставлены в порядке входного массива, поэтому индекс листа - позиция в clauses
+fn new_rule_condition(
+    condition: Option<NewConditionNode>,
+    clauses: &[ClauseModel],
+) -> Result<ConditionNode, DbErr> {
+    let clause_ids: Vec<i32> = clauses.iter().map(|clause| clause.id).collect();
+    match condition {
+        Some(condition) => condition.resolve(&clause_ids).ok_or(DbErr::Custom(
+            "Дерево условий ссылается на несуществующее условие".to_string(),
+        )),
+        None => Ok(ConditionNode::from_groups(clauses)),
+    }
+}
+
+// Выводимый правилом вопрос зависит от вопросов из условий этого правила
+fn question_dependencies(rules: &[RuleWithClausesAndEffects]) -> HashMap<i32, HashSet<i32>> {
+    let mut dependencies: HashMap<i32, HashSet<i32>> = HashMap::new();
+    rules
+        .iter()
+        .filter(|rule| !rule.attribute_rule)
+        .for_each(|rule| {
+            rule.rule_question_answer_ids.iter().for_each(|effect| {
+                dependencies
+                    .entry(effect.question_id)
+                    .or_default()
+                    .extend(rule.clauses.iter().map(|clause| clause.question_id));
+            })
+        });
+    dependencies
+}
+
+pub fn question_cycle_error(cycle: &[i32], rules: &[RuleWithClausesAndEffects]) -> DbErr {
+    let edges: Vec<String> = cycle
+        .iter()
+        .zip(cycle.iter().cycle().skip(1))
+        .map(|(from, to)| {
+            let rule_ids: Vec<String> = rules
+                .iter()
+                .filter(|rule| {
+                    !rule.attribute_rule
+                        && rule
+                            .rule_question_answer_ids
+                            .iter()
+                            .any(|effect| effect.question_id == *from)
+                        && rule.clauses.iter().any(|clause| clause.question_id == *to)
+                })
+                .map(|rule| rule.id.to_string())
+                .collect();
+            format!("{} -> {} (правила {})", from, to, rule_ids.join(", "))
+        })
+        .collect();
+
+    DbErr::Custom(format!(
+        "Циклическая зависимость вопросов: {}",
+        edges.join("; ")
+    ))
+}
+
+fn check_question_cycles(rules: &[RuleWithClausesAndEffects]) -> Result<(), DbErr> {
+    topological_sort(&question_dependencies(rules))
+        .map(|_| ())
+        .map_err(|cycle| question_cycle_error(&cycle, rules))
+}
+
+// Проверяет системы измененных правил; вызывается в транзакции изменения до ее фиксации
+pub async fn check_rules_question_cycles<C>(db: &C, rule_ids: &[i32]) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut system_ids: Vec<i32> = RuleEntity::find()
+        .filter(RuleColumn::Id.is_in(rule_ids.to_vec()))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|rule| rule.system_id)
+        .collect();
+    system_ids.sort();
+    system_ids.dedup();
+
+    for system_id in system_ids {
+        check_question_cycles(&get_rules(db, system_id).await?)?;
+    }
+
+    Ok(())
+}
 
 pub async fn get_rules<C>(db: &C, system_id: i32) -> Result<Vec<RuleWithClausesAndEffects>, DbErr>
 where
@@ -52,6 +166,8 @@ where
                     id: _rule.id,
                     system_id: _rule.system_id,
                     attribute_rule: _rule.attribute_rule,
+                    certainty_factor: _rule.certainty_factor,
+                    condition: rule_condition(&_rule, &_clauses),
                     clauses: _clauses,
                     rule_question_answer_ids: _answers,
                     rule_attribute_attributevalue_ids: _attributesvalues,
@@ -76,9 +192,15 @@ where
     let new_rules = rule_info.into_iter().map(|rule_raw| {
         let txn_cloned = Arc::clone(&shared_txn);
         async move {
+            if !(-1.0..=1.0).contains(&rule_raw.certainty_factor) {
+                return Err(DbErr::Custom(
+                    "Коэффициент уверенности правила должен быть от -1 до 1".to_string(),
+                ));
+            }
             let new_rule = RuleActiveModel {
                 system_id: Set(rule_raw.system_id),
                 attribute_rule: Set(rule_raw.attribute_rule),
+                certainty_factor: Set(rule_raw.certainty_factor),
                 ..Default::default()
             };
             let created_rule = new_rule.insert(*txn_cloned).await?;
@@ -93,6 +215,7 @@ where
                     logical_group: clause.logical_group,
                     operator: clause.operator,
                     question_id: clause.question_id,
+                    value_type: clause.value_type,
                 })
                 .collect();
             let answers_to_create = rule_raw
@@ -117,15 +240,26 @@ where
                 .collect();
 
             let (clauses, rule_question_answers, rule_attribute_attributevalues) = try_join!(
-                create_clauses(*txn_cloned, clauses_to_create),
-                create_rule_question_answers(*txn_cloned, answers_to_create),
+                insert_clauses(*txn_cloned, clauses_to_create),
+                insert_rule_question_answers(*txn_cloned, answers_to_create),
                 create_rule_attribute_attributevalues(*txn_cloned, attributevalues_to_create)
             )?;
 
+            let condition = new_rule_condition(rule_raw.condition, &clauses)?;
+            let created_rule = RuleActiveModel {
+                id: Unchanged(created_rule.id),
+                condition: Set(Some(json!(condition))),
+                ..Default::default()
+            }
+            .update(*txn_cloned)
+            .await?;
+
             Ok::<RuleWithClausesAndEffects, DbErr>(RuleWithClausesAndEffects {
                 id: created_rule.id,
                 system_id: created_rule.system_id,
                 attribute_rule: created_rule.attribute_rule,
+                certainty_factor: created_rule.certainty_factor,
+                condition: rule_condition(&created_rule, &clauses),
                 clauses,
                 rule_question_answer_ids: rule_question_answers,
                 rule_attribute_attributevalue_ids: rule_attribute_attributevalues,
@@ -136,6 +270,47 @@ where
     let mut result = try_join_all(new_rules).await?;
     result.sort_by_key(|rule| rule.id);
 
+    let rule_ids: Vec<i32> = result.iter().map(|rule| rule.id).collect();
+    check_rules_question_cycles(&txn, &rule_ids).await?;
+
+    txn.commit().await?;
+
+    Ok(result)
+}
+
+pub async fn get_rules_dsl<C>(db: &C, system_id: i32) -> Result<String, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    get_system(db, system_id).await?;
+    let (rules, questions, attributes) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_attributes(db, system_id)
+    )?;
+
+    Ok(RuleDslPrinter::new(&questions, &attributes).print(&rules))
+}
+
+// Текст описывает все правила системы, поэтому прежние правила заменяются целиком
+pub async fn replace_rules_from_dsl<C>(
+    db: &C,
+    system_id: i32,
+    source: &str,
+) -> Result<Vec<RuleWithClausesAndEffects>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (questions, attributes) =
+        try_join!(get_questions(db, system_id), get_attributes(db, system_id))?;
+    let rules = RuleDslParser::new(system_id, &questions, &attributes).parse(source)?;
+
+    let txn = db.begin().await?;
+    RuleEntity::delete_many()
+        .filter(RuleColumn::SystemId.eq(system_id))
+        .exec(&txn)
+        .await?;
+    let result = create_rule(&txn, rules).await?;
     txn.commit().await?;
 
     Ok(result)
@@ -151,3 +326,138 @@ where
         .await?
         .rows_affected)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::clause;
+    use entity::{
+        answers, attributes, attributesvalues, questions,
+        sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+        systems, users,
+    };
+    use sea_orm::Database;
+
+    #[test]
+    fn condition_leaves_follow_clause_positions() {
+        // Ответ проверяется лишним запросом, поэтому id условий могут идти не по порядку входа
+        let clauses = vec![
+            clause(12, 1, Operatorenum::Equal, Valuetypeenum::Answer, "11"),
+            clause(10, 2, Operatorenum::Above, Valuetypeenum::Integer, "5"),
+            clause(11, 3, Operatorenum::Equal, Valuetypeenum::Text, "да"),
+        ];
+        let condition = NewConditionNode::Or(vec![
+            NewConditionNode::Clause(0),
+            NewConditionNode::Not(Box::new(NewConditionNode::And(vec![
+                NewConditionNode::Clause(1),
+                NewConditionNode::Clause(2),
+            ]))),
+        ]);
+
+        assert_eq!(
+            new_rule_condition(Some(condition), &clauses).unwrap(),
+            ConditionNode::Or(vec![
+                ConditionNode::Clause(12),
+                ConditionNode::Not(Box::new(ConditionNode::And(vec![
+                    ConditionNode::Clause(10),
+                    ConditionNode::Clause(11),
+                ]))),
+            ])
+        );
+        assert!(new_rule_condition(Some(NewConditionNode::Clause(3)), &clauses).is_err());
+    }
+
+    // Текст проходит через create_rule и обратно; запуск: cargo test -- --ignored
+    #[tokio::test]
+    #[ignore = "нужна база PostgreSQL из DATABASE_URL"]
+    async fn dsl_round_trip_through_database() {
+        let db = Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let txn = db.begin().await.unwrap();
+
+        let user = users::ActiveModel {
+            email: Set("dsl@test.local".to_string()),
+            username: Set("dsl".to_string()),
+            first_name: Set("dsl".to_string()),
+            last_name: Set("dsl".to_string()),
+            password: Set(String::new()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .unwrap();
+        let system = systems::ActiveModel {
+            user_id: Set(user.id),
+            name: Set("DSL".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .unwrap();
+        for (body, with_chooses) in [("Температура", false), ("Кашель?", true)] {
+            let question = questions::ActiveModel {
+                system_id: Set(system.id),
+                body: Set(body.to_string()),
+                with_chooses: Set(with_chooses),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await
+            .unwrap();
+            for answer in if with_chooses {
+                vec!["да", "нет"]
+            } else {
+                vec![]
+            } {
+                answers::ActiveModel {
+                    question_id: Set(question.id),
+                    body: Set(answer.to_string()),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await
+                .unwrap();
+            }
+        }
+        let attribute = attributes::ActiveModel {
+            system_id: Set(system.id),
+            name: Set("Диагноз".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .unwrap();
+        attributesvalues::ActiveModel {
+            attribute_id: Set(attribute.id),
+            value: Set("Грипп".to_string()),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .unwrap();
+
+        // Условия разных типов под OR и NOT, ссылка на ответ - не первая
+        let source = "IF \"Температура\" ABOVE 38 AND NOT (\"Кашель?\" = \"нет\" OR \"Температура\" BELOW 36.6)\nTHEN \"Диагноз\" = \"Грипп\" CF 0.8\n";
+        let rules = replace_rules_from_dsl(&txn, system.id, source)
+            .await
+            .unwrap();
+        let printed = get_rules_dsl(&txn, system.id).await.unwrap();
+        txn.rollback().await.unwrap();
+
+        let value_types: Vec<Valuetypeenum> = rules[0]
+            .clauses
+            .iter()
+            .map(|clause| clause.value_type)
+            .collect();
+        assert_eq!(
+            value_types,
+            vec![
+                Valuetypeenum::Integer,
+                Valuetypeenum::Answer,
+                Valuetypeenum::Decimal
+            ]
+        );
+        assert_eq!(printed, source);
+    }
+}
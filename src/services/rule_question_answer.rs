@@ -1,3 +1,4 @@
+use crate::services::rule::check_rules_question_cycles;
 use entity::rule_question_answer::{
     ActiveModel as RuleQuestionAnswerActiveModel, Column as RuleQuestionAnswerColumn,
     Entity as RuleQuestionAnswerEntity, Model as RuleQuestionAnswerModel,
@@ -8,7 +9,8 @@ use sea_orm::{
     TransactionTrait,
 };
 
-pub async fn create_rule_question_answers<C>(
+// Вставка без проверки циклов, для вызова из транзакции, которая проверит их сама
+pub async fn insert_rule_question_answers<C>(
     db: &C,
     rule_question_answer_info: Vec<RuleQuestionAnswerModel>,
 ) -> Result<Vec<RuleQuestionAnswerModel>, DbErr>
@@ -34,6 +36,27 @@ where
     Ok(result)
 }
 
+pub async fn create_rule_question_answers<C>(
+    db: &C,
+    rule_question_answer_info: Vec<RuleQuestionAnswerModel>,
+) -> Result<Vec<RuleQuestionAnswerModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let txn = db.begin().await?;
+
+    let result = insert_rule_question_answers(&txn, rule_question_answer_info).await?;
+    let rule_ids: Vec<i32> = result
+        .iter()
+        .map(|rule_question_answer| rule_question_answer.rule_id)
+        .collect();
+    check_rules_question_cycles(&txn, &rule_ids).await?;
+
+    txn.commit().await?;
+
+    Ok(result)
+}
+
 pub async fn multiple_delete_rule_question_answers<C>(
     db: &C,
     rule_question_answers_ids: Vec<i32>,
@@ -0,0 +1,171 @@
+use crate::{
+    constants::{COOKIE_NAME, SESSION_LIFETIME_DAYS, SESSION_TOKEN_LEN},
+    models::session::{SessionClientModel, UserSessionModel},
+    utils::generate_random_string::generate_random_string,
+};
+use chrono::{Duration as ChronoDuration, Utc};
+use entity::{
+    sessions::{
+        ActiveModel as SessionActiveModel, Column as SessionColumn, Entity as SessionEntity,
+        Model as SessionModel,
+    },
+    users::{Entity as UserEntity, Model as UserModel},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
+use sha2::{Digest, Sha256};
+use tower_cookies::{
+    cookie::{
+        time::{Duration, OffsetDateTime},
+        SameSite,
+    },
+    Cookie, Cookies, Key,
+};
+
+// В базе лежит SHA-256 токена, поэтому по утекшей таблице сессий войти нельзя.
+// Токен случайный и длинный - медленный хэш вроде Argon2 не нужен, и сессия ищется по хэшу
+fn hash_session_token(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+// Новая сессия пользователя. В cookie кладется случайный токен сессии, а не id пользователя,
+// поэтому сессию можно отозвать на сервере
+pub async fn create_session<C>(
+    db: &C,
+    user_id: i32,
+    client: SessionClientModel,
+    cookie: &Cookies,
+    cookie_key: &Key,
+) -> Result<SessionModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let now = Utc::now().naive_utc();
+    SessionEntity::delete_many()
+        .filter(SessionColumn::UserId.eq(user_id))
+        .filter(SessionColumn::ExpiresAt.lte(now))
+        .exec(db)
+        .await?;
+
+    let token = generate_random_string(SESSION_TOKEN_LEN);
+    let session = SessionActiveModel {
+        user_id: Set(user_id),
+        token_hash: Set(hash_session_token(&token)),
+        user_agent: Set(client.user_agent),
+        ip: Set(client.ip),
+        created_at: Set(now),
+        expires_at: Set(now + ChronoDuration::days(SESSION_LIFETIME_DAYS)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    cookie.private(cookie_key).add(
+        Cookie::build((COOKIE_NAME, token))
+            .path("/")
+            .secure(true)
+            .http_only(true)
+            .same_site(SameSite::Strict)
+            .expires(OffsetDateTime::now_utc() + Duration::days(SESSION_LIFETIME_DAYS))
+            .into(),
+    );
+
+    Ok(session)
+}
+
+// Действующая сессия по токену из cookie вместе с ее пользователем
+pub async fn find_session<C>(
+    db: &C,
+    token: &str,
+) -> Result<Option<(SessionModel, UserModel)>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(SessionEntity::find()
+        .filter(SessionColumn::TokenHash.eq(hash_session_token(token)))
+        .filter(SessionColumn::ExpiresAt.gt(Utc::now().naive_utc()))
+        .find_also_related(UserEntity)
+        .one(db)
+        .await?
+        .and_then(|(session, user)| Some((session, user?))))
+}
+
+pub async fn get_sessions<C>(
+    db: &C,
+    user_id: i32,
+    current_session_id: i32,
+) -> Result<Vec<UserSessionModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(SessionEntity::find()
+        .filter(SessionColumn::UserId.eq(user_id))
+        .filter(SessionColumn::ExpiresAt.gt(Utc::now().naive_utc()))
+        .order_by_desc(SessionColumn::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|session| UserSessionModel {
+            id: session.id,
+            user_agent: session.user_agent,
+            ip: session.ip,
+            created_at: session.created_at,
+            expires_at: session.expires_at,
+            current: session.id == current_session_id,
+        })
+        .collect())
+}
+
+pub async fn delete_session<C>(db: &C, user_id: i32, session_id: i32) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let result = SessionEntity::delete_many()
+        .filter(SessionColumn::Id.eq(session_id))
+        .filter(SessionColumn::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+    if result.rows_affected == 0 {
+        return Err(DbErr::Custom("Сессия не найдена".to_string()));
+    }
+
+    Ok(result.rows_affected)
+}
+
+// Отзывает все сессии пользователя, кроме except - например, той, из которой сменили пароль
+pub async fn delete_user_sessions<C>(
+    db: &C,
+    user_id: i32,
+    except: Option<i32>,
+) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let mut query = SessionEntity::delete_many().filter(SessionColumn::UserId.eq(user_id));
+    if let Some(session_id) = except {
+        query = query.filter(SessionColumn::Id.ne(session_id));
+    }
+
+    Ok(query.exec(db).await?.rows_affected)
+}
+
+// Выход: сессия удаляется на сервере, cookie - у клиента
+pub async fn logout_session<C>(db: &C, cookie: &Cookies, cookie_key: &Key) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if let Some(session_cookie) = cookie.private(cookie_key).get(COOKIE_NAME) {
+        SessionEntity::delete_many()
+            .filter(SessionColumn::TokenHash.eq(hash_session_token(session_cookie.value())))
+            .exec(db)
+            .await?;
+    }
+    cookie.remove(Cookie::new(COOKIE_NAME, ""));
+
+    Ok(())
+}
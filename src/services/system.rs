@@ -1,7 +1,20 @@
 use crate::{
+    models::{
+        coverage::{AnswerCountModel, CoverageModel, QuestionCoverageModel, RuleCoverageModel},
+        decision_tree::DecisionTreeModel,
+        induction::InductionModel,
+        inference::{GivenAnswerModel, InferenceResultModel},
+        lint::LintIssueModel,
+    },
     pagination::{SystemListPagination, SystemStars},
-    services::{object::get_objects, rule::get_rules},
-    utils::topological_sort::topological_sort,
+    services::{
+        object::get_objects,
+        rule::{get_rules, question_cycle_error},
+    },
+    utils::{
+        decision_tree::DecisionTreeCompiler, induction::RuleInducer, inference::InferenceEngine,
+        lint::Linter, topological_sort::topological_sort,
+    },
     IMAGE_DIR,
 };
 use sea_orm::{
@@ -10,9 +23,10 @@ use sea_orm::{
     Statement, TransactionTrait,
 };
 
-use super::question::get_questions;
+use super::{attribute::get_attributes, question::get_questions};
 use entity::{
     clauses::Entity as ClauseEntity,
+    histories::{Column as HistoryColumn, Entity as HistoryEntity},
     questions::QuestionWithAnswersModel,
     rule_question_answer::Entity as RuleQuestionAnswerEntity,
     rules::Model as RuleModel,
@@ -23,7 +37,10 @@ use entity::{
     },
     users::{Column as UserColumn, Entity as UserEntity},
 };
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
 use tokio::{
     fs::{self, File},
     io::AsyncWriteExt,
@@ -107,6 +124,8 @@ where
                 id: rule.id,
                 system_id,
                 attribute_rule: rule.attribute_rule,
+                certainty_factor: rule.certainty_factor,
+                condition: None,
             })
         })
         .collect();
@@ -163,7 +182,8 @@ where
                 .or_insert(HashSet::new());
         });
     //println!("77777777777777777777 {:?}", &rules_belonging_questions);
-    let belonging_questions_order = topological_sort(&rules_belonging_questions);
+    let belonging_questions_order = topological_sort(&rules_belonging_questions)
+        .map_err(|cycle| question_cycle_error(&cycle, &_rules))?;
     //println!("8888888888888888888888 {:?}", &belonging_questions_order);
     let ordered_questions = belonging_questions_order
         .into_iter()
@@ -182,6 +202,156 @@ where
     })
 }
 
+pub async fn evaluate_system<C>(
+    db: &C,
+    system_id: i32,
+    given_answers: Vec<GivenAnswerModel>,
+) -> Result<InferenceResultModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (rules, questions, objects) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id)
+    )?;
+
+    Ok(InferenceEngine::new(rules, questions, objects).infer(&given_answers))
+}
+
+pub async fn lint_system<C>(db: &C, system_id: i32) -> Result<Vec<LintIssueModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    get_system(db, system_id).await?;
+    let (rules, questions, objects, attributes) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id),
+        get_attributes(db, system_id)
+    )?;
+
+    Ok(Linter::new(&rules, &questions, &objects, &attributes).lint())
+}
+
+pub async fn compile_decision_tree<C>(db: &C, system_id: i32) -> Result<DecisionTreeModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    get_system(db, system_id).await?;
+    let (rules, questions, objects) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id)
+    )?;
+    let engine = InferenceEngine::new(rules, questions, objects);
+
+    DecisionTreeCompiler::new(&engine)
+        .compile()
+        .ok_or(DbErr::Custom(
+            "Дерево решений слишком большое для построения".to_string(),
+        ))
+}
+
+pub async fn induce_rules<C>(db: &C, system_id: i32) -> Result<InductionModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (rules, questions, objects, attributes) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id),
+        get_attributes(db, system_id)
+    )?;
+
+    Ok(RuleInducer::new(system_id, &rules, &questions, &objects, &attributes).induce())
+}
+
+pub async fn get_system_coverage<C>(db: &C, system_id: i32) -> Result<CoverageModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (rules, questions, objects) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id)
+    )?;
+    let histories = HistoryEntity::find()
+        .filter(HistoryColumn::SystemId.eq(system_id))
+        .all(db)
+        .await?;
+    let histories_count = histories.len();
+    let engine = InferenceEngine::new(rules, questions, objects);
+
+    // Истории до появления структурированных ответов воспроизвести нельзя. Объяснение
+    // пишет только сервис консультаций, поэтому записи без него (присланные клиентом)
+    // тоже пропускаются - их ответы могли быть подделаны
+    let given_answers: Vec<Vec<GivenAnswerModel>> = histories
+        .into_iter()
+        .filter(|history| history.explanation.is_some())
+        .filter_map(|history| serde_json::from_value(history.answers?).ok())
+        .collect();
+
+    let mut fire_counts: HashMap<i32, usize> = HashMap::new();
+    let mut answer_counts: HashMap<i32, Vec<AnswerCountModel>> = HashMap::new();
+    for given in &given_answers {
+        let facts = engine.given_facts(given);
+        for (question_id, fact) in &facts {
+            let counts = answer_counts.entry(*question_id).or_default();
+            let value = fact.value.trim();
+            match counts
+                .iter_mut()
+                .find(|count| count.answer_id == fact.answer_id && count.value == value)
+            {
+                Some(count) => count.count += 1,
+                None => counts.push(AnswerCountModel {
+                    answer_id: fact.answer_id,
+                    value: value.to_string(),
+                    count: 1,
+                }),
+            }
+        }
+
+        for rule_id in engine.run(facts).fired_rules {
+            *fire_counts.entry(rule_id).or_default() += 1;
+        }
+    }
+
+    let rules: Vec<RuleCoverageModel> = engine
+        .rules
+        .iter()
+        .map(|rule| RuleCoverageModel {
+            rule_id: rule.id,
+            fire_count: fire_counts.get(&rule.id).copied().unwrap_or(0),
+        })
+        .collect();
+    let questions = engine
+        .questions
+        .iter()
+        .map(|question| {
+            let mut answers = answer_counts.remove(&question.id).unwrap_or_default();
+            answers.sort_by_key(|answer| Reverse(answer.count));
+            QuestionCoverageModel {
+                question_id: question.id,
+                answered: answers.iter().map(|answer| answer.count).sum(),
+                answers,
+            }
+        })
+        .collect();
+
+    Ok(CoverageModel {
+        replayed_histories: given_answers.len(),
+        skipped_histories: histories_count - given_answers.len(),
+        never_fired_rule_ids: rules
+            .iter()
+            .filter(|rule| rule.fire_count == 0)
+            .map(|rule| rule.rule_id)
+            .collect(),
+        rules,
+        questions,
+    })
+}
+
 pub async fn create_system<C>(
     db: &C,
     system_info: NewSystemMultipartModel,
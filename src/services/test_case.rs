@@ -0,0 +1,217 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::{
+    models::{
+        inference::GivenAnswerModel,
+        test_case::{NewTestCaseModel, TestCaseResultModel, TestRunModel},
+    },
+    services::{
+        attribute::get_attributes, object::get_objects, question::get_questions, rule::get_rules,
+    },
+    utils::inference::InferenceEngine,
+};
+use entity::test_cases::{
+    ActiveModel as TestCaseActiveModel, Column as TestCaseColumn, Entity as TestCaseEntity,
+    Model as TestCaseModel,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set, TransactionTrait,
+};
+use serde_json::json;
+use tokio::try_join;
+
+pub async fn get_test_cases<C>(db: &C, system_id: i32) -> Result<Vec<TestCaseModel>, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    TestCaseEntity::find()
+        .filter(TestCaseColumn::SystemId.eq(system_id))
+        .order_by_asc(TestCaseColumn::Id)
+        .all(db)
+        .await
+}
+
+pub async fn create_test_case<C>(
+    db: &C,
+    system_id: i32,
+    test_case_info: NewTestCaseModel,
+) -> Result<TestCaseModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let (questions, objects, attributes) = try_join!(
+        get_questions(db, system_id),
+        get_objects(db, system_id),
+        get_attributes(db, system_id)
+    )?;
+
+    for answer in &test_case_info.answers {
+        let question = questions
+            .iter()
+            .find(|question| question.id == answer.question_id)
+            .ok_or(DbErr::Custom("Вопрос не найден".to_string()))?;
+        match (answer.answer_id, &answer.value) {
+            (Some(answer_id), _) => {
+                if !question.answers.iter().any(|option| option.id == answer_id) {
+                    return Err(DbErr::Custom("Ответ не найден".to_string()));
+                }
+            }
+            (None, Some(_)) => (),
+            (None, None) => return Err(DbErr::Custom("Ответ не указан".to_string())),
+        }
+    }
+    if !test_case_info
+        .expected_object_ids
+        .iter()
+        .all(|object_id| objects.iter().any(|object| object.id == *object_id))
+    {
+        return Err(DbErr::Custom("Объект не найден".to_string()));
+    }
+    if !test_case_info
+        .expected_attribute_value_ids
+        .iter()
+        .all(|attribute_value_id| {
+            attributes
+                .iter()
+                .flat_map(|attribute| attribute.values.iter())
+                .any(|value| value.id == *attribute_value_id)
+        })
+    {
+        return Err(DbErr::Custom("Значение атрибута не найдено".to_string()));
+    }
+
+    TestCaseActiveModel {
+        system_id: Set(system_id),
+        name: Set(test_case_info.name),
+        answers: Set(json!(test_case_info.answers)),
+        expected_object_ids: Set(json!(test_case_info.expected_object_ids)),
+        expected_attribute_value_ids: Set(json!(test_case_info.expected_attribute_value_ids)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+}
+
+pub async fn delete_test_case<C>(db: &C, system_id: i32, test_case_id: i32) -> Result<u64, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    Ok(TestCaseEntity::delete_many()
+        .filter(TestCaseColumn::Id.eq(test_case_id))
+        .filter(TestCaseColumn::SystemId.eq(system_id))
+        .exec(db)
+        .await?
+        .rows_affected)
+}
+
+// value_attributes - атрибут каждого значения атрибута системы
+fn run_test_case(
+    engine: &InferenceEngine,
+    value_attributes: &HashMap<i32, i32>,
+    test_case: &TestCaseModel,
+) -> Result<TestCaseResultModel, DbErr> {
+    let parse_error = |err: serde_json::Error| DbErr::Custom(err.to_string());
+    let given: Vec<GivenAnswerModel> =
+        serde_json::from_value(test_case.answers.clone()).map_err(parse_error)?;
+    let expected_objects: BTreeSet<i32> =
+        serde_json::from_value(test_case.expected_object_ids.clone()).map_err(parse_error)?;
+    let expected_values: BTreeSet<i32> =
+        serde_json::from_value(test_case.expected_attribute_value_ids.clone())
+            .map_err(parse_error)?;
+
+    let result = engine.infer(&given);
+
+    // Фактический результат - объекты с наибольшим ненулевым процентом совпадения
+    let best_percent = result
+        .objects
+        .iter()
+        .map(|object| object.percent)
+        .max()
+        .unwrap_or(0);
+    let actual_objects: BTreeSet<i32> = result
+        .objects
+        .iter()
+        .filter(|object| best_percent > 0 && object.percent == best_percent)
+        .map(|object| object.object_id)
+        .collect();
+    // Значения сравниваются только по атрибутам, названным в тесте: остальные выведенные
+    // значения тест не проверяет и лишними не считаются
+    let checked_attributes: BTreeSet<i32> = expected_values
+        .iter()
+        .filter_map(|value_id| value_attributes.get(value_id).copied())
+        .collect();
+    let actual_values: BTreeSet<i32> = result
+        .attribute_values
+        .iter()
+        .filter(|value| value.certainty_factor > 0.0)
+        .filter(|value| checked_attributes.contains(&value.attribute_id))
+        .map(|value| value.attribute_value_id)
+        .collect();
+
+    let missing_object_ids: Vec<i32> = expected_objects
+        .difference(&actual_objects)
+        .copied()
+        .collect();
+    let unexpected_object_ids: Vec<i32> = actual_objects
+        .difference(&expected_objects)
+        .copied()
+        .collect();
+    let missing_attribute_value_ids: Vec<i32> = expected_values
+        .difference(&actual_values)
+        .copied()
+        .collect();
+    let unexpected_attribute_value_ids: Vec<i32> = actual_values
+        .difference(&expected_values)
+        .copied()
+        .collect();
+
+    Ok(TestCaseResultModel {
+        test_case_id: test_case.id,
+        name: test_case.name.clone(),
+        passed: missing_object_ids.is_empty()
+            && unexpected_object_ids.is_empty()
+            && missing_attribute_value_ids.is_empty()
+            && unexpected_attribute_value_ids.is_empty(),
+        missing_object_ids,
+        unexpected_object_ids,
+        missing_attribute_value_ids,
+        unexpected_attribute_value_ids,
+        objects: result.objects,
+    })
+}
+
+pub async fn run_test_cases<C>(db: &C, system_id: i32) -> Result<TestRunModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let test_cases = get_test_cases(db, system_id).await?;
+    let (rules, questions, objects, attributes) = try_join!(
+        get_rules(db, system_id),
+        get_questions(db, system_id),
+        get_objects(db, system_id),
+        get_attributes(db, system_id)
+    )?;
+    let engine = InferenceEngine::new(rules, questions, objects);
+    let value_attributes: HashMap<i32, i32> = attributes
+        .iter()
+        .flat_map(|attribute| {
+            attribute
+                .values
+                .iter()
+                .map(|value| (value.id, attribute.id))
+        })
+        .collect();
+
+    let results = test_cases
+        .iter()
+        .map(|test_case| run_test_case(&engine, &value_attributes, test_case))
+        .collect::<Result<Vec<TestCaseResultModel>, DbErr>>()?;
+    let passed = results.iter().filter(|result| result.passed).count();
+
+    Ok(TestRunModel {
+        passed,
+        failed: results.len() - passed,
+        results,
+    })
+}
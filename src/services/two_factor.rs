@@ -0,0 +1,560 @@
+use crate::{
+    constants::{
+        SESSION_TOKEN_LEN, TOTP_ISSUER, TOTP_RECOVERY_CODES, TOTP_RECOVERY_CODE_LEN,
+        TOTP_SECRET_LEN, TWO_FACTOR_CHALLENGE_MINUTES, TWO_FACTOR_LOCK_MINUTES,
+        TWO_FACTOR_MAX_ATTEMPTS,
+    },
+    models::{
+        session::SessionClientModel,
+        two_factor::{
+            RecoveryCodesModel, TwoFactorChallengeModel, TwoFactorEnrollmentModel,
+            TwoFactorLoginModel,
+        },
+    },
+    services::session::create_session,
+    utils::{
+        auth::{check_password, hash_password},
+        clock::Clock,
+        generate_random_string::generate_random_string,
+    },
+};
+use chrono::Duration as ChronoDuration;
+use entity::{
+    two_factor_challenges::{
+        ActiveModel as ChallengeActiveModel, Column as ChallengeColumn, Entity as ChallengeEntity,
+    },
+    users::{ActiveModel as UserActiveModel, Entity as UserEntity, Model as UserModel},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseBackend, DbErr, EntityTrait,
+    QueryFilter, Set, Statement, TransactionTrait, Unchanged,
+};
+use serde_json::json;
+use totp_rs::{Algorithm, Secret, TOTP};
+use tower_cookies::{Cookies, Key};
+
+const TOTP_STEP: u64 = 30;
+
+fn build_totp(secret: Vec<u8>, account_name: &str) -> Result<TOTP, DbErr> {
+    // skew = 0: соседние шаги перебираются вручную, чтобы запомнить принятый шаг
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        TOTP_STEP,
+        secret,
+        Some(TOTP_ISSUER.to_string()),
+        account_name.to_string(),
+    )
+    .map_err(|_| DbErr::Custom("Не удалось создать секрет TOTP".to_string()))
+}
+
+fn user_totp(user: &UserModel) -> Result<TOTP, DbErr> {
+    let secret = user
+        .totp_secret
+        .as_ref()
+        .and_then(|secret| Secret::Encoded(secret.clone()).to_bytes().ok())
+        .ok_or(DbErr::Custom(
+            "Двухфакторная аутентификация не подключена".to_string(),
+        ))?;
+
+    build_totp(secret, &user.email)
+}
+
+// Коды восстановления вводят руками, поэтому регистр и дефисы не важны
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|char| char.is_ascii_alphanumeric())
+        .map(|char| char.to_ascii_lowercase())
+        .collect()
+}
+
+fn new_recovery_codes() -> (Vec<String>, Vec<String>) {
+    (0..TOTP_RECOVERY_CODES)
+        .map(|_| {
+            let code = generate_random_string(TOTP_RECOVERY_CODE_LEN).to_ascii_lowercase();
+            let (left, right) = code.split_at(TOTP_RECOVERY_CODE_LEN / 2);
+            (format!("{}-{}", left, right), hash_password(&code))
+        })
+        .unzip()
+}
+
+// Шаг TOTP, которому соответствует код (с допуском в один шаг). Шаги не новее
+// последнего принятого не подходят, поэтому один код нельзя использовать дважды
+fn matching_step(totp: &TOTP, code: &str, last_step: Option<i64>, clock: &Clock) -> Option<i64> {
+    let current_step = (clock.timestamp() / TOTP_STEP) as i64;
+
+    (current_step - 1..=current_step + 1)
+        .filter(|step| last_step.is_none_or(|last_step| *step > last_step))
+        .find(|step| totp.check(code, (*step).max(0) as u64 * TOTP_STEP))
+}
+
+fn matching_recovery_code(hashes: &[String], code: &str) -> Option<usize> {
+    let code = normalize_recovery_code(code);
+    hashes
+        .iter()
+        .position(|hash| check_password(&code, hash).is_ok())
+}
+
+// Проверяет код из приложения или одноразовый код восстановления.
+// Принятый шаг TOTP запоминается, использованный код восстановления удаляется.
+// Оба изменения - условные UPDATE: из параллельных запросов с одним кодом
+// проходит только тот, что успел изменить строку первым
+async fn verify_code<C>(
+    db: &C,
+    user: &UserModel,
+    code: &str,
+    allow_recovery: bool,
+    clock: &Clock,
+) -> Result<bool, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let code = code.trim();
+
+    if code.len() == 6 && code.chars().all(|char| char.is_ascii_digit()) {
+        let totp = user_totp(user)?;
+        let Some(step) = matching_step(&totp, code, user.totp_last_step, clock) else {
+            return Ok(false);
+        };
+
+        let result = db
+            .execute(Statement::from_sql_and_values(
+                DatabaseBackend::Postgres,
+                "UPDATE \"public\".\"users\" SET totp_last_step = $2
+                WHERE id = $1 AND (totp_last_step IS NULL OR totp_last_step < $2);",
+                [user.id.into(), step.into()],
+            ))
+            .await?;
+
+        return Ok(result.rows_affected() == 1);
+    }
+
+    if !allow_recovery {
+        return Ok(false);
+    }
+
+    let mut hashes: Vec<String> =
+        serde_json::from_value(user.totp_recovery_codes.clone()).unwrap_or_default();
+    let Some(index) = matching_recovery_code(&hashes, code) else {
+        return Ok(false);
+    };
+    hashes.remove(index);
+
+    // У json нет сравнения, поэтому списки сравниваются как jsonb
+    let result = db
+        .execute(Statement::from_sql_and_values(
+            DatabaseBackend::Postgres,
+            "UPDATE \"public\".\"users\" SET totp_recovery_codes = $2
+            WHERE id = $1 AND totp_recovery_codes::jsonb = $3::jsonb;",
+            [
+                user.id.into(),
+                json!(hashes).into(),
+                user.totp_recovery_codes.clone().into(),
+            ],
+        ))
+        .await?;
+
+    Ok(result.rows_affected() == 1)
+}
+
+fn locked_error() -> DbErr {
+    DbErr::Custom("Слишком много неверных кодов, попробуйте позже".to_string())
+}
+
+// Попытка резервируется до проверки кода одним UPDATE, поэтому и параллельные запросы,
+// и новые входы с паролем дают не больше TWO_FACTOR_MAX_ATTEMPTS попыток до блокировки
+async fn take_attempt<C>(db: &C, user_id: i32, clock: &Clock) -> Result<i32, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    db.query_one(Statement::from_sql_and_values(
+        DatabaseBackend::Postgres,
+        "UPDATE \"public\".\"users\" SET totp_failed_attempts = totp_failed_attempts + 1
+        WHERE id = $1 AND (totp_locked_until IS NULL OR totp_locked_until <= $2)
+        RETURNING totp_failed_attempts;",
+        [user_id.into(), clock.now_naive().into()],
+    ))
+    .await?
+    .ok_or_else(locked_error)?
+    .try_get::<i32>("", "totp_failed_attempts")
+}
+
+async fn require_code<C>(
+    db: &C,
+    user: &UserModel,
+    code: &str,
+    allow_recovery: bool,
+    clock: &Clock,
+) -> Result<(), DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let attempts = take_attempt(db, user.id, clock).await?;
+
+    if verify_code(db, user, code, allow_recovery, clock).await? {
+        UserActiveModel {
+            id: Unchanged(user.id),
+            totp_failed_attempts: Set(0),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+
+        return Ok(());
+    }
+
+    if attempts >= TWO_FACTOR_MAX_ATTEMPTS {
+        UserActiveModel {
+            id: Unchanged(user.id),
+            totp_failed_attempts: Set(0),
+            totp_locked_until: Set(Some(
+                clock.now_naive() + ChronoDuration::minutes(TWO_FACTOR_LOCK_MINUTES),
+            )),
+            ..Default::default()
+        }
+        .update(db)
+        .await?;
+        ChallengeEntity::delete_many()
+            .filter(ChallengeColumn::UserId.eq(user.id))
+            .exec(db)
+            .await?;
+
+        return Err(locked_error());
+    }
+
+    Err(DbErr::Custom(
+        "Неверный код двухфакторной аутентификации".to_string(),
+    ))
+}
+
+// Новый секрет и коды восстановления. 2FA начинает работать только после подтверждения кодом,
+// до этого повторный вызов просто заменяет секрет
+pub async fn enroll_two_factor<C>(
+    db: &C,
+    user: UserModel,
+) -> Result<TwoFactorEnrollmentModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if user.totp_enabled {
+        return Err(DbErr::Custom(
+            "Двухфакторная аутентификация уже включена".to_string(),
+        ));
+    }
+
+    let totp = build_totp(
+        rand::random::<[u8; TOTP_SECRET_LEN]>().to_vec(),
+        &user.email,
+    )?;
+    let secret = totp.get_secret_base32();
+    let (recovery_codes, hashes) = new_recovery_codes();
+
+    UserActiveModel {
+        id: Unchanged(user.id),
+        totp_secret: Set(Some(secret.clone())),
+        totp_last_step: Set(None),
+        totp_recovery_codes: Set(json!(hashes)),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(TwoFactorEnrollmentModel {
+        secret,
+        provisioning_uri: totp.get_url(),
+        recovery_codes,
+    })
+}
+
+pub async fn confirm_two_factor<C>(
+    db: &C,
+    user: UserModel,
+    code: &str,
+    clock: &Clock,
+) -> Result<UserModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if user.totp_enabled {
+        return Err(DbErr::Custom(
+            "Двухфакторная аутентификация уже включена".to_string(),
+        ));
+    }
+    require_code(db, &user, code, false, clock).await?;
+
+    UserActiveModel {
+        id: Unchanged(user.id),
+        totp_enabled: Set(true),
+        ..Default::default()
+    }
+    .update(db)
+    .await
+}
+
+pub async fn disable_two_factor<C>(
+    db: &C,
+    user: UserModel,
+    code: &str,
+    clock: &Clock,
+) -> Result<UserModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if !user.totp_enabled {
+        return Err(DbErr::Custom(
+            "Двухфакторная аутентификация не включена".to_string(),
+        ));
+    }
+    require_code(db, &user, code, true, clock).await?;
+
+    UserActiveModel {
+        id: Unchanged(user.id),
+        totp_secret: Set(None),
+        totp_enabled: Set(false),
+        totp_last_step: Set(None),
+        totp_recovery_codes: Set(json!([])),
+        ..Default::default()
+    }
+    .update(db)
+    .await
+}
+
+pub async fn regenerate_recovery_codes<C>(
+    db: &C,
+    user: UserModel,
+    code: &str,
+    clock: &Clock,
+) -> Result<RecoveryCodesModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    if !user.totp_enabled {
+        return Err(DbErr::Custom(
+            "Двухфакторная аутентификация не включена".to_string(),
+        ));
+    }
+    require_code(db, &user, code, false, clock).await?;
+
+    let (recovery_codes, hashes) = new_recovery_codes();
+    UserActiveModel {
+        id: Unchanged(user.id),
+        totp_recovery_codes: Set(json!(hashes)),
+        ..Default::default()
+    }
+    .update(db)
+    .await?;
+
+    Ok(RecoveryCodesModel { recovery_codes })
+}
+
+// Промежуточное состояние входа: пароль проверен, сессия будет создана после кода.
+// У пользователя действует только последний вход, прежние удаляются
+pub async fn create_two_factor_challenge<C>(
+    db: &C,
+    user_id: i32,
+    clock: &Clock,
+) -> Result<TwoFactorChallengeModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let now = clock.now_naive();
+    ChallengeEntity::delete_many()
+        .filter(ChallengeColumn::UserId.eq(user_id))
+        .exec(db)
+        .await?;
+
+    let challenge = ChallengeActiveModel {
+        user_id: Set(user_id),
+        token: Set(generate_random_string(SESSION_TOKEN_LEN)),
+        expires_at: Set(now + ChronoDuration::minutes(TWO_FACTOR_CHALLENGE_MINUTES)),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(TwoFactorChallengeModel {
+        two_factor_required: true,
+        challenge: challenge.token,
+        expires_at: challenge.expires_at,
+    })
+}
+
+// Второй шаг входа. Неверные коды считаются для пользователя, а не для входа,
+// после TWO_FACTOR_MAX_ATTEMPTS ввод кодов блокируется на TWO_FACTOR_LOCK_MINUTES
+pub async fn complete_two_factor_login<C>(
+    db: &C,
+    login_info: TwoFactorLoginModel,
+    client: SessionClientModel,
+    cookie: Cookies,
+    cookie_key: &Key,
+    clock: &Clock,
+) -> Result<UserModel, DbErr>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let Some((challenge, Some(user))) = ChallengeEntity::find()
+        .filter(ChallengeColumn::Token.eq(login_info.challenge))
+        .filter(ChallengeColumn::ExpiresAt.gt(clock.now_naive()))
+        .find_also_related(UserEntity)
+        .one(db)
+        .await?
+    else {
+        return Err(DbErr::Custom(
+            "Вход не найден или истек, введите пароль еще раз".to_string(),
+        ));
+    };
+
+    require_code(db, &user, &login_info.code, true, clock).await?;
+
+    ChallengeEntity::delete_by_id(challenge.id).exec(db).await?;
+    create_session(db, user.id, client, &cookie, cookie_key).await?;
+
+    Ok(user)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::DateTime;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn fixed_clock(timestamp: i64) -> Clock {
+        Clock::Fixed(DateTime::from_timestamp(timestamp, 0).unwrap())
+    }
+
+    fn test_totp() -> TOTP {
+        build_totp(b"12345678901234567890".to_vec(), "user@example.com").unwrap()
+    }
+
+    fn code_at(totp: &TOTP, timestamp: i64) -> String {
+        totp.generate(timestamp as u64)
+    }
+
+    #[test]
+    fn accepts_current_and_adjacent_steps() {
+        let totp = test_totp();
+        let clock = fixed_clock(NOW);
+        let step = NOW / TOTP_STEP as i64;
+
+        for offset in -1..=1 {
+            let code = code_at(&totp, NOW + offset * TOTP_STEP as i64);
+            assert_eq!(
+                matching_step(&totp, &code, None, &clock),
+                Some(step + offset)
+            );
+        }
+    }
+
+    #[test]
+    fn rejects_codes_outside_window() {
+        let totp = test_totp();
+        let clock = fixed_clock(NOW);
+
+        let old_code = code_at(&totp, NOW - 2 * TOTP_STEP as i64);
+        let future_code = code_at(&totp, NOW + 2 * TOTP_STEP as i64);
+        assert_eq!(matching_step(&totp, &old_code, None, &clock), None);
+        assert_eq!(matching_step(&totp, &future_code, None, &clock), None);
+    }
+
+    #[test]
+    fn rejects_replayed_step() {
+        let totp = test_totp();
+        let clock = fixed_clock(NOW);
+        let code = code_at(&totp, NOW);
+
+        let step = matching_step(&totp, &code, None, &clock).unwrap();
+        assert_eq!(matching_step(&totp, &code, Some(step), &clock), None);
+        // Более ранний код после принятого тоже не подходит
+        let previous = code_at(&totp, NOW - TOTP_STEP as i64);
+        assert_eq!(matching_step(&totp, &previous, Some(step), &clock), None);
+        // Следующий шаг еще принимается
+        let next = code_at(&totp, NOW + TOTP_STEP as i64);
+        assert_eq!(
+            matching_step(&totp, &next, Some(step), &clock),
+            Some(step + 1)
+        );
+    }
+
+    #[test]
+    fn fixed_clock_drives_code_window() {
+        let totp = test_totp();
+        let code = code_at(&totp, NOW);
+
+        assert!(matching_step(&totp, &code, None, &fixed_clock(NOW)).is_some());
+        assert!(matching_step(&totp, &code, None, &fixed_clock(NOW + 120)).is_none());
+    }
+
+    #[test]
+    fn recovery_codes_are_one_time() {
+        let codes = ["aaaaa-11111", "bbbbb-22222", "ccccc-33333"];
+        let mut hashes: Vec<String> = codes
+            .iter()
+            .map(|code| hash_password(&normalize_recovery_code(code)))
+            .collect();
+
+        let index = matching_recovery_code(&hashes, codes[1]).unwrap();
+        assert_eq!(index, 1);
+        hashes.remove(index);
+        assert_eq!(matching_recovery_code(&hashes, codes[1]), None);
+        assert_eq!(matching_recovery_code(&hashes, codes[2]), Some(1));
+    }
+
+    #[test]
+    fn recovery_codes_ignore_case_and_separators() {
+        let code = "abcde-12345";
+        let hashes = vec![hash_password(&normalize_recovery_code(code))];
+
+        assert_eq!(matching_recovery_code(&hashes, "ABCDE12345"), Some(0));
+        assert_eq!(matching_recovery_code(&hashes, " abcde 12345 "), Some(0));
+        assert_eq!(matching_recovery_code(&hashes, "abcde-12346"), None);
+    }
+
+    // Параллельный запрос видит пользователя до изменения, поэтому второй вызов
+    // с тем же снимком пользователя должен получить отказ. Запуск: cargo test -- --ignored
+    #[tokio::test]
+    #[ignore = "нужна база PostgreSQL из DATABASE_URL"]
+    async fn same_code_is_accepted_once() {
+        let db = sea_orm::Database::connect(std::env::var("DATABASE_URL").unwrap())
+            .await
+            .unwrap();
+        let txn = db.begin().await.unwrap();
+        let clock = fixed_clock(NOW);
+        let recovery_code = "abcde-12345";
+
+        let user = UserActiveModel {
+            email: Set("totp@test.local".to_string()),
+            username: Set("totp".to_string()),
+            first_name: Set("totp".to_string()),
+            last_name: Set("totp".to_string()),
+            password: Set(String::new()),
+            totp_secret: Set(Some(test_totp().get_secret_base32())),
+            totp_enabled: Set(true),
+            totp_recovery_codes: Set(json!([hash_password(&normalize_recovery_code(
+                recovery_code
+            ))])),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await
+        .unwrap();
+
+        let code = code_at(&test_totp(), NOW);
+        assert!(verify_code(&txn, &user, &code, false, &clock)
+            .await
+            .unwrap());
+        assert!(!verify_code(&txn, &user, &code, false, &clock)
+            .await
+            .unwrap());
+
+        assert!(verify_code(&txn, &user, recovery_code, true, &clock)
+            .await
+            .unwrap());
+        assert!(!verify_code(&txn, &user, recovery_code, true, &clock)
+            .await
+            .unwrap());
+
+        txn.rollback().await.unwrap();
+    }
+}
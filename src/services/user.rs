@@ -1,9 +1,15 @@
 use crate::{
     config::Config,
     constants::COOKIE_NAME,
-    models::email::Email,
+    models::{email::Email, session::SessionClientModel, two_factor::LoginResultModel},
+    services::{
+        access_token::delete_user_access_tokens,
+        session::{create_session, delete_user_sessions},
+        two_factor::create_two_factor_challenge,
+    },
     utils::{
         auth::{check_password, hash_password},
+        clock::Clock,
         generate_random_string::generate_random_string,
     },
 };
@@ -17,44 +23,42 @@ use sea_orm::{
     ActiveModelTrait, ColumnTrait, ConnectionTrait, DbErr, EntityTrait, IntoActiveModel,
     QueryFilter, Set, TransactionTrait, Unchanged,
 };
-use tower_cookies::{
-    cookie::{
-        time::{Duration, OffsetDateTime},
-        SameSite,
-    },
-    Cookie, Cookies, Key,
-};
-
-pub async fn get_user<C>(db: &C, user_id: i32) -> Result<UserModel, DbErr>
-where
-    C: ConnectionTrait + TransactionTrait,
-{
-    Ok(UserEntity::find_by_id(user_id)
-        .one(db)
-        .await?
-        .ok_or(DbErr::Custom("Пользователь не найден".to_string()))?)
-}
+use tower_cookies::{cookie::time::OffsetDateTime, Cookie, Cookies, Key};
 
 pub async fn update_user<C>(
     db: &C,
     user_data: UpdateUserResponse,
     user_id: i32,
+    current_session_id: i32,
 ) -> Result<UserModel, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
+    let password_changed = user_data.new_password.is_some();
     let update_user = UpdateUserModel {
         id: user_id,
         email: user_data.email,
         first_name: user_data.first_name,
         last_name: user_data.last_name,
-        password: user_data.new_password,
+        password: user_data.new_password.as_deref().map(hash_password),
         verified: None,
         verification_code: None,
         password_reset_at: None,
     };
 
-    Ok(update_user.into_active_model().update(db).await?)
+    let txn = db.begin().await?;
+
+    let user = update_user.into_active_model().update(&txn).await?;
+
+    // После смены пароля остальные сессии и все токены отзываются, текущая сессия остается
+    if password_changed {
+        delete_user_sessions(&txn, user_id, Some(current_session_id)).await?;
+        delete_user_access_tokens(&txn, user_id).await?;
+    }
+
+    txn.commit().await?;
+
+    Ok(user)
 }
 
 pub async fn create_user<C>(
@@ -98,9 +102,11 @@ where
 pub async fn login_user<C>(
     db: &C,
     user_info: LoginUserModel,
+    client: SessionClientModel,
     cookie: Cookies,
     cookie_key: &Key,
-) -> Result<UserModel, DbErr>
+    clock: &Clock,
+) -> Result<LoginResultModel, DbErr>
 where
     C: ConnectionTrait + TransactionTrait,
 {
@@ -123,22 +129,21 @@ where
         "Предоставлены неверные учетные данные".to_string(),
     )))?;
 
-    cookie.private(cookie_key).add(
-        Cookie::build((COOKIE_NAME, user.id.to_string()))
-            .path("/")
-            .secure(true)
-            .http_only(false)
-            .same_site(SameSite::Strict)
-            .expires(OffsetDateTime::now_utc() + Duration::days(2))
-            .into(),
-    );
+    // С включенной 2FA сессия создается только после проверки кода
+    if user.totp_enabled {
+        let challenge = create_two_factor_challenge(db, user.id, clock).await?;
+        return Ok(LoginResultModel::TwoFactorRequired(challenge));
+    }
 
-    Ok(user)
+    create_session(db, user.id, client, &cookie, cookie_key).await?;
+
+    Ok(LoginResultModel::Authorized(Box::new(user)))
 }
 
 pub async fn verify_email<C>(
     db: &C,
     verification_code: String,
+    client: SessionClientModel,
     cookie: Cookies,
     cookie_key: &Key,
 ) -> Result<UserModel, DbErr>
@@ -166,15 +171,7 @@ where
     .update(db)
     .await?;
 
-    cookie.private(cookie_key).add(
-        Cookie::build((COOKIE_NAME, user.id.to_string()))
-            .path("/")
-            .secure(true)
-            .http_only(false)
-            .same_site(SameSite::Strict)
-            .expires(OffsetDateTime::now_utc() + Duration::days(2))
-            .into(),
-    );
+    create_session(db, user.id, client, &cookie, cookie_key).await?;
 
     Ok(user)
 }
@@ -244,6 +241,8 @@ where
             "Токен востановления пароля недействителен".to_string(),
         ))?;
 
+    let txn = db.begin().await?;
+
     UserActiveModel {
         id: Unchanged(user.id),
         verification_code: Set(None),
@@ -251,8 +250,14 @@ where
         password: Set(hash_password(&reset_password_model.password)),
         ..Default::default()
     }
-    .update(db)
+    .update(&txn)
     .await?;
 
+    // Пароль сбрасывают, когда доступ мог попасть к кому-то еще - отзываем все сессии и токены
+    delete_user_sessions(&txn, user.id, None).await?;
+    delete_user_access_tokens(&txn, user.id).await?;
+
+    txn.commit().await?;
+
     Ok(())
 }
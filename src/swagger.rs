@@ -1,9 +1,16 @@
 use crate::{
     error,
+    models::{
+        access_token as access_token_model, backup as backup_model,
+        consultation as consultation_model, coverage as coverage_model,
+        decision_tree as decision_tree_model, induction as induction_model,
+        inference as inference_model, interchange as interchange_model, lint as lint_model,
+        session as session_model, test_case as test_case_model, two_factor as two_factor_model,
+    },
     routes::{
-        answer, attribute, attribute_value, clause, history, object,
+        answer, attribute, attribute_value, clause, consultation, history, object,
         object_attribute_attributevalue, question, rule, rule_attribute_attributevalue,
-        rule_question_answer, system, user,
+        rule_question_answer, system, test_case, user,
     },
 };
 #[cfg(not(debug_assertions))]
@@ -11,16 +18,16 @@ use axum::Json;
 use entity::{
     answers as answer_model, attributes as attributes_model,
     attributesvalues as attributesvalues_model, clauses as clause_model,
-    histories as history_model,
+    consultations as consultation_entity_model, histories as history_model,
     object_attribute_attributevalue as object_attribute_attributevalue_model,
     objects as object_model, questions as question_model,
     rule_attribute_attributevalue as rule_attribute_attributevalue_model,
     rule_question_answer as rule_question_answer_model, rules as rule_model,
     sea_orm_active_enums as sea_orm_active_enums_model, systems as system_model,
-    users as user_model,
+    test_cases as test_case_entity_model, users as user_model,
 };
 use utoipa::{
-    openapi::security::{ApiKey, ApiKeyValue, SecurityScheme},
+    openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
     Modify, OpenApi,
 };
 
@@ -46,9 +53,18 @@ use utoipa::{
         clause::clause_list,
         clause::clause_multiple_delete,
         clause::clause_multiple_update,
+        consultation::consultation_create,
+        consultation::consultation_retrieve,
+        consultation::consultation_answer,
+        consultation::consultation_finish,
+        test_case::test_case_create,
+        test_case::test_case_list,
+        test_case::test_case_delete,
+        test_case::test_case_run,
         history::history_create,
         history::history_list,
         history::history_delete,
+        history::history_explanation,
         object::object_create,
         object::object_list,
         object::object_multiple_delete,
@@ -64,14 +80,23 @@ use utoipa::{
         rule::rule_create,
         rule::rule_list,
         rule::rule_multiple_delete,
+        rule::rule_dsl_retrieve,
+        rule::rule_dsl_update,
         system::system_create,
         system::system_list,
         system::system_retrieve,
         system::system_partial_update,
         system::system_delete,
         system::system_start,
+        system::system_inference,
+        system::system_lint,
+        system::system_coverage,
+        system::system_decision_tree,
+        system::system_induced_rules,
         system::system_backup,
         system::system_restore,
+        system::system_export,
+        system::system_import,
         system::system_stars,
         user::user_login,
         user::user_logout,
@@ -93,6 +118,10 @@ use utoipa::{
         clause_model::ClauseModel,
         clause_model::UpdateClauseModel,
         clause_model::NewClauseWithoutRule,
+        consultation_entity_model::ConsultationModel,
+        consultation_entity_model::NewConsultationModel,
+        sea_orm_active_enums_model::Consultationmodeenum,
+        sea_orm_active_enums_model::Valuetypeenum,
         history_model::HistoryModel,
         history_model::HistoryWithSystem,
         object_model::ObjectWithAttributesValuesModel,
@@ -107,16 +136,70 @@ use utoipa::{
         rule_attribute_attributevalue_model::NewRuleAttributeAttributeValueWithoutRuleModel,
         rule_model::RuleWithClausesAndEffects,
         rule_model::NewRuleWithClausesAndEffects,
+        rule_model::ConditionNode,
+        rule_model::NewConditionNode,
         system_model::SystemModel,
         system_model::NewSystemMultipartModel,
         system_model::UpdateSystemMultipartModel,
         system_model::SystemDeleteModel,
+        test_case_entity_model::TestCaseModel,
         user_model::UserModel,
         user_model::LoginUserModel,
         user_model::UpdateUserResponse,
         user_model::ForgotPasswordModel,
         user_model::ResetPasswordModel,
-        sea_orm_active_enums_model::Operatorenum
+        sea_orm_active_enums_model::Operatorenum,
+        inference_model::GivenAnswerModel,
+        inference_model::GoalStatus,
+        inference_model::DerivedAnswerModel,
+        inference_model::DerivedAttributeValueModel,
+        inference_model::ObjectScoreModel,
+        inference_model::InferenceResultModel,
+        inference_model::SatisfiedClauseModel,
+        inference_model::RuleTraceModel,
+        inference_model::ObjectValueTraceModel,
+        inference_model::ObjectTraceModel,
+        inference_model::ExplanationModel,
+        lint_model::LintIssueCode,
+        lint_model::LintIssueModel,
+        coverage_model::RuleCoverageModel,
+        backup_model::BackupUploadModel,
+        coverage_model::AnswerCountModel,
+        coverage_model::QuestionCoverageModel,
+        coverage_model::CoverageModel,
+        decision_tree_model::DecisionNodeModel,
+        decision_tree_model::DecisionEdgeModel,
+        decision_tree_model::DecisionTreeModel,
+        decision_tree_model::DecisionTreeFormat,
+        induction_model::InducedAttributeModel,
+        induction_model::InductionModel,
+        interchange_model::InterchangeFormat,
+        interchange_model::SystemDocumentModel,
+        interchange_model::QuestionDocumentModel,
+        interchange_model::AnswerDocumentModel,
+        interchange_model::AttributeDocumentModel,
+        interchange_model::AttributeValueDocumentModel,
+        interchange_model::ObjectDocumentModel,
+        interchange_model::RuleDocumentModel,
+        interchange_model::ClauseDocumentModel,
+        interchange_model::TestCaseDocumentModel,
+        interchange_model::GivenAnswerDocumentModel,
+        test_case_model::NewTestCaseModel,
+        test_case_model::TestCaseResultModel,
+        test_case_model::TestRunModel,
+        consultation_model::ConsultationStepModel,
+        session_model::UserSessionModel,
+        access_token_model::TokenScope,
+        access_token_model::NewAccessTokenModel,
+        access_token_model::AccessTokenInfoModel,
+        access_token_model::CreatedAccessTokenModel,
+        two_factor_model::TwoFactorChallengeModel,
+        two_factor_model::TwoFactorLoginModel,
+        two_factor_model::TwoFactorPasswordModel,
+        two_factor_model::TwoFactorCodeModel,
+        two_factor_model::TwoFactorVerifyModel,
+        two_factor_model::TwoFactorEnrollmentModel,
+        two_factor_model::RecoveryCodesModel
     ))
 )]
 pub struct ApiDoc;
@@ -141,6 +224,10 @@ impl Modify for SecurityAddon {
             components.add_security_scheme(
                 "Cookie",
                 SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new("session_id"))),
+            );
+            components.add_security_scheme(
+                "Bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
             )
         }
     }
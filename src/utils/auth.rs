@@ -1,33 +1,49 @@
-use crate::{constants::COOKIE_NAME, error::CustomErrors};
+use crate::{
+    constants::COOKIE_NAME,
+    error::CustomErrors,
+    models::{access_token::TokenScope, session::SessionClientModel},
+    services::{
+        access_token::{find_access_token, token_scopes},
+        session::find_session,
+    },
+    AppState,
+};
 use argon2::{
     password_hash::{rand_core::OsRng, Error, PasswordHasher, SaltString},
     Argon2, PasswordHash, PasswordVerifier,
 };
-use axum::http::StatusCode;
-use entity::users::{Entity as UserEntity, Model as UserModel};
-use sea_orm::{ConnectionTrait, EntityTrait, TransactionTrait};
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts},
+    http::{header, request::Parts, Method, StatusCode},
+};
+use entity::{
+    access_tokens::Model as AccessTokenModel, sessions::Model as SessionModel,
+    users::Model as UserModel,
+};
+use sea_orm::{ConnectionTrait, TransactionTrait};
+use std::{convert::Infallible, net::SocketAddr};
 use tower_cookies::{Cookies, Key};
 
+// Сессия и пользователь по токену из cookie
 pub async fn cookie_check<'a, C>(
     db: &'a C,
     cookie: Cookies,
     cookie_key: &'a Key,
-) -> Result<UserModel, CustomErrors>
+) -> Result<(SessionModel, UserModel), CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    let user_id = cookie
-        .private(&cookie_key)
+    let token = cookie
+        .private(cookie_key)
         .get(COOKIE_NAME)
         .map(|res| res.value().to_owned())
-        .and_then(|res| res.parse::<i32>().ok())
         .ok_or(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Not authorized".to_string(),
         })?;
 
-    let user = UserEntity::find_by_id(user_id)
-        .one(db)
+    find_session(db, &token)
         .await
         .map_err(|err| CustomErrors::SeaORMError {
             error: err,
@@ -36,28 +52,15 @@ where
         .ok_or(CustomErrors::StringError {
             status: StatusCode::UNAUTHORIZED,
             error: "Invalid credentials provided".to_string(),
-        })?;
-    Ok(user)
+        })
 }
 
-pub async fn password_check<'a, C>(
-    db: &'a C,
-    cookie: Cookies,
-    cookie_key: &'a Key,
-    password_to_check: &'a str,
-) -> Result<UserModel, CustomErrors>
-where
-    C: ConnectionTrait + TransactionTrait,
-{
-    let user_cookie = cookie_check(db, cookie, cookie_key).await?;
-
-    Ok(check_password(password_to_check, &user_cookie.password)
-        .and(Ok(user_cookie))
-        .map_err(|err| CustomErrors::Argon2Error {
-            status: StatusCode::BAD_REQUEST,
-            error: err,
-            message: Some("Неверный пароль".to_owned()),
-        })?)
+pub fn password_check(user: &UserModel, password_to_check: &str) -> Result<(), CustomErrors> {
+    check_password(password_to_check, &user.password).map_err(|err| CustomErrors::Argon2Error {
+        status: StatusCode::BAD_REQUEST,
+        error: err,
+        message: Some("Неверный пароль".to_owned()),
+    })
 }
 
 pub fn hash_password(new_password: &str) -> String {
@@ -72,3 +75,260 @@ pub fn check_password(password_to_check: &str, actual_password: &str) -> Result<
     let parsed_hash = PasswordHash::new(&actual_password).expect("Cant parse actual password");
     Argon2::default().verify_password(password_to_check.as_bytes(), &parsed_hash)
 }
+
+// Чем подтвержден запрос: cookie сессии или токеном из заголовка Authorization
+#[derive(Clone)]
+pub enum Credential {
+    Session(SessionModel),
+    AccessToken(AccessTokenModel),
+}
+
+impl Credential {
+    // Сессия дает все права, токен - только права своих областей
+    pub fn require_scope(&self, allowed: &[TokenScope]) -> Result<(), CustomErrors> {
+        let Credential::AccessToken(access_token) = self else {
+            return Ok(());
+        };
+        if token_scopes(access_token)
+            .iter()
+            .any(|scope| allowed.contains(scope))
+        {
+            return Ok(());
+        }
+
+        Err(CustomErrors::StringError {
+            status: StatusCode::FORBIDDEN,
+            error: format!(
+                "Токену не хватает прав, нужна одна из областей: {}",
+                allowed
+                    .iter()
+                    .map(TokenScope::as_str)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        })
+    }
+}
+
+// Пользователь из cookie или токена. Обработчик с этим параметром недоступен без входа,
+// пользователь загружается один раз за запрос
+#[derive(Clone)]
+pub struct CurrentUser(pub UserModel);
+
+// Сессия, из которой пришел запрос. Токенам недоступно: управлять сессиями, токенами
+// и паролем можно только после входа по паролю
+pub struct CurrentSession(pub SessionModel);
+
+// Пользователь для выгрузки и восстановления резервных копий, токену нужна область backup
+pub struct BackupUser(pub UserModel);
+
+// Для обработчиков, доступных и без входа
+pub struct OptionalUser(pub Option<UserModel>);
+
+fn unauthorized() -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::UNAUTHORIZED,
+        error: "Not authorized".to_string(),
+    }
+}
+
+fn bearer_token(parts: &Parts) -> Option<&str> {
+    parts
+        .headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().split_once(' '))
+        // Схема авторизации не зависит от регистра (RFC 9110)
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("Bearer"))
+        .map(|(_, token)| token.trim())
+}
+
+async fn authenticate(
+    parts: &mut Parts,
+    state: &AppState,
+) -> Result<(Credential, UserModel), CustomErrors> {
+    if let Some(authenticated) = parts.extensions.get::<(Credential, UserModel)>() {
+        return Ok(authenticated.clone());
+    }
+
+    let authenticated = match bearer_token(parts) {
+        Some(token) => {
+            let (access_token, user) = find_access_token(&state.db_sea, token)
+                .await
+                .map_err(|err| CustomErrors::SeaORMError {
+                    error: err,
+                    message: None,
+                })?
+                .ok_or_else(unauthorized)?;
+            (Credential::AccessToken(access_token), user)
+        }
+        None => {
+            let cookie =
+                Cookies::from_request_parts(parts, state)
+                    .await
+                    .map_err(|(status, error)| CustomErrors::StringError {
+                        status,
+                        error: error.to_string(),
+                    })?;
+            let (session, user) =
+                cookie_check(&state.db_sea, cookie, &state.config.cookie_key).await?;
+            (Credential::Session(session), user)
+        }
+    };
+    parts.extensions.insert(authenticated.clone());
+    Ok(authenticated)
+}
+
+// Токен read-only дает только чтение, изменения требуют systems:write
+fn method_scopes(parts: &Parts) -> &'static [TokenScope] {
+    match parts.method {
+        Method::GET | Method::HEAD => &[TokenScope::ReadOnly, TokenScope::SystemsWrite],
+        _ => &[TokenScope::SystemsWrite],
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentUser {
+    type Rejection = CustomErrors;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (credential, user) = authenticate(parts, state).await?;
+        credential.require_scope(method_scopes(parts))?;
+        Ok(CurrentUser(user))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for CurrentSession {
+    type Rejection = CustomErrors;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        match authenticate(parts, state).await? {
+            (Credential::Session(session), _) => Ok(CurrentSession(session)),
+            (Credential::AccessToken(_), _) => Err(CustomErrors::StringError {
+                status: StatusCode::FORBIDDEN,
+                error: "Действие недоступно по токену, войдите по паролю".to_string(),
+            }),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for BackupUser {
+    type Rejection = CustomErrors;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let (credential, user) = authenticate(parts, state).await?;
+        credential.require_scope(&[TokenScope::Backup])?;
+        Ok(BackupUser(user))
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for OptionalUser {
+    type Rejection = CustomErrors;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        match CurrentUser::from_request_parts(parts, state).await {
+            Ok(CurrentUser(user)) => Ok(OptionalUser(Some(user))),
+            Err(CustomErrors::StringError {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }) => Ok(OptionalUser(None)),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+// User-Agent и адрес клиента для новой сессии
+#[async_trait]
+impl<S> FromRequestParts<S> for SessionClientModel
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|user_agent| user_agent.chars().take(512).collect());
+        let ip = parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(address)| address.ip().to_string());
+
+        Ok(SessionClientModel { user_agent, ip })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use serde_json::{json, Value};
+
+    fn token(scopes: Value) -> Credential {
+        Credential::AccessToken(AccessTokenModel {
+            id: 1,
+            user_id: 1,
+            name: "ci".to_string(),
+            prefix: "esat_abc".to_string(),
+            token_hash: String::new(),
+            scopes,
+            created_at: Utc::now().naive_utc(),
+            last_used_at: None,
+        })
+    }
+
+    fn parts(authorization: &str) -> Parts {
+        axum::http::Request::builder()
+            .header(header::AUTHORIZATION, authorization)
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0
+    }
+
+    #[test]
+    fn token_gets_only_its_scopes() {
+        let read_only = token(json!(["read-only"]));
+        assert!(read_only.require_scope(&[TokenScope::ReadOnly]).is_ok());
+        assert!(read_only
+            .require_scope(&[TokenScope::SystemsWrite])
+            .is_err());
+
+        // Пустой или нераспознанный список областей прав не дает
+        for scopes in [json!([]), json!(["admin"]), json!("read-only")] {
+            assert!(token(scopes)
+                .require_scope(&[TokenScope::ReadOnly])
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn bearer_scheme_ignores_case() {
+        for authorization in [
+            "Bearer esat_abc_1",
+            "bearer esat_abc_1",
+            "BEARER  esat_abc_1 ",
+        ] {
+            assert_eq!(bearer_token(&parts(authorization)), Some("esat_abc_1"));
+        }
+        assert_eq!(bearer_token(&parts("Basic esat_abc_1")), None);
+        assert_eq!(bearer_token(&parts("Bearer")), None);
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{
+    constants::{MAX_BACKUP_SIZE, MAX_STREAMED_BACKUP_SIZE},
+    error::CustomErrors,
+    utils::transfer::too_large,
+};
+use bincode::Options;
+use entity::{
+    backup::SystemBackupModel,
+    backup_legacy::{SystemBackupModelV1, SystemBackupModelV2},
+};
+use http::StatusCode;
+use serde::de::DeserializeOwned;
+use std::io::{self, Read, Write};
+
+// Заголовок перед данными: сигнатура и номер версии (u16, little-endian).
+// Копии без заголовка записывались версиями 1-2, их раскладка подбирается перебором.
+// Заголовок пишется с версии 3
+pub const BACKUP_MAGIC: &[u8; 4] = b"ESBK";
+pub const BACKUP_FORMAT_VERSION: u16 = 3;
+const LAST_HEADERLESS_VERSION: u16 = 2;
+
+const HEADER_LEN: usize = BACKUP_MAGIC.len() + 2;
+
+// Те же настройки, что у bincode::serialize, но лишние байты в конце - ошибка,
+// иначе старую копию можно принять за более раннюю версию
+fn decode<T: DeserializeOwned>(payload: &[u8]) -> Option<T> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(payload)
+        .ok()
+}
+
+fn decode_version(version: u16, payload: &[u8]) -> Option<SystemBackupModel> {
+    match version {
+        1 => decode::<SystemBackupModelV1>(payload)
+            .map(SystemBackupModelV2::from)
+            .map(SystemBackupModel::from),
+        2 => decode::<SystemBackupModelV2>(payload).map(SystemBackupModel::from),
+        3 => decode(payload),
+        _ => None,
+    }
+}
+
+// Текущая версия пишется прямо в поток, без буфера на всю копию
+pub fn write_backup<W: Write>(backup: &SystemBackupModel, mut writer: W) -> io::Result<()> {
+    writer.write_all(BACKUP_MAGIC)?;
+    writer.write_all(&BACKUP_FORMAT_VERSION.to_le_bytes())?;
+    bincode::serialize_into(writer, backup).map_err(io::Error::other)
+}
+
+// Текущая версия читается из потока; старые версии собираются в память и разбираются
+// через decode_backup, им нужен перебор раскладок
+pub fn read_backup<R: Read>(mut reader: R) -> Result<SystemBackupModel, CustomErrors> {
+    let read_error = |_| CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error: "Ошибка при загрузке файла".to_string(),
+    };
+    let damaged = || CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error: format!(
+            "Резервная копия версии {} повреждена",
+            BACKUP_FORMAT_VERSION
+        ),
+    };
+
+    let mut data = Vec::with_capacity(HEADER_LEN);
+    reader
+        .by_ref()
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut data)
+        .map_err(read_error)?;
+    if !data.starts_with(BACKUP_MAGIC)
+        || data[BACKUP_MAGIC.len()..] != BACKUP_FORMAT_VERSION.to_le_bytes()
+    {
+        reader
+            .take(MAX_BACKUP_SIZE as u64 + 1)
+            .read_to_end(&mut data)
+            .map_err(read_error)?;
+        if data.len() > MAX_BACKUP_SIZE {
+            return Err(too_large(MAX_BACKUP_SIZE));
+        }
+        return decode_backup(&data);
+    }
+
+    let backup = bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .with_limit(MAX_STREAMED_BACKUP_SIZE)
+        .deserialize_from(&mut reader)
+        .map_err(|_| damaged())?;
+    // Как и reject_trailing_bytes в decode: лишние данные в конце - повреждение
+    if reader.read(&mut [0u8; 1]).map_err(read_error)? != 0 {
+        return Err(damaged());
+    }
+
+    Ok(backup)
+}
+
+pub fn decode_backup(data: &[u8]) -> Result<SystemBackupModel, CustomErrors> {
+    if data.len() < HEADER_LEN || !data.starts_with(BACKUP_MAGIC) {
+        return (1..=LAST_HEADERLESS_VERSION)
+            .rev()
+            .find_map(|version| decode_version(version, data))
+            .ok_or(CustomErrors::StringError {
+                status: StatusCode::BAD_REQUEST,
+                error: "Не удалось определить версию формата резервной копии".to_string(),
+            });
+    }
+
+    let version = u16::from_le_bytes([data[BACKUP_MAGIC.len()], data[BACKUP_MAGIC.len() + 1]]);
+    if version <= LAST_HEADERLESS_VERSION || version > BACKUP_FORMAT_VERSION {
+        return Err(CustomErrors::StringError {
+            status: StatusCode::BAD_REQUEST,
+            error: format!(
+                "Неизвестная версия формата резервной копии {}, поддерживаются версии 1-{}",
+                version, BACKUP_FORMAT_VERSION
+            ),
+        });
+    }
+
+    decode_version(version, &data[HEADER_LEN..]).ok_or(CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error: format!("Резервная копия версии {} повреждена", version),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::legacy_clause;
+    use entity::{
+        backup::{BackupAnswerModel, BackupQuestionModel, BackupRuleModel, BackupTestCaseModel},
+        backup_legacy::{BackupRuleModelV1, BackupSystemModelV1},
+        sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+    };
+    use sea_orm::prelude::DateTime;
+
+    fn backup_v1() -> SystemBackupModelV1 {
+        SystemBackupModelV1 {
+            system: BackupSystemModelV1 {
+                id: 1,
+                user_id: 1,
+                about: None,
+                created_at: DateTime::default(),
+                updated_at: DateTime::default(),
+                name: "Система".to_string(),
+                private: false,
+                image_uri: None,
+            },
+            objects: vec![],
+            object_attribute_attributevalue: vec![],
+            attributes: vec![],
+            attributes_values: vec![],
+            rules: vec![BackupRuleModelV1 {
+                id: 1,
+                system_id: 1,
+                attribute_rule: false,
+            }],
+            rule_attribute_attributevalue: vec![],
+            clauses: vec![
+                legacy_clause(1, 1, Operatorenum::Equal, "да"),
+                legacy_clause(2, 2, Operatorenum::Above, "5"),
+                legacy_clause(3, 2, Operatorenum::Below, "2,5"),
+                legacy_clause(4, 2, Operatorenum::Equal, "True"),
+                legacy_clause(5, 2, Operatorenum::Equal, "кот"),
+            ],
+            questions: vec![
+                BackupQuestionModel {
+                    id: 1,
+                    system_id: 1,
+                    body: "Есть шерсть?".to_string(),
+                    with_chooses: true,
+                },
+                BackupQuestionModel {
+                    id: 2,
+                    system_id: 1,
+                    body: "Значение".to_string(),
+                    with_chooses: false,
+                },
+            ],
+            answers: vec![
+                BackupAnswerModel {
+                    id: 10,
+                    question_id: 1,
+                    body: "да".to_string(),
+                },
+                BackupAnswerModel {
+                    id: 11,
+                    question_id: 1,
+                    body: "нет".to_string(),
+                },
+            ],
+            rule_question_answer: vec![],
+        }
+    }
+
+    const CONDITION: &str = r#"{"Or":[{"Clause":1},{"Clause":2}]}"#;
+
+    fn headerless_payloads() -> Vec<(u16, Vec<u8>)> {
+        let v1 = backup_v1();
+        let mut payloads = vec![(1, bincode::serialize(&v1).unwrap())];
+        let v2 = SystemBackupModelV2::from(v1);
+        payloads.push((2, bincode::serialize(&v2).unwrap()));
+        payloads
+    }
+
+    // Текущая версия: своя уверенность у правила, дерево условий и тестовый сценарий
+    fn current_backup() -> SystemBackupModel {
+        let (_, payload) = headerless_payloads().pop().unwrap();
+        let mut backup = decoded(2, &payload);
+        backup.rules[0].certainty_factor = 0.5;
+        backup.rules[0].condition = Some(CONDITION.to_string());
+        backup.test_cases.push(BackupTestCaseModel {
+            id: 1,
+            system_id: 1,
+            name: "Сценарий".to_string(),
+            answers: "[]".to_string(),
+            expected_object_ids: "[]".to_string(),
+            expected_attribute_value_ids: "[]".to_string(),
+            created_at: DateTime::default(),
+        });
+        backup
+    }
+
+    fn with_header(version: u16, payload: &[u8]) -> Vec<u8> {
+        let mut data = Vec::from(BACKUP_MAGIC.as_slice());
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(payload);
+        data
+    }
+
+    fn decoded(version: u16, data: &[u8]) -> SystemBackupModel {
+        decode_backup(data).unwrap_or_else(|_| panic!("версия {} не распознана", version))
+    }
+
+    fn error_text(result: Result<SystemBackupModel, CustomErrors>) -> String {
+        match result {
+            Err(CustomErrors::StringError { status, error }) => {
+                assert_eq!(status, StatusCode::BAD_REQUEST);
+                error
+            }
+            Err(_) => panic!("ожидалась StringError"),
+            Ok(_) => panic!("ожидалась ошибка"),
+        }
+    }
+
+    fn assert_upgraded(version: u16, backup: &SystemBackupModel) {
+        assert_eq!(backup.system.name, "Система");
+        assert_eq!(backup.system.stars, 0);
+
+        let rule: &BackupRuleModel = &backup.rules[0];
+        assert_eq!(rule.certainty_factor, 1.0, "версия {}", version);
+        assert_eq!(rule.condition, None, "версия {}", version);
+        assert!(backup.test_cases.is_empty());
+        assert!(backup.media.is_empty());
+    }
+
+    #[test]
+    fn current_backup_round_trips() {
+        let backup = current_backup();
+
+        let mut encoded = Vec::new();
+        write_backup(&backup, &mut encoded).unwrap();
+        assert!(encoded.starts_with(BACKUP_MAGIC));
+
+        assert_eq!(
+            bincode::serialize(&decoded(BACKUP_FORMAT_VERSION, &encoded)).unwrap(),
+            bincode::serialize(&backup).unwrap()
+        );
+        assert_eq!(
+            bincode::serialize(&read_backup(encoded.as_slice()).ok().unwrap()).unwrap(),
+            bincode::serialize(&backup).unwrap()
+        );
+
+        // Обрезанная или дополненная копия текущей версии при чтении потоком не принимается
+        assert!(read_backup(&encoded[..encoded.len() - 1]).is_err());
+        encoded.push(0);
+        assert!(read_backup(encoded.as_slice()).is_err());
+    }
+
+    #[test]
+    fn legacy_backups_are_read_from_stream() {
+        for (version, payload) in headerless_payloads() {
+            let backup = read_backup(payload.as_slice())
+                .unwrap_or_else(|_| panic!("версия {} не распознана", version));
+            assert_upgraded(version, &backup);
+        }
+    }
+
+    #[test]
+    fn headerless_versions_are_detected_and_upgraded() {
+        for (version, payload) in headerless_payloads() {
+            assert_upgraded(version, &decoded(version, &payload));
+        }
+    }
+
+    // Тип значения условий в копиях без заголовка восстанавливается по тексту
+    #[test]
+    fn legacy_clauses_get_value_types() {
+        let (_, payload) = headerless_payloads().remove(0);
+        let backup = decoded(1, &payload);
+
+        let clauses: Vec<(Valuetypeenum, &str)> = backup
+            .clauses
+            .iter()
+            .map(|clause| (clause.value_type, clause.compared_value.as_str()))
+            .collect();
+        assert_eq!(
+            clauses,
+            vec![
+                (Valuetypeenum::Answer, "10"),
+                (Valuetypeenum::Integer, "5"),
+                (Valuetypeenum::Decimal, "2.5"),
+                (Valuetypeenum::Boolean, "true"),
+                (Valuetypeenum::Text, "кот"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_and_damaged_backups() {
+        let payload = bincode::serialize(&current_backup()).unwrap();
+
+        // Версии без заголовка с заголовком не записывались
+        for version in [0, LAST_HEADERLESS_VERSION, BACKUP_FORMAT_VERSION + 1] {
+            let unknown = error_text(decode_backup(&with_header(version, &payload)));
+            assert!(unknown.starts_with("Неизвестная версия"), "{}", unknown);
+        }
+
+        let truncated = with_header(BACKUP_FORMAT_VERSION, &payload[..payload.len() - 1]);
+        let damaged = error_text(decode_backup(&truncated));
+        assert_eq!(damaged, "Резервная копия версии 3 повреждена");
+
+        // Лишний байт в конце не дает принять копию за другую версию
+        let mut trailing = payload;
+        trailing.push(0);
+        let garbage = error_text(decode_backup(&trailing));
+        assert_eq!(
+            garbage,
+            "Не удалось определить версию формата резервной копии"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+// Источник текущего времени для двухфакторной аутентификации. Коды TOTP и сроки входа
+// считаются от него, поэтому с Fixed вход с 2FA проверяется без сети и синхронизации часов
+#[derive(Debug, Clone, Copy)]
+pub enum Clock {
+    System,
+    Fixed(DateTime<Utc>),
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Fixed(time) => *time,
+        }
+    }
+
+    pub fn now_naive(&self) -> NaiveDateTime {
+        self.now().naive_utc()
+    }
+
+    pub fn timestamp(&self) -> u64 {
+        self.now().timestamp().max(0) as u64
+    }
+}
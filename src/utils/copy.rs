@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::error::CustomErrors;
+use crate::{error::CustomErrors, models::inference::GivenAnswerModel};
 use entity::{
     answers::{ActiveModel as AnswerActiveModel, Model as AnswerModel},
     attributes::{ActiveModel as AttributeActiveModel, Model as AttributeModel},
@@ -19,22 +19,24 @@ use entity::{
     rule_question_answer::{
         ActiveModel as RuleQuestionAnswerActiveModel, Model as RuleQuestionAnswerModel,
     },
-    rules::{ActiveModel as RuleActiveModel, Model as RuleModel},
+    rules::{ActiveModel as RuleActiveModel, ConditionNode, Model as RuleModel},
+    sea_orm_active_enums::Valuetypeenum,
     systems::{ActiveModel as SystemActiveModel, Model as SystemModel},
+    test_cases::{ActiveModel as TestCaseActiveModel, Model as TestCaseModel},
 };
 use futures::{
     future::try_join_all,
     stream::{StreamExt, TryStreamExt},
 };
 use http::StatusCode;
-use sea_orm::{ActiveModelTrait, ConnectionTrait, Set, TransactionTrait};
+use sea_orm::{ActiveModelTrait, ConnectionTrait, Set, TransactionTrait, Unchanged};
+use serde_json::json;
 
 pub async fn copy_system<C>(db: &C, old_system: &SystemModel) -> Result<SystemModel, CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
 {
-    let mut split_name = old_system.name.clone();
-    let _ = split_name.split_off(94);
+    let split_name: String = old_system.name.chars().take(94).collect();
 
     let model = SystemActiveModel {
         user_id: Set(old_system.user_id),
@@ -169,6 +171,7 @@ where
         let model = RuleActiveModel {
             system_id: Set(new_system_id),
             attribute_rule: Set(rule.attribute_rule),
+            certainty_factor: Set(rule.certainty_factor),
             ..Default::default()
         };
         model.insert(db)
@@ -281,6 +284,7 @@ pub async fn copy_clauses<C>(
     old_clauses: &Vec<ClauseModel>,
     rule_map: &HashMap<i32, i32>,
     question_map: &HashMap<i32, i32>,
+    answer_map: &HashMap<i32, i32>,
 ) -> Result<Vec<ClauseModel>, CustomErrors>
 where
     C: ConnectionTrait + TransactionTrait,
@@ -301,9 +305,26 @@ where
                     error: "Ошибка в расшифровке системы".to_string(),
                 })?;
 
+        // Для ссылки на ответ сравниваемое значение - id ответа, его тоже нужно заменить
+        let compared_value = match old_clause.value_type {
+            Valuetypeenum::Answer => old_clause
+                .compared_value
+                .trim()
+                .parse::<i32>()
+                .ok()
+                .and_then(|answer_id| answer_map.get(&answer_id))
+                .ok_or(CustomErrors::StringError {
+                    status: StatusCode::UNPROCESSABLE_ENTITY,
+                    error: "Ошибка в расшифровке системы".to_string(),
+                })?
+                .to_string(),
+            _ => old_clause.compared_value.clone(),
+        };
+
         let model = ClauseActiveModel {
             rule_id: Set(*new_rule_id),
-            compared_value: Set(old_clause.compared_value.clone()),
+            compared_value: Set(compared_value),
+            value_type: Set(old_clause.value_type),
             logical_group: Set(old_clause.logical_group.clone()),
             operator: Set(old_clause.operator.clone()),
             question_id: Set(*new_question_id),
@@ -324,6 +345,57 @@ where
     Ok(result)
 }
 
+// Вызывается после copy_clauses: листья деревьев условий ссылаются на id условий
+pub async fn copy_rule_conditions<C>(
+    db: &C,
+    old_rules: &[RuleModel],
+    old_clauses: &[ClauseModel],
+    new_clauses: &[ClauseModel],
+    rule_map: &HashMap<i32, i32>,
+) -> Result<(), CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let clause_map: HashMap<i32, i32> = old_clauses
+        .iter()
+        .zip(new_clauses)
+        .map(|(old_clause, new_clause)| (old_clause.id, new_clause.id))
+        .collect();
+
+    let new_conditions = old_rules.iter().filter_map(|old_rule| {
+        // Листья удаленных условий отбрасываются, иначе map_clauses потеряет все дерево
+        let live_clause_ids: Vec<i32> = old_clauses
+            .iter()
+            .filter(|clause| clause.rule_id == old_rule.id)
+            .map(|clause| clause.id)
+            .collect();
+        let condition = old_rule
+            .condition
+            .clone()
+            .and_then(|condition| serde_json::from_value::<ConditionNode>(condition).ok())?
+            .prune(&live_clause_ids)
+            .and_then(|condition| {
+                condition.map_clauses(&|clause_id| clause_map.get(&clause_id).copied())
+            });
+        let new_rule_id = rule_map.get(&old_rule.id)?;
+
+        let model = RuleActiveModel {
+            id: Unchanged(*new_rule_id),
+            condition: Set(condition.map(|condition| json!(condition))),
+            ..Default::default()
+        };
+        Some(model.update(db))
+    });
+    try_join_all(new_conditions)
+        .await
+        .map_err(|err| CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        })?;
+
+    Ok(())
+}
+
 pub async fn copy_rule_attribute_attributevalues<C>(
     db: &C,
     old_rule_attribute_attributevalues: &Vec<RuleAttributeAttributeValueModel>,
@@ -477,3 +549,76 @@ where
 
     Ok(result)
 }
+
+pub async fn copy_test_cases<C>(
+    db: &C,
+    new_system_id: i32,
+    old_test_cases: &[TestCaseModel],
+    question_map: &HashMap<i32, i32>,
+    answer_map: &HashMap<i32, i32>,
+    object_map: &HashMap<i32, i32>,
+    attributevalue_map: &HashMap<i32, i32>,
+) -> Result<Vec<TestCaseModel>, CustomErrors>
+where
+    C: ConnectionTrait + TransactionTrait,
+{
+    let decode_error = || CustomErrors::StringError {
+        status: StatusCode::UNPROCESSABLE_ENTITY,
+        error: "Ошибка в расшифровке системы".to_string(),
+    };
+    let remap_ids = |ids: &serde_json::Value, map: &HashMap<i32, i32>| {
+        serde_json::from_value::<Vec<i32>>(ids.clone())
+            .ok()
+            .and_then(|ids| {
+                ids.into_iter()
+                    .map(|id| map.get(&id).copied())
+                    .collect::<Option<Vec<i32>>>()
+            })
+            .ok_or_else(decode_error)
+    };
+
+    let new_test_cases = old_test_cases
+        .iter()
+        .map(|old_test_case| {
+            let answers =
+                serde_json::from_value::<Vec<GivenAnswerModel>>(old_test_case.answers.clone())
+                    .map_err(|_| decode_error())?
+                    .into_iter()
+                    .map(|answer| {
+                        Some(GivenAnswerModel {
+                            question_id: *question_map.get(&answer.question_id)?,
+                            answer_id: match answer.answer_id {
+                                Some(answer_id) => Some(*answer_map.get(&answer_id)?),
+                                None => None,
+                            },
+                            ..answer
+                        })
+                    })
+                    .collect::<Option<Vec<GivenAnswerModel>>>()
+                    .ok_or_else(decode_error)?;
+
+            Ok(TestCaseActiveModel {
+                system_id: Set(new_system_id),
+                name: Set(old_test_case.name.clone()),
+                answers: Set(json!(answers)),
+                expected_object_ids: Set(json!(remap_ids(
+                    &old_test_case.expected_object_ids,
+                    object_map
+                )?)),
+                expected_attribute_value_ids: Set(json!(remap_ids(
+                    &old_test_case.expected_attribute_value_ids,
+                    attributevalue_map
+                )?)),
+                ..Default::default()
+            }
+            .insert(db))
+        })
+        .collect::<Result<Vec<_>, CustomErrors>>()?;
+
+    try_join_all(new_test_cases)
+        .await
+        .map_err(|err| CustomErrors::SeaORMError {
+            error: err,
+            message: None,
+        })
+}
@@ -1,8 +1,20 @@
+use crate::{
+    config::BackupKeyring,
+    constants::{BACKUP_CHUNK_SIZE, MAX_BACKUP_SIZE},
+    error::CustomErrors,
+    utils::transfer::too_large,
+};
 use aes_gcm_siv::{
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
     Aes256GcmSiv, Error, Key, Nonce,
 };
+use argon2::{Algorithm, Argon2, Params, Version};
+use http::StatusCode;
+use rand::{thread_rng, Rng};
+use std::io::{self, Write};
 
+// Целиком сейчас только расшифровываются старые копии, зашифровать так нужно лишь тестам
+#[cfg(test)]
 pub fn encrypt_data(key: &[u8], nonce_key: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, Error> {
     let key = Key::<Aes256GcmSiv>::from_slice(key);
 
@@ -22,3 +34,689 @@ pub fn decrypt_data(key: &[u8], nonce_key: &[u8], ciphertext: &[u8]) -> Result<V
 
     Ok(plaintext)
 }
+
+// Прежний конверт резервной копии: сигнатура, длина id ключа (u8), id ключа, случайный nonce,
+// шифротекст. Копии без конверта зашифрованы общим NONCE_KEY
+const ENVELOPE_MAGIC: &[u8; 4] = b"ESBE";
+const NONCE_LEN: usize = 12;
+
+// Разбор конверта: (id ключа, nonce, шифротекст)
+fn open_envelope(data: &[u8]) -> Option<(&str, &[u8], &[u8])> {
+    let rest = data.strip_prefix(ENVELOPE_MAGIC.as_slice())?;
+    let (key_id_len, rest) = rest.split_first()?;
+    if rest.len() < *key_id_len as usize + NONCE_LEN {
+        return None;
+    }
+    let (key_id, rest) = rest.split_at(*key_id_len as usize);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    Some((std::str::from_utf8(key_id).ok()?, nonce, ciphertext))
+}
+
+// Ключи связки в порядке проверки: сначала ключ с указанным id, затем остальные
+fn keys_to_try<'a>(keyring: &'a BackupKeyring, key_id: &'a str) -> impl Iterator<Item = &'a str> {
+    keyring.key(key_id).into_iter().chain(
+        keyring
+            .keys
+            .iter()
+            .filter(move |(id, _)| id != key_id)
+            .map(|(_, key)| key.as_str()),
+    )
+}
+
+// Подходящий ключ определяется проверкой тега
+fn decrypt_backup(keyring: &BackupKeyring, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let (key_id, nonce, ciphertext) = match open_envelope(data) {
+        Some(envelope) => envelope,
+        None => {
+            let nonce = keyring.legacy_nonce.as_ref().ok_or(Error)?;
+            return keyring
+                .keys
+                .iter()
+                .find_map(|(_, key)| decrypt_data(key.as_bytes(), nonce.as_bytes(), data).ok())
+                .ok_or(Error);
+        }
+    };
+
+    keys_to_try(keyring, key_id)
+        .find_map(|key| decrypt_data(key.as_bytes(), nonce, ciphertext).ok())
+        .ok_or(Error)
+}
+
+// Прежняя копия, защищенная паролем: сигнатура, соль, случайный nonce, шифротекст.
+// Параметры Argon2 в ней не записаны - это параметры по умолчанию argon2 0.5
+// (Argon2id, версия 0x13, m = 19456, t = 2, p = 1)
+const PASSPHRASE_MAGIC: &[u8; 4] = b"ESBP";
+const SALT_LEN: usize = 16;
+
+fn derive_key(argon2: &Argon2, passphrase: &str, salt: &[u8]) -> Result<[u8; 32], argon2::Error> {
+    let mut key = [0u8; 32];
+    argon2.hash_password_into(passphrase.as_bytes(), salt, &mut key)?;
+    Ok(key)
+}
+
+// Параметры Argon2 в потоковом заголовке: алгоритм (u8), версия (u8),
+// m, t, p (u32, little-endian). С ними копию можно открыть и после смены
+// параметров по умолчанию в новой версии argon2
+const KDF_PARAMS_LEN: usize = 14;
+// Параметры приходят из загружаемого файла, поэтому ограничены сверху:
+// иначе одна загрузка могла бы занять всю память и процессор сервера
+const MAX_KDF_MEMORY: u32 = 256 * 1024;
+const MAX_KDF_ITERATIONS: u32 = 16;
+const MAX_KDF_PARALLELISM: u32 = 16;
+
+fn write_kdf_params(header: &mut Vec<u8>, algorithm: Algorithm, version: Version, params: &Params) {
+    header.push(match algorithm {
+        Algorithm::Argon2d => 0,
+        Algorithm::Argon2i => 1,
+        Algorithm::Argon2id => 2,
+    });
+    header.push(version as u8);
+    header.extend_from_slice(&params.m_cost().to_le_bytes());
+    header.extend_from_slice(&params.t_cost().to_le_bytes());
+    header.extend_from_slice(&params.p_cost().to_le_bytes());
+}
+
+fn read_kdf_params(data: &[u8]) -> Option<Argon2<'static>> {
+    let algorithm = match data.first()? {
+        0 => Algorithm::Argon2d,
+        1 => Algorithm::Argon2i,
+        2 => Algorithm::Argon2id,
+        _ => return None,
+    };
+    let version = Version::try_from(u32::from(*data.get(1)?)).ok()?;
+    let cost = |index: usize| {
+        let bytes = data.get(2 + index * 4..6 + index * 4)?;
+        Some(u32::from_le_bytes(bytes.try_into().ok()?))
+    };
+    let (m_cost, t_cost, p_cost) = (cost(0)?, cost(1)?, cost(2)?);
+    if m_cost > MAX_KDF_MEMORY || t_cost > MAX_KDF_ITERATIONS || p_cost > MAX_KDF_PARALLELISM {
+        return None;
+    }
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32)).ok()?;
+
+    Some(Argon2::new(algorithm, version, params))
+}
+
+fn decrypt_backup_with_passphrase(passphrase: &str, data: &[u8]) -> Result<Vec<u8>, Error> {
+    let rest = data
+        .strip_prefix(PASSPHRASE_MAGIC.as_slice())
+        .ok_or(Error)?;
+    if rest.len() < SALT_LEN + NONCE_LEN {
+        return Err(Error);
+    }
+    let (salt, rest) = rest.split_at(SALT_LEN);
+    let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+
+    let key = derive_key(&Argon2::default(), passphrase, salt).map_err(|_| Error)?;
+    decrypt_data(&key, nonce, ciphertext)
+}
+
+// Потоковый конверт: сигнатура, вид ключа (u8), для ключа сервера - длина id ключа (u8)
+// и id ключа, для пароля - параметры Argon2 и соль, затем префикс nonce. Дальше идут части копии:
+// признак последней части (u8), длина шифротекста (u32, little-endian), шифротекст.
+// Nonce части - префикс, номер части (u32, big-endian) и признак последней (схема STREAM),
+// а заголовок входит в AAD каждой части, поэтому части нельзя незаметно переставить,
+// подменить или отрезать
+const STREAM_MAGIC: &[u8; 4] = b"ESBS";
+const SERVER_KEY: u8 = 0;
+const PASSPHRASE_KEY: u8 = 1;
+const NONCE_PREFIX_LEN: usize = 7;
+const FRAME_HEADER_LEN: usize = 5;
+const TAG_LEN: usize = 16;
+
+fn stream_cipher(key: &[u8]) -> Aes256GcmSiv {
+    Aes256GcmSiv::new(Key::<Aes256GcmSiv>::from_slice(key))
+}
+
+fn chunk_nonce(header: &[u8], counter: u32, last: bool) -> [u8; NONCE_LEN] {
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce[..NONCE_PREFIX_LEN].copy_from_slice(&header[header.len() - NONCE_PREFIX_LEN..]);
+    nonce[NONCE_PREFIX_LEN..NONCE_LEN - 1].copy_from_slice(&counter.to_be_bytes());
+    nonce[NONCE_LEN - 1] = u8::from(last);
+    nonce
+}
+
+// Шифрует копию частями по BACKUP_CHUNK_SIZE по мере записи, заголовок пишется сразу
+pub struct BackupEncryptor<W: Write> {
+    cipher: Aes256GcmSiv,
+    header: Vec<u8>,
+    counter: u32,
+    buffer: Vec<u8>,
+    inner: W,
+}
+
+impl<W: Write> BackupEncryptor<W> {
+    pub fn with_keyring(keyring: &BackupKeyring, inner: W) -> io::Result<Self> {
+        let mut header = Vec::from(STREAM_MAGIC.as_slice());
+        header.push(SERVER_KEY);
+        header.push(keyring.current_key_id.len() as u8);
+        header.extend_from_slice(keyring.current_key_id.as_bytes());
+
+        Self::new(
+            stream_cipher(keyring.current_key().as_bytes()),
+            header,
+            inner,
+        )
+    }
+
+    pub fn with_passphrase(passphrase: &str, inner: W) -> io::Result<Self> {
+        Self::with_argon2(
+            Algorithm::default(),
+            Version::default(),
+            Params::default(),
+            passphrase,
+            inner,
+        )
+    }
+
+    fn with_argon2(
+        algorithm: Algorithm,
+        version: Version,
+        params: Params,
+        passphrase: &str,
+        inner: W,
+    ) -> io::Result<Self> {
+        let mut salt = [0u8; SALT_LEN];
+        thread_rng().fill(&mut salt);
+        let mut header = Vec::from(STREAM_MAGIC.as_slice());
+        header.push(PASSPHRASE_KEY);
+        write_kdf_params(&mut header, algorithm, version, &params);
+        header.extend_from_slice(&salt);
+        let argon2 = Argon2::new(algorithm, version, params);
+        let key = derive_key(&argon2, passphrase, &salt)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+
+        Self::new(stream_cipher(&key), header, inner)
+    }
+
+    fn new(cipher: Aes256GcmSiv, mut header: Vec<u8>, mut inner: W) -> io::Result<Self> {
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        thread_rng().fill(&mut nonce_prefix);
+        header.extend_from_slice(&nonce_prefix);
+        inner.write_all(&header)?;
+
+        Ok(BackupEncryptor {
+            cipher,
+            header,
+            counter: 0,
+            buffer: Vec::with_capacity(BACKUP_CHUNK_SIZE),
+            inner,
+        })
+    }
+
+    fn seal_chunk(&mut self, len: usize, last: bool) -> io::Result<()> {
+        let nonce = chunk_nonce(&self.header, self.counter, last);
+        let ciphertext = self
+            .cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &self.buffer[..len],
+                    aad: &self.header,
+                },
+            )
+            .map_err(|_| io::Error::other("Ошибка в создании резервной копии"))?;
+        self.counter = self
+            .counter
+            .checked_add(1)
+            .ok_or(io::Error::other("Слишком большая резервная копия"))?;
+        self.buffer.drain(..len);
+
+        let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + ciphertext.len());
+        frame.push(u8::from(last));
+        frame.extend_from_slice(&(ciphertext.len() as u32).to_le_bytes());
+        frame.extend(ciphertext);
+        self.inner.write_all(&frame)
+    }
+
+    // Остаток становится последней частью; она пустая, только если пуста вся копия
+    pub fn finish(mut self) -> io::Result<W> {
+        self.seal_chunk(self.buffer.len(), true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for BackupEncryptor<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(data);
+        // Полная часть уходит, только когда за ней есть данные: последнюю помечает finish
+        while self.buffer.len() > BACKUP_CHUNK_SIZE {
+            self.seal_chunk(BACKUP_CHUNK_SIZE, false)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+fn damaged(passphrase_backup: bool) -> CustomErrors {
+    let message = if passphrase_backup {
+        "Неверный пароль или файл поврежден"
+    } else {
+        "Файл поврежден или изменен"
+    };
+    CustomErrors::AesGsmError {
+        error: Error,
+        message: Some(message.to_string()),
+    }
+}
+
+enum DecryptorState {
+    // Формат еще не ясен: мало данных
+    Start,
+    // Ключ сервера подбирается по первой части, дальше остается один шифр
+    Stream {
+        header: Vec<u8>,
+        ciphers: Vec<Aes256GcmSiv>,
+        counter: u32,
+        finished: bool,
+    },
+    // Старые форматы зашифрованы целиком и расшифровываются только полностью прочитанными
+    Whole,
+}
+
+// Расшифровывает загружаемую копию по мере поступления данных
+pub struct BackupDecryptor<'a> {
+    keyring: &'a BackupKeyring,
+    passphrase: Option<&'a str>,
+    passphrase_backup: bool,
+    buffer: Vec<u8>,
+    state: DecryptorState,
+}
+
+impl<'a> BackupDecryptor<'a> {
+    pub fn new(keyring: &'a BackupKeyring, passphrase: Option<&'a str>) -> Self {
+        BackupDecryptor {
+            keyring,
+            passphrase,
+            passphrase_backup: false,
+            buffer: Vec::new(),
+            state: DecryptorState::Start,
+        }
+    }
+
+    // Известно после первых байтов потоковой копии или после finish для старой
+    pub fn is_passphrase_backup(&self) -> bool {
+        self.passphrase_backup
+    }
+
+    fn damaged(&self) -> CustomErrors {
+        damaged(self.passphrase_backup)
+    }
+
+    fn passphrase(&self) -> Result<&'a str, CustomErrors> {
+        self.passphrase.ok_or(CustomErrors::StringError {
+            status: StatusCode::BAD_REQUEST,
+            error: "Резервная копия защищена паролем".to_string(),
+        })
+    }
+
+    // Возвращает расшифрованные данные всех частей, которые пришли целиком
+    pub fn push(&mut self, data: &[u8]) -> Result<Vec<u8>, CustomErrors> {
+        self.buffer.extend_from_slice(data);
+        if matches!(self.state, DecryptorState::Start) && !self.read_header(false)? {
+            return Ok(Vec::new());
+        }
+
+        match self.state {
+            DecryptorState::Whole if self.buffer.len() > MAX_BACKUP_SIZE => {
+                Err(too_large(MAX_BACKUP_SIZE))
+            }
+            DecryptorState::Whole => Ok(Vec::new()),
+            _ => self.open_chunks(),
+        }
+    }
+
+    // Конец загрузки: остаток старой копии или ошибка, если поток оборван
+    pub fn finish(&mut self) -> Result<Vec<u8>, CustomErrors> {
+        if matches!(self.state, DecryptorState::Start) {
+            self.read_header(true)?;
+        }
+
+        match self.state {
+            DecryptorState::Whole => self.open_whole(),
+            _ => {
+                let plaintext = self.open_chunks()?;
+                match self.state {
+                    DecryptorState::Stream { finished: true, .. } if self.buffer.is_empty() => {
+                        Ok(plaintext)
+                    }
+                    _ => Err(self.damaged()),
+                }
+            }
+        }
+    }
+
+    // false - данных пока мало, чтобы разобрать заголовок
+    fn read_header(&mut self, at_end: bool) -> Result<bool, CustomErrors> {
+        let magic_len = self.buffer.len().min(STREAM_MAGIC.len());
+        if self.buffer[..magic_len] != STREAM_MAGIC[..magic_len]
+            || (at_end && magic_len < STREAM_MAGIC.len())
+        {
+            self.state = DecryptorState::Whole;
+            return Ok(true);
+        }
+
+        let rest = &self.buffer[magic_len..];
+        let key_len = match (rest.first(), rest.get(1)) {
+            (Some(&SERVER_KEY), Some(key_id_len)) => 2 + *key_id_len as usize,
+            (Some(&PASSPHRASE_KEY), _) => 1 + KDF_PARAMS_LEN + SALT_LEN,
+            (Some(&SERVER_KEY) | None, _) if !at_end => return Ok(false),
+            _ => return Err(self.damaged()),
+        };
+        let header_len = STREAM_MAGIC.len() + key_len + NONCE_PREFIX_LEN;
+        if self.buffer.len() < header_len {
+            return if at_end {
+                Err(self.damaged())
+            } else {
+                Ok(false)
+            };
+        }
+
+        let header: Vec<u8> = self.buffer.drain(..header_len).collect();
+        let key_data = &header[STREAM_MAGIC.len() + 1..STREAM_MAGIC.len() + key_len];
+        let ciphers = if header[STREAM_MAGIC.len()] == PASSPHRASE_KEY {
+            self.passphrase_backup = true;
+            let passphrase = self.passphrase()?;
+            let (params, salt) = key_data.split_at(KDF_PARAMS_LEN);
+            let argon2 = read_kdf_params(params).ok_or(self.damaged())?;
+            let key = derive_key(&argon2, passphrase, salt).map_err(|_| self.damaged())?;
+            vec![stream_cipher(&key)]
+        } else {
+            let key_id = std::str::from_utf8(&key_data[1..]).unwrap_or_default();
+            keys_to_try(self.keyring, key_id)
+                .map(|key| stream_cipher(key.as_bytes()))
+                .collect()
+        };
+        self.state = DecryptorState::Stream {
+            header,
+            ciphers,
+            counter: 0,
+            finished: false,
+        };
+        Ok(true)
+    }
+
+    fn open_chunks(&mut self) -> Result<Vec<u8>, CustomErrors> {
+        let passphrase_backup = self.passphrase_backup;
+        let DecryptorState::Stream {
+            header,
+            ciphers,
+            counter,
+            finished,
+        } = &mut self.state
+        else {
+            return Err(damaged(passphrase_backup));
+        };
+
+        let mut plaintext = Vec::new();
+        let mut position = 0;
+        while self.buffer.len() - position >= FRAME_HEADER_LEN {
+            let frame = &self.buffer[position..];
+            let last = match frame[0] {
+                _ if *finished => return Err(damaged(passphrase_backup)),
+                0 => false,
+                1 => true,
+                _ => return Err(damaged(passphrase_backup)),
+            };
+            let len = u32::from_le_bytes([frame[1], frame[2], frame[3], frame[4]]) as usize;
+            if len > BACKUP_CHUNK_SIZE + TAG_LEN {
+                return Err(damaged(passphrase_backup));
+            }
+            let Some(ciphertext) = frame.get(FRAME_HEADER_LEN..FRAME_HEADER_LEN + len) else {
+                break;
+            };
+
+            let nonce = chunk_nonce(header, *counter, last);
+            let payload = || Payload {
+                msg: ciphertext,
+                aad: header.as_slice(),
+            };
+            let Some((index, chunk)) = ciphers.iter().enumerate().find_map(|(index, cipher)| {
+                cipher
+                    .decrypt(Nonce::from_slice(&nonce), payload())
+                    .ok()
+                    .map(|chunk| (index, chunk))
+            }) else {
+                return Err(damaged(passphrase_backup));
+            };
+            if ciphers.len() > 1 {
+                let cipher = ciphers.swap_remove(index);
+                *ciphers = vec![cipher];
+            }
+
+            plaintext.extend(chunk);
+            *counter = counter.checked_add(1).ok_or(damaged(passphrase_backup))?;
+            *finished = last;
+            position += FRAME_HEADER_LEN + len;
+        }
+        self.buffer.drain(..position);
+
+        Ok(plaintext)
+    }
+
+    fn open_whole(&mut self) -> Result<Vec<u8>, CustomErrors> {
+        let data = std::mem::take(&mut self.buffer);
+        self.passphrase_backup = data.starts_with(PASSPHRASE_MAGIC);
+
+        // Копию с паролем может открыть только пароль, копию на ключе сервера - только связка
+        let plaintext = if self.passphrase_backup {
+            decrypt_backup_with_passphrase(self.passphrase()?, &data)
+        } else {
+            decrypt_backup(self.keyring, &data)
+        };
+        plaintext.map_err(|_| self.damaged())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD_KEY: &str = "0123456789abcdef0123456789abcdef";
+    const NEW_KEY: &str = "fedcba9876543210fedcba9876543210";
+    const LEGACY_NONCE: &str = "unique nonce";
+
+    fn keyring(current_key_id: &str, keys: &[(&str, &str)]) -> BackupKeyring {
+        BackupKeyring {
+            current_key_id: current_key_id.to_string(),
+            keys: keys
+                .iter()
+                .map(|(id, key)| (id.to_string(), key.to_string()))
+                .collect(),
+            legacy_nonce: Some(LEGACY_NONCE.to_string()),
+        }
+    }
+
+    fn encrypted(keyring: &BackupKeyring, plaintext: &[u8]) -> Vec<u8> {
+        let mut encryptor = BackupEncryptor::with_keyring(keyring, Vec::new()).unwrap();
+        encryptor.write_all(plaintext).unwrap();
+        encryptor.finish().unwrap()
+    }
+
+    fn encrypted_with_passphrase(passphrase: &str, plaintext: &[u8]) -> Vec<u8> {
+        let mut encryptor = BackupEncryptor::with_passphrase(passphrase, Vec::new()).unwrap();
+        encryptor.write_all(plaintext).unwrap();
+        encryptor.finish().unwrap()
+    }
+
+    // Данные приходят кусками произвольной длины, не совпадающими с частями
+    fn opened(decryptor: &mut BackupDecryptor, data: &[u8], piece: usize) -> Option<Vec<u8>> {
+        let mut plaintext = Vec::new();
+        for piece in data.chunks(piece) {
+            plaintext.extend(decryptor.push(piece).ok()?);
+        }
+        plaintext.extend(decryptor.finish().ok()?);
+        Some(plaintext)
+    }
+
+    fn decrypted(
+        keyring: &BackupKeyring,
+        passphrase: Option<&str>,
+        data: &[u8],
+        piece: usize,
+    ) -> Option<Vec<u8>> {
+        opened(&mut BackupDecryptor::new(keyring, passphrase), data, piece)
+    }
+
+    // Прежний конверт ESBE
+    fn legacy_envelope(keyring: &BackupKeyring, plaintext: &[u8]) -> Vec<u8> {
+        let nonce = [7u8; NONCE_LEN];
+        let mut envelope = Vec::from(ENVELOPE_MAGIC.as_slice());
+        envelope.push(keyring.current_key_id.len() as u8);
+        envelope.extend_from_slice(keyring.current_key_id.as_bytes());
+        envelope.extend_from_slice(&nonce);
+        envelope.extend(encrypt_data(keyring.current_key().as_bytes(), &nonce, plaintext).unwrap());
+        envelope
+    }
+
+    #[test]
+    fn backup_round_trips_in_chunks() {
+        let keyring = keyring("old", &[("old", OLD_KEY)]);
+        let plaintext: Vec<u8> = (0..BACKUP_CHUNK_SIZE * 2 + 100)
+            .map(|index| index as u8)
+            .collect();
+
+        let first = encrypted(&keyring, &plaintext);
+        let second = encrypted(&keyring, &plaintext);
+        assert_ne!(first, second);
+
+        for piece in [1, 1000, first.len()] {
+            assert_eq!(decrypted(&keyring, None, &first, piece).unwrap(), plaintext);
+        }
+        assert_eq!(
+            decrypted(&keyring, None, &encrypted(&keyring, b""), 3).unwrap(),
+            b""
+        );
+    }
+
+    #[test]
+    fn rotated_keyring_opens_old_backups() {
+        let encrypted = encrypted(&keyring("old", &[("old", OLD_KEY)]), b"backup");
+
+        let rotated = keyring("new", &[("new", NEW_KEY), ("old", OLD_KEY)]);
+        assert_eq!(decrypted(&rotated, None, &encrypted, 4).unwrap(), b"backup");
+
+        // Ключ ищется и под другим id, если его переименовали
+        let renamed = keyring("new", &[("new", NEW_KEY), ("archive", OLD_KEY)]);
+        assert_eq!(decrypted(&renamed, None, &encrypted, 4).unwrap(), b"backup");
+    }
+
+    #[test]
+    fn rejects_wrong_key_and_damaged_stream() {
+        let owner = keyring("old", &[("old", OLD_KEY)]);
+        let plaintext = vec![1u8; BACKUP_CHUNK_SIZE + 1];
+        let encrypted = encrypted(&owner, &plaintext);
+
+        let stranger = keyring("old", &[("old", NEW_KEY)]);
+        assert!(decrypted(&stranger, None, &encrypted, 100).is_none());
+
+        let mut damaged = encrypted.clone();
+        *damaged.last_mut().unwrap() ^= 1;
+        assert!(decrypted(&owner, None, &damaged, 100).is_none());
+
+        // Без последней части поток считается оборванным
+        let first_frame_end = encrypted.len() - (FRAME_HEADER_LEN + 1 + TAG_LEN);
+        assert!(decrypted(&owner, None, &encrypted[..first_frame_end], 100).is_none());
+        assert!(decrypted(&owner, None, &encrypted[..encrypted.len() - 1], 100).is_none());
+
+        let mut trailing = encrypted.clone();
+        trailing.push(0);
+        assert!(decrypted(&owner, None, &trailing, 100).is_none());
+    }
+
+    #[test]
+    fn opens_legacy_envelopes() {
+        let rotated = keyring("new", &[("new", NEW_KEY), ("old", OLD_KEY)]);
+
+        let envelope = legacy_envelope(&keyring("old", &[("old", OLD_KEY)]), b"backup");
+        assert_eq!(decrypted(&rotated, None, &envelope, 5).unwrap(), b"backup");
+
+        let encrypted =
+            encrypt_data(OLD_KEY.as_bytes(), LEGACY_NONCE.as_bytes(), b"backup").unwrap();
+        assert_eq!(decrypted(&rotated, None, &encrypted, 5).unwrap(), b"backup");
+
+        let without_nonce = BackupKeyring {
+            legacy_nonce: None,
+            ..rotated
+        };
+        assert!(decrypted(&without_nonce, None, &encrypted, 5).is_none());
+    }
+
+    // Каждая проверка выводит ключ через Argon2, поэтому расшифровок немного
+    #[test]
+    fn passphrase_backup_needs_the_same_passphrase() {
+        let keyring = keyring("old", &[("old", OLD_KEY)]);
+        let encrypted = encrypted_with_passphrase("пароль", b"backup");
+
+        let mut decryptor = BackupDecryptor::new(&keyring, Some("пароль"));
+        assert_eq!(opened(&mut decryptor, &encrypted, 64).unwrap(), b"backup");
+        assert!(decryptor.is_passphrase_backup());
+
+        assert!(decrypted(&keyring, Some("другой"), &encrypted, 64).is_none());
+        assert!(decrypted(&keyring, None, &encrypted, 64).is_none());
+    }
+
+    #[test]
+    fn passphrase_backup_keeps_argon2_params() {
+        let keyring = keyring("old", &[("old", OLD_KEY)]);
+        let params = Params::new(64, 1, 2, Some(32)).unwrap();
+        let mut encryptor = BackupEncryptor::with_argon2(
+            Algorithm::Argon2i,
+            Version::V0x10,
+            params,
+            "пароль",
+            Vec::new(),
+        )
+        .unwrap();
+        encryptor.write_all(b"backup").unwrap();
+        let encrypted = encryptor.finish().unwrap();
+
+        assert_eq!(
+            decrypted(&keyring, Some("пароль"), &encrypted, 7).unwrap(),
+            b"backup"
+        );
+
+        // Параметры сверх предела отклоняются до вывода ключа
+        let m_cost = STREAM_MAGIC.len() + 3;
+        let mut greedy = encrypted.clone();
+        greedy[m_cost..m_cost + 4].copy_from_slice(&(MAX_KDF_MEMORY + 1).to_le_bytes());
+        assert!(decrypted(&keyring, Some("пароль"), &greedy, 7).is_none());
+
+        let mut unknown = encrypted;
+        unknown[STREAM_MAGIC.len() + 1] = 3;
+        assert!(decrypted(&keyring, Some("пароль"), &unknown, 7).is_none());
+    }
+
+    #[test]
+    fn opens_legacy_passphrase_backups() {
+        let keyring = keyring("old", &[("old", OLD_KEY)]);
+        let salt = [3u8; SALT_LEN];
+        let nonce = [5u8; NONCE_LEN];
+        let mut encrypted = Vec::from(PASSPHRASE_MAGIC.as_slice());
+        encrypted.extend_from_slice(&salt);
+        encrypted.extend_from_slice(&nonce);
+        encrypted.extend(
+            encrypt_data(
+                &derive_key(&Argon2::default(), "пароль", &salt).unwrap(),
+                &nonce,
+                b"backup",
+            )
+            .unwrap(),
+        );
+
+        let mut decryptor = BackupDecryptor::new(&keyring, Some("пароль"));
+        assert_eq!(opened(&mut decryptor, &encrypted, 64).unwrap(), b"backup");
+        assert!(decryptor.is_passphrase_backup());
+
+        // Копия на ключе сервера паролем не открывается
+        let envelope = legacy_envelope(&keyring, b"backup");
+        let mut decryptor = BackupDecryptor::new(&keyring, Some("пароль"));
+        assert_eq!(opened(&mut decryptor, &envelope, 64).unwrap(), b"backup");
+        assert!(!decryptor.is_passphrase_backup());
+    }
+}
@@ -0,0 +1,205 @@
+use std::collections::HashMap;
+
+use crate::{
+    models::decision_tree::{DecisionEdgeModel, DecisionNodeModel, DecisionTreeModel},
+    utils::inference::{Fact, InferenceEngine, WorkingMemory},
+};
+use entity::{clauses::ClauseModel, questions::QuestionWithAnswersModel};
+
+// Ограничение на размер дерева, чтобы большая система не подвесила сервер
+const MAX_NODES: usize = 5000;
+
+pub struct DecisionTreeCompiler<'a> {
+    engine: &'a InferenceEngine,
+    nodes: Vec<DecisionNodeModel>,
+    edges: Vec<DecisionEdgeModel>,
+}
+
+impl<'a> DecisionTreeCompiler<'a> {
+    pub fn new(engine: &'a InferenceEngine) -> Self {
+        DecisionTreeCompiler {
+            engine,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+
+    // None - дерево получилось больше MAX_NODES вершин
+    pub fn compile(mut self) -> Option<DecisionTreeModel> {
+        let root_node_id = self.build(HashMap::new())?;
+
+        Some(DecisionTreeModel {
+            root_node_id,
+            nodes: self.nodes,
+            edges: self.edges,
+        })
+    }
+
+    // Спрашиваем только вопросы, от которых зависят еще не вычисленные правила
+    fn next_question(&self, memory: &WorkingMemory) -> Option<&'a QuestionWithAnswersModel> {
+        let engine = self.engine;
+        engine.questions.iter().find(|question| {
+            !memory.facts.contains_key(&question.id)
+                && engine.rules.iter().any(|rule| {
+                    InferenceEngine::rule_holds(rule, &memory.facts).is_none()
+                        && rule
+                            .clauses
+                            .iter()
+                            .any(|clause| clause.question_id == question.id)
+                })
+        })
+    }
+
+    // Ветви вопроса: все варианты ответа, а для свободного ввода - по одному значению
+    // на каждый набор выполненных условий
+    fn branches(&self, question: &QuestionWithAnswersModel) -> Vec<(Fact, String)> {
+        let clauses: Vec<&ClauseModel> = self
+            .engine
+            .rules
+            .iter()
+            .flat_map(|rule| rule.clauses.iter())
+            .filter(|clause| clause.question_id == question.id)
+            .collect();
+
+        let mut signatures: Vec<Vec<Option<bool>>> = Vec::new();
+        InferenceEngine::candidate_facts(Some(question), &clauses)
+            .into_iter()
+            .flatten()
+            .filter_map(|fact| {
+                if fact.answer_id.is_some() {
+                    let label = fact.value.clone();
+                    return Some((fact, label));
+                }
+                if question.with_chooses {
+                    return None;
+                }
+
+                let facts = HashMap::from([(question.id, fact.clone())]);
+                let signature: Vec<Option<bool>> = clauses
+                    .iter()
+                    .map(|clause| InferenceEngine::clause_holds(clause, &facts))
+                    .collect();
+                if signatures.contains(&signature) {
+                    return None;
+                }
+                let label = if signature.iter().all(|holds| *holds != Some(true)) {
+                    "другое".to_string()
+                } else {
+                    fact.value.clone()
+                };
+                signatures.push(signature);
+                Some((fact, label))
+            })
+            .collect()
+    }
+
+    fn build(&mut self, facts: HashMap<i32, Fact>) -> Option<usize> {
+        if self.nodes.len() >= MAX_NODES {
+            return None;
+        }
+
+        let memory = self.engine.run(facts.clone());
+        let node_id = self.nodes.len();
+
+        let Some(question) = self.next_question(&memory) else {
+            let result = self.engine.result(memory);
+            let best_percent = result
+                .objects
+                .iter()
+                .map(|object| object.percent)
+                .max()
+                .unwrap_or(0);
+            let objects: Vec<_> = result
+                .objects
+                .iter()
+                .filter(|object| best_percent > 0 && object.percent == best_percent)
+                .collect();
+            let mut attribute_value_ids: Vec<i32> = result
+                .attribute_values
+                .iter()
+                .filter(|value| value.certainty_factor > 0.0)
+                .map(|value| value.attribute_value_id)
+                .collect();
+            attribute_value_ids.sort();
+            attribute_value_ids.dedup();
+
+            self.nodes.push(DecisionNodeModel {
+                id: node_id,
+                question_id: None,
+                label: if objects.is_empty() {
+                    "Объект не определен".to_string()
+                } else {
+                    objects
+                        .iter()
+                        .map(|object| format!("{} ({}%)", object.name, object.percent))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                },
+                object_ids: objects.iter().map(|object| object.object_id).collect(),
+                attribute_value_ids,
+                dead_end: objects.is_empty(),
+            });
+            return Some(node_id);
+        };
+
+        self.nodes.push(DecisionNodeModel {
+            id: node_id,
+            question_id: Some(question.id),
+            label: question.body.clone(),
+            object_ids: Vec::new(),
+            attribute_value_ids: Vec::new(),
+            dead_end: false,
+        });
+
+        for (fact, label) in self.branches(question) {
+            let answer_id = fact.answer_id;
+            let value = fact.value.clone();
+            let mut branch_facts = facts.clone();
+            branch_facts.insert(question.id, fact);
+
+            let to_node_id = self.build(branch_facts)?;
+            self.edges.push(DecisionEdgeModel {
+                from_node_id: node_id,
+                to_node_id,
+                answer_id,
+                value,
+                label,
+            });
+        }
+
+        Some(node_id)
+    }
+}
+
+fn dot_escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn decision_tree_to_dot(tree: &DecisionTreeModel) -> String {
+    let mut dot = String::from("digraph decision_tree {\n");
+
+    for node in &tree.nodes {
+        let style = match (node.question_id, node.dead_end) {
+            (Some(_), _) => "shape=box",
+            (None, false) => "shape=ellipse, style=filled, fillcolor=palegreen",
+            (None, true) => "shape=ellipse, style=filled, fillcolor=lightpink",
+        };
+        dot.push_str(&format!(
+            "    n{} [{}, label=\"{}\"];\n",
+            node.id,
+            style,
+            dot_escape(&node.label)
+        ));
+    }
+    for edge in &tree.edges {
+        dot.push_str(&format!(
+            "    n{} -> n{} [label=\"{}\"];\n",
+            edge.from_node_id,
+            edge.to_node_id,
+            dot_escape(&edge.label)
+        ));
+    }
+
+    dot.push_str("}\n");
+    dot
+}
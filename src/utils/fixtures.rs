@@ -0,0 +1,155 @@
+// Построители моделей для модульных тестов: все, что не задано явно, заполняется
+// значениями по умолчанию, а нужные поля меняются через ..fixture
+use entity::{
+    answers::AnswerModel,
+    attributes::AttributeWithAttributeValuesModel,
+    attributesvalues::AttributeValueModel,
+    backup_legacy::BackupClauseModelV1,
+    clauses::ClauseModel,
+    object_attribute_attributevalue::ObjectAttributeAttributeValueModel,
+    objects::ObjectWithAttributesValuesModel,
+    questions::QuestionWithAnswersModel,
+    rule_attribute_attributevalue::RuleAttributeAttributeValueModel,
+    rule_question_answer::RuleQuestionAnswerModel,
+    rules::RuleWithClausesAndEffects,
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+};
+
+pub fn clause(
+    id: i32,
+    question_id: i32,
+    operator: Operatorenum,
+    value_type: Valuetypeenum,
+    compared_value: &str,
+) -> ClauseModel {
+    ClauseModel {
+        id,
+        rule_id: 0,
+        compared_value: compared_value.to_string(),
+        logical_group: "1".to_string(),
+        operator,
+        question_id,
+        value_type,
+    }
+}
+
+// Условие из резервных копий до появления типа значения
+pub fn legacy_clause(
+    id: i32,
+    question_id: i32,
+    operator: Operatorenum,
+    compared_value: &str,
+) -> BackupClauseModelV1 {
+    BackupClauseModelV1 {
+        id,
+        rule_id: 1,
+        compared_value: compared_value.to_string(),
+        logical_group: "1".to_string(),
+        operator,
+        question_id,
+    }
+}
+
+// Правило без выводов; условия привязываются к нему по rule_id
+pub fn rule(id: i32, clauses: Vec<ClauseModel>) -> RuleWithClausesAndEffects {
+    RuleWithClausesAndEffects {
+        id,
+        system_id: 1,
+        attribute_rule: false,
+        certainty_factor: 1.0,
+        condition: None,
+        clauses: clauses
+            .into_iter()
+            .map(|clause| ClauseModel {
+                rule_id: id,
+                ..clause
+            })
+            .collect(),
+        rule_question_answer_ids: vec![],
+        rule_attribute_attributevalue_ids: vec![],
+    }
+}
+
+pub fn concludes_value(
+    mut rule: RuleWithClausesAndEffects,
+    attribute_id: i32,
+    attribute_value_id: i32,
+) -> RuleWithClausesAndEffects {
+    rule.attribute_rule = true;
+    rule.rule_attribute_attributevalue_ids
+        .push(RuleAttributeAttributeValueModel {
+            id: 0,
+            attribute_value_id,
+            rule_id: rule.id,
+            attribute_id,
+        });
+    rule
+}
+
+pub fn concludes_answer(
+    mut rule: RuleWithClausesAndEffects,
+    question_id: i32,
+    answer_id: i32,
+) -> RuleWithClausesAndEffects {
+    rule.rule_question_answer_ids.push(RuleQuestionAnswerModel {
+        id: 0,
+        answer_id,
+        rule_id: rule.id,
+        question_id,
+    });
+    rule
+}
+
+// Вопрос без ответов предполагает ввод значения
+pub fn question(id: i32, body: &str, answers: &[(i32, &str)]) -> QuestionWithAnswersModel {
+    QuestionWithAnswersModel {
+        id,
+        system_id: 1,
+        body: body.to_string(),
+        with_chooses: !answers.is_empty(),
+        answers: answers
+            .iter()
+            .map(|(answer_id, answer)| AnswerModel {
+                id: *answer_id,
+                question_id: id,
+                body: answer.to_string(),
+            })
+            .collect(),
+    }
+}
+
+// Значения объекта задаются парами (атрибут, значение атрибута)
+pub fn object(id: i32, values: &[(i32, i32)]) -> ObjectWithAttributesValuesModel {
+    ObjectWithAttributesValuesModel {
+        id,
+        system_id: 1,
+        name: format!("Объект {}", id),
+        object_attribute_attributevalue_ids: values
+            .iter()
+            .map(
+                |(attribute_id, attribute_value_id)| ObjectAttributeAttributeValueModel {
+                    id: 0,
+                    object_id: id,
+                    attribute_value_id: *attribute_value_id,
+                    attribute_id: *attribute_id,
+                },
+            )
+            .collect(),
+    }
+}
+
+pub fn attribute(id: i32, name: &str, values: &[(i32, &str)]) -> AttributeWithAttributeValuesModel {
+    AttributeWithAttributeValuesModel {
+        id,
+        system_id: 1,
+        name: name.to_string(),
+        values: values
+            .iter()
+            .map(|(value_id, value)| AttributeValueModel {
+                id: *value_id,
+                attribute_id: id,
+                value: value.to_string(),
+            })
+            .collect(),
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::BTreeMap;
+
+use crate::models::induction::{InducedAttributeModel, InductionModel};
+use entity::{
+    attributes::AttributeWithAttributeValuesModel,
+    clauses::NewClauseWithoutRule,
+    objects::ObjectWithAttributesValuesModel,
+    questions::QuestionWithAnswersModel,
+    rule_attribute_attributevalue::NewRuleAttributeAttributeValueWithoutRuleModel,
+    rules::{NewRuleWithClausesAndEffects, RuleWithClausesAndEffects},
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+};
+
+pub struct RuleInducer<'a> {
+    system_id: i32,
+    rules: &'a [RuleWithClausesAndEffects],
+    questions: &'a [QuestionWithAnswersModel],
+    objects: &'a [ObjectWithAttributesValuesModel],
+    attributes: &'a [AttributeWithAttributeValuesModel],
+}
+
+impl<'a> RuleInducer<'a> {
+    pub fn new(
+        system_id: i32,
+        rules: &'a [RuleWithClausesAndEffects],
+        questions: &'a [QuestionWithAnswersModel],
+        objects: &'a [ObjectWithAttributesValuesModel],
+        attributes: &'a [AttributeWithAttributeValuesModel],
+    ) -> Self {
+        RuleInducer {
+            system_id,
+            rules,
+            questions,
+            objects,
+            attributes,
+        }
+    }
+
+    // Значение атрибута у объекта - набор id значений, пустой если не задано
+    fn object_value(object: &ObjectWithAttributesValuesModel, attribute_id: i32) -> Vec<i32> {
+        let mut values: Vec<i32> = object
+            .object_attribute_attributevalue_ids
+            .iter()
+            .filter(|value| value.attribute_id == attribute_id)
+            .map(|value| value.attribute_value_id)
+            .collect();
+        values.sort();
+        values.dedup();
+        values
+    }
+
+    fn split<'o>(
+        objects: &[&'o ObjectWithAttributesValuesModel],
+        attribute_id: i32,
+    ) -> Vec<Vec<&'o ObjectWithAttributesValuesModel>> {
+        let mut groups: BTreeMap<Vec<i32>, Vec<&ObjectWithAttributesValuesModel>> = BTreeMap::new();
+        objects.iter().for_each(|object| {
+            groups
+                .entry(Self::object_value(object, attribute_id))
+                .or_default()
+                .push(object)
+        });
+        groups.into_values().collect()
+    }
+
+    // Каждый объект - отдельный класс, поэтому энтропия группы из n объектов равна log2(n)
+    fn information_gain(objects: &[&ObjectWithAttributesValuesModel], attribute_id: i32) -> f64 {
+        let total = objects.len() as f64;
+        let remainder: f64 = Self::split(objects, attribute_id)
+            .iter()
+            .map(|group| group.len() as f64 / total * (group.len() as f64).log2())
+            .sum();
+        total.log2() - remainder
+    }
+
+    // ID3: делим объекты по атрибуту с наибольшим приростом информации, пока группы
+    // не станут одноэлементными или неразличимыми
+    fn build(
+        &self,
+        objects: Vec<&ObjectWithAttributesValuesModel>,
+        used: &mut Vec<InducedAttributeModel>,
+        indistinguishable: &mut Vec<Vec<i32>>,
+    ) {
+        if objects.len() < 2 {
+            return;
+        }
+
+        let best = self
+            .attributes
+            .iter()
+            .map(|attribute| (attribute.id, Self::information_gain(&objects, attribute.id)))
+            .filter(|(_, gain)| *gain > f64::EPSILON)
+            .max_by(|first, second| first.1.total_cmp(&second.1).then(second.0.cmp(&first.0)));
+
+        let Some((attribute_id, information_gain)) = best else {
+            let mut object_ids: Vec<i32> = objects.iter().map(|object| object.id).collect();
+            object_ids.sort();
+            indistinguishable.push(object_ids);
+            return;
+        };
+
+        if !used
+            .iter()
+            .any(|attribute| attribute.attribute_id == attribute_id)
+        {
+            used.push(InducedAttributeModel {
+                attribute_id,
+                information_gain,
+                question_id: self
+                    .matching_question(attribute_id)
+                    .map(|question| question.id),
+            });
+        }
+        for group in Self::split(&objects, attribute_id) {
+            self.build(group, used, indistinguishable);
+        }
+    }
+
+    // Вопрос подходит атрибуту, если среди его ответов есть все значения атрибута,
+    // встречающиеся у объектов; при нескольких кандидатах предпочитаем вопрос с именем атрибута
+    fn matching_question(&self, attribute_id: i32) -> Option<&'a QuestionWithAnswersModel> {
+        let attribute = self
+            .attributes
+            .iter()
+            .find(|attribute| attribute.id == attribute_id)?;
+        let values = self.used_values(attribute);
+        if values.is_empty() {
+            return None;
+        }
+
+        let candidates: Vec<&QuestionWithAnswersModel> = self
+            .questions
+            .iter()
+            .filter(|question| {
+                question.with_chooses
+                    && values
+                        .iter()
+                        .all(|(_, value)| Self::answer_for(question, value).is_some())
+            })
+            .collect();
+        let name = attribute.name.trim().to_lowercase();
+
+        candidates
+            .iter()
+            .find(|question| question.body.to_lowercase().contains(&name))
+            .or(candidates.first())
+            .copied()
+    }
+
+    fn used_values(&self, attribute: &AttributeWithAttributeValuesModel) -> Vec<(i32, String)> {
+        attribute
+            .values
+            .iter()
+            .filter(|value| {
+                self.objects.iter().any(|object| {
+                    object
+                        .object_attribute_attributevalue_ids
+                        .iter()
+                        .any(|object_value| object_value.attribute_value_id == value.id)
+                })
+            })
+            .map(|value| (value.id, value.value.clone()))
+            .collect()
+    }
+
+    fn answer_for(question: &QuestionWithAnswersModel, value: &str) -> Option<i32> {
+        question
+            .answers
+            .iter()
+            .find(|answer| answer.body.trim().to_lowercase() == value.trim().to_lowercase())
+            .map(|answer| answer.id)
+    }
+
+    // Такое правило уже есть - повторно не предлагаем
+    fn rule_exists(&self, question_id: i32, answer_id: i32, attribute_value_id: i32) -> bool {
+        self.rules.iter().any(|rule| {
+            rule.rule_attribute_attributevalue_ids
+                .iter()
+                .any(|effect| effect.attribute_value_id == attribute_value_id)
+                && rule.clauses.iter().any(|clause| {
+                    clause.question_id == question_id
+                        && clause.value_type == Valuetypeenum::Answer
+                        && clause.operator == Operatorenum::Equal
+                        && clause.compared_value.trim() == answer_id.to_string()
+                })
+        })
+    }
+
+    fn draft_rules(&self, attribute: &InducedAttributeModel) -> Vec<NewRuleWithClausesAndEffects> {
+        let Some(question) = attribute.question_id.and_then(|question_id| {
+            self.questions
+                .iter()
+                .find(|question| question.id == question_id)
+        }) else {
+            return Vec::new();
+        };
+        let Some(full_attribute) = self
+            .attributes
+            .iter()
+            .find(|full_attribute| full_attribute.id == attribute.attribute_id)
+        else {
+            return Vec::new();
+        };
+
+        self.used_values(full_attribute)
+            .into_iter()
+            .filter_map(|(attribute_value_id, value)| {
+                let answer_id = Self::answer_for(question, &value)?;
+                if self.rule_exists(question.id, answer_id, attribute_value_id) {
+                    return None;
+                }
+                Some(NewRuleWithClausesAndEffects {
+                    system_id: self.system_id,
+                    attribute_rule: true,
+                    certainty_factor: 1.0,
+                    condition: None,
+                    clauses: vec![NewClauseWithoutRule {
+                        compared_value: answer_id.to_string(),
+                        logical_group: "1".to_string(),
+                        operator: Operatorenum::Equal,
+                        question_id: question.id,
+                        value_type: Valuetypeenum::Answer,
+                    }],
+                    rule_question_answer_ids: Vec::new(),
+                    rule_attribute_attributevalue_ids: vec![
+                        NewRuleAttributeAttributeValueWithoutRuleModel {
+                            attribute_value_id,
+                            attribute_id: attribute.attribute_id,
+                        },
+                    ],
+                })
+            })
+            .collect()
+    }
+
+    pub fn induce(&self) -> InductionModel {
+        let mut attributes = Vec::new();
+        let mut indistinguishable_object_ids = Vec::new();
+        self.build(
+            self.objects.iter().collect(),
+            &mut attributes,
+            &mut indistinguishable_object_ids,
+        );
+
+        let rules = attributes
+            .iter()
+            .flat_map(|attribute| self.draft_rules(attribute))
+            .collect();
+        let unmatched_attribute_ids = attributes
+            .iter()
+            .filter(|attribute| attribute.question_id.is_none())
+            .map(|attribute| attribute.attribute_id)
+            .collect();
+
+        InductionModel {
+            attributes,
+            rules,
+            unmatched_attribute_ids,
+            indistinguishable_object_ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::{attribute, clause, concludes_value, object, question, rule};
+
+    const COLOR: i32 = 1;
+    const SIZE: i32 = 2;
+    const TASTE: i32 = 3;
+
+    fn attributes() -> Vec<AttributeWithAttributeValuesModel> {
+        vec![
+            attribute(COLOR, "Цвет", &[(11, "красный"), (12, "зеленый")]),
+            attribute(SIZE, "Размер", &[(21, "большой"), (22, "малый")]),
+            attribute(TASTE, "Вкус", &[(31, "сладкий")]),
+        ]
+    }
+
+    // Объекты 4 и 5 совпадают по всем атрибутам, вкус у всех одинаковый
+    fn objects() -> Vec<ObjectWithAttributesValuesModel> {
+        [
+            (1, 11, 21),
+            (2, 11, 22),
+            (3, 12, 21),
+            (4, 12, 22),
+            (5, 12, 22),
+        ]
+        .into_iter()
+        .map(|(id, color, size)| object(id, &[(COLOR, color), (SIZE, size), (TASTE, 31)]))
+        .collect()
+    }
+
+    fn questions() -> Vec<QuestionWithAnswersModel> {
+        vec![
+            question(100, "Какой оттенок?", &[(121, "красный"), (122, "зеленый")]),
+            question(101, "Какой цвет?", &[(111, "Красный"), (112, "Зеленый")]),
+        ]
+    }
+
+    // Правило "цвет красный" по вопросу 101 уже есть
+    fn rules() -> Vec<RuleWithClausesAndEffects> {
+        vec![concludes_value(
+            rule(
+                1,
+                vec![clause(
+                    1,
+                    101,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "111",
+                )],
+            ),
+            COLOR,
+            11,
+        )]
+    }
+
+    fn induce() -> InductionModel {
+        let (rules, questions, objects, attributes) =
+            (rules(), questions(), objects(), attributes());
+        RuleInducer::new(1, &rules, &questions, &objects, &attributes).induce()
+    }
+
+    #[test]
+    fn splits_by_information_gain() {
+        let induction = induce();
+
+        // При равном приросте берется атрибут с меньшим id
+        let attribute_ids: Vec<i32> = induction
+            .attributes
+            .iter()
+            .map(|attribute| attribute.attribute_id)
+            .collect();
+        assert_eq!(attribute_ids, vec![COLOR, SIZE]);
+
+        let expected = 5f64.log2() - (0.4 + 0.6 * 3f64.log2());
+        assert!((induction.attributes[0].information_gain - expected).abs() < 1e-9);
+        assert!((induction.attributes[1].information_gain - 1.0).abs() < 1e-9);
+
+        assert_eq!(induction.indistinguishable_object_ids, vec![vec![4, 5]]);
+    }
+
+    #[test]
+    fn drafts_only_missing_rules_for_matched_questions() {
+        let induction = induce();
+
+        // Оба вопроса подходят по ответам, предпочитаем содержащий имя атрибута
+        assert_eq!(induction.attributes[0].question_id, Some(101));
+        assert_eq!(induction.unmatched_attribute_ids, vec![SIZE]);
+
+        assert_eq!(induction.rules.len(), 1);
+        let rule = &induction.rules[0];
+        assert_eq!(rule.clauses[0].question_id, 101);
+        assert_eq!(rule.clauses[0].compared_value, "112");
+        assert_eq!(
+            rule.rule_attribute_attributevalue_ids[0].attribute_value_id,
+            12
+        );
+    }
+
+    #[test]
+    fn single_object_needs_no_questions() {
+        let (questions, attributes) = (questions(), attributes());
+        let objects = vec![object(1, &[(COLOR, 11)])];
+
+        let induction = RuleInducer::new(1, &[], &questions, &objects, &attributes).induce();
+
+        assert!(induction.attributes.is_empty());
+        assert!(induction.rules.is_empty());
+        assert!(induction.indistinguishable_object_ids.is_empty());
+    }
+}
@@ -0,0 +1,1047 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::models::inference::{
+    DerivedAnswerModel, DerivedAttributeValueModel, ExplanationModel, GivenAnswerModel, GoalStatus,
+    InferenceResultModel, ObjectScoreModel, ObjectTraceModel, ObjectValueTraceModel,
+    RuleTraceModel, SatisfiedClauseModel,
+};
+use entity::{
+    clauses::ClauseModel,
+    objects::ObjectWithAttributesValuesModel,
+    questions::QuestionWithAnswersModel,
+    rules::{ConditionNode, RuleWithClausesAndEffects},
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+    systems::TestSystemModel,
+};
+
+#[derive(Clone, Debug)]
+pub struct Fact {
+    pub answer_id: Option<i32>,
+    pub value: String,
+    pub certainty_factor: f64,
+}
+
+// Комбинирование коэффициентов уверенности по MYCIN
+pub fn combine_certainty_factors(first: f64, second: f64) -> f64 {
+    if first >= 0.0 && second >= 0.0 {
+        first + second * (1.0 - first)
+    } else if first < 0.0 && second < 0.0 {
+        first + second * (1.0 + first)
+    } else {
+        let denominator = 1.0 - first.abs().min(second.abs());
+        if denominator == 0.0 {
+            0.0
+        } else {
+            (first + second) / denominator
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WorkingMemory {
+    pub facts: HashMap<i32, Fact>,
+    pub fired_rules: Vec<i32>,
+    pub derived_answers: Vec<DerivedAnswerModel>,
+    pub attribute_values: Vec<DerivedAttributeValueModel>,
+}
+
+pub struct InferenceEngine {
+    pub rules: Vec<RuleWithClausesAndEffects>,
+    pub questions: Vec<QuestionWithAnswersModel>,
+    pub objects: Vec<ObjectWithAttributesValuesModel>,
+}
+
+impl From<TestSystemModel> for InferenceEngine {
+    fn from(system: TestSystemModel) -> Self {
+        InferenceEngine::new(system.rules, system.questions, system.objects)
+    }
+}
+
+impl InferenceEngine {
+    pub fn new(
+        rules: Vec<RuleWithClausesAndEffects>,
+        questions: Vec<QuestionWithAnswersModel>,
+        objects: Vec<ObjectWithAttributesValuesModel>,
+    ) -> Self {
+        InferenceEngine {
+            rules,
+            questions,
+            objects,
+        }
+    }
+
+    fn answer_body(&self, question_id: i32, answer_id: i32) -> Option<&str> {
+        self.questions
+            .iter()
+            .find(|question| question.id == question_id)
+            .and_then(|question| {
+                question
+                    .answers
+                    .iter()
+                    .find(|answer| answer.id == answer_id)
+            })
+            .map(|answer| answer.body.as_str())
+    }
+
+    pub fn given_facts(&self, given: &[GivenAnswerModel]) -> HashMap<i32, Fact> {
+        given
+            .iter()
+            .filter_map(|answer| {
+                let value = match (answer.answer_id, &answer.value) {
+                    (Some(answer_id), _) => self
+                        .answer_body(answer.question_id, answer_id)
+                        .map(str::to_owned)
+                        .or(answer.value.clone())?,
+                    (None, Some(value)) => value.clone(),
+                    (None, None) => return None,
+                };
+                Some((
+                    answer.question_id,
+                    Fact {
+                        answer_id: answer.answer_id,
+                        value,
+                        certainty_factor: answer.certainty_factor.unwrap_or(1.0).clamp(0.0, 1.0),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    // None - на вопрос еще нет ответа, условие пока не вычислимо
+    pub fn clause_holds(clause: &ClauseModel, facts: &HashMap<i32, Fact>) -> Option<bool> {
+        let fact = facts.get(&clause.question_id)?;
+        let compared_value = clause.compared_value.trim();
+
+        let numbers = || {
+            let parse = |raw: &str| raw.trim().replace(',', ".").parse::<f64>().ok();
+            parse(&fact.value).zip(parse(compared_value))
+        };
+        let boolean = |raw: &str| match raw.trim().to_lowercase().as_str() {
+            "true" | "yes" | "1" | "да" => Some(true),
+            "false" | "no" | "0" | "нет" => Some(false),
+            _ => None,
+        };
+
+        let equal = match clause.value_type {
+            Valuetypeenum::Integer | Valuetypeenum::Decimal => {
+                numbers().is_some_and(|(value, border)| value == border)
+            }
+            Valuetypeenum::Boolean => boolean(&fact.value)
+                .zip(boolean(compared_value))
+                .is_some_and(|(value, border)| value == border),
+            Valuetypeenum::Answer => fact
+                .answer_id
+                .is_some_and(|answer_id| answer_id.to_string() == compared_value),
+            Valuetypeenum::Text => fact.value.trim() == compared_value,
+        };
+
+        Some(match clause.operator {
+            Operatorenum::Equal => equal,
+            Operatorenum::NotEqual => !equal,
+            Operatorenum::Above => numbers().is_some_and(|(value, border)| value > border),
+            Operatorenum::Below => numbers().is_some_and(|(value, border)| value < border),
+            Operatorenum::NoLessThan => numbers().is_some_and(|(value, border)| value >= border),
+            Operatorenum::NoMoreThan => numbers().is_some_and(|(value, border)| value <= border),
+        })
+    }
+
+    pub fn rule_condition(rule: &RuleWithClausesAndEffects) -> Option<ConditionNode> {
+        let clause_ids: Vec<i32> = rule.clauses.iter().map(|clause| clause.id).collect();
+
+        rule.condition
+            .clone()
+            .unwrap_or_else(|| ConditionNode::from_groups(&rule.clauses))
+            .prune(&clause_ids)
+    }
+
+    fn rule_clause(rule: &RuleWithClausesAndEffects, clause_id: i32) -> Option<&ClauseModel> {
+        rule.clauses.iter().find(|clause| clause.id == clause_id)
+    }
+
+    pub fn node_holds(
+        rule: &RuleWithClausesAndEffects,
+        node: &ConditionNode,
+        facts: &HashMap<i32, Fact>,
+    ) -> Option<bool> {
+        match node {
+            ConditionNode::And(nodes) => {
+                let mut result = Some(true);
+                for node in nodes {
+                    match Self::node_holds(rule, node, facts) {
+                        Some(true) => (),
+                        Some(false) => return Some(false),
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            ConditionNode::Or(nodes) => {
+                let mut result = Some(false);
+                for node in nodes {
+                    match Self::node_holds(rule, node, facts) {
+                        Some(true) => return Some(true),
+                        Some(false) => (),
+                        None => result = None,
+                    }
+                }
+                result
+            }
+            ConditionNode::Not(node) => Self::node_holds(rule, node, facts).map(|holds| !holds),
+            ConditionNode::Clause(clause_id) => Self::rule_clause(rule, *clause_id)
+                .and_then(|clause| Self::clause_holds(clause, facts)),
+        }
+    }
+
+    // Правило без условий никогда не срабатывает
+    pub fn rule_holds(
+        rule: &RuleWithClausesAndEffects,
+        facts: &HashMap<i32, Fact>,
+    ) -> Option<bool> {
+        Self::rule_condition(rule).map_or(Some(false), |condition| {
+            Self::node_holds(rule, &condition, facts)
+        })
+    }
+
+    // Уверенность в вычисленном значении узла: для И - минимум, для ИЛИ - максимум
+    // по определившим его потомкам, НЕ сохраняет уверенность потомка
+    fn node_certainty_factor(
+        rule: &RuleWithClausesAndEffects,
+        node: &ConditionNode,
+        facts: &HashMap<i32, Fact>,
+    ) -> f64 {
+        let children = |nodes: &[ConditionNode], value: bool| -> Vec<f64> {
+            nodes
+                .iter()
+                .filter(|node| Self::node_holds(rule, node, facts) == Some(value))
+                .map(|node| Self::node_certainty_factor(rule, node, facts))
+                .collect()
+        };
+
+        match (node, Self::node_holds(rule, node, facts)) {
+            (ConditionNode::And(nodes), Some(true)) => {
+                children(nodes, true).into_iter().fold(1.0, f64::min)
+            }
+            (ConditionNode::And(nodes), Some(false)) => {
+                children(nodes, false).into_iter().fold(0.0, f64::max)
+            }
+            (ConditionNode::Or(nodes), Some(true)) => {
+                children(nodes, true).into_iter().fold(0.0, f64::max)
+            }
+            (ConditionNode::Or(nodes), Some(false)) => {
+                children(nodes, false).into_iter().fold(1.0, f64::min)
+            }
+            (ConditionNode::Not(node), Some(_)) => Self::node_certainty_factor(rule, node, facts),
+            (ConditionNode::Clause(clause_id), Some(_)) => Self::rule_clause(rule, *clause_id)
+                .and_then(|clause| facts.get(&clause.question_id))
+                .map_or(0.0, |fact| fact.certainty_factor),
+            (_, None) => 0.0,
+        }
+    }
+
+    pub fn premise_certainty_factor(
+        rule: &RuleWithClausesAndEffects,
+        facts: &HashMap<i32, Fact>,
+    ) -> f64 {
+        match Self::rule_condition(rule) {
+            Some(condition) if Self::node_holds(rule, &condition, facts) == Some(true) => {
+                Self::node_certainty_factor(rule, &condition, facts)
+            }
+            _ => 0.0,
+        }
+    }
+
+    // Условия, определившие значение узла; второй элемент - условие стоит под НЕ
+    fn supporting_clauses<'a>(
+        rule: &'a RuleWithClausesAndEffects,
+        node: &ConditionNode,
+        facts: &HashMap<i32, Fact>,
+        negated: bool,
+    ) -> Vec<(&'a ClauseModel, bool)> {
+        let find = |nodes: &[ConditionNode], expected: bool| {
+            nodes
+                .iter()
+                .find(|node| Self::node_holds(rule, node, facts) == Some(expected))
+                .map(|node| Self::supporting_clauses(rule, node, facts, negated))
+                .unwrap_or_default()
+        };
+        let all = |nodes: &[ConditionNode]| {
+            nodes
+                .iter()
+                .flat_map(|node| Self::supporting_clauses(rule, node, facts, negated))
+                .collect()
+        };
+
+        match (node, Self::node_holds(rule, node, facts)) {
+            (_, None) => Vec::new(),
+            (ConditionNode::And(nodes), Some(true)) => all(nodes),
+            (ConditionNode::And(nodes), Some(false)) => find(nodes, false),
+            (ConditionNode::Or(nodes), Some(true)) => find(nodes, true),
+            (ConditionNode::Or(nodes), Some(false)) => all(nodes),
+            (ConditionNode::Not(node), Some(_)) => {
+                Self::supporting_clauses(rule, node, facts, !negated)
+            }
+            (ConditionNode::Clause(clause_id), Some(_)) => Self::rule_clause(rule, *clause_id)
+                .map(|clause| vec![(clause, negated)])
+                .unwrap_or_default(),
+        }
+    }
+
+    // Условия без ответа, от которых зависит значение еще не вычислимого узла
+    fn unknown_clauses<'a>(
+        rule: &'a RuleWithClausesAndEffects,
+        node: &ConditionNode,
+        facts: &HashMap<i32, Fact>,
+    ) -> Vec<&'a ClauseModel> {
+        if Self::node_holds(rule, node, facts).is_some() {
+            return Vec::new();
+        }
+
+        match node {
+            ConditionNode::And(nodes) | ConditionNode::Or(nodes) => nodes
+                .iter()
+                .flat_map(|node| Self::unknown_clauses(rule, node, facts))
+                .collect(),
+            ConditionNode::Not(node) => Self::unknown_clauses(rule, node, facts),
+            ConditionNode::Clause(clause_id) => {
+                Self::rule_clause(rule, *clause_id).into_iter().collect()
+            }
+        }
+    }
+
+    // Значения, на которых меняется результат условий по вопросу; None - нет ответа
+    pub fn candidate_facts(
+        question: Option<&QuestionWithAnswersModel>,
+        clauses: &[&ClauseModel],
+    ) -> Vec<Option<Fact>> {
+        let fact = |answer_id: Option<i32>, value: String| Fact {
+            answer_id,
+            value,
+            certainty_factor: 1.0,
+        };
+        let mut candidates = vec![None];
+        // На вопрос другой системы ответить невозможно
+        let Some(question) = question else {
+            return candidates;
+        };
+        candidates.extend(
+            question
+                .answers
+                .iter()
+                .map(|answer| Some(fact(Some(answer.id), answer.body.clone()))),
+        );
+
+        let mut numbers: Vec<f64> = Vec::new();
+        let mut values: Vec<String> = vec![String::new()];
+        for clause in clauses {
+            let border = clause.compared_value.trim();
+            match clause.value_type {
+                Valuetypeenum::Integer | Valuetypeenum::Decimal => {
+                    if let Ok(number) = border.replace(',', ".").parse::<f64>() {
+                        numbers.extend([number - 1.0, number, number + 1.0]);
+                    }
+                }
+                Valuetypeenum::Boolean => {
+                    values.extend(["true".to_string(), "false".to_string()]);
+                }
+                Valuetypeenum::Answer | Valuetypeenum::Text => values.push(border.to_string()),
+            }
+        }
+        numbers.sort_by(f64::total_cmp);
+        numbers.dedup();
+        let middles: Vec<f64> = numbers
+            .windows(2)
+            .map(|pair| (pair[0] + pair[1]) / 2.0)
+            .collect();
+        values.extend(
+            numbers
+                .into_iter()
+                .chain(middles)
+                .map(|number| number.to_string()),
+        );
+        values.sort();
+        values.dedup();
+
+        candidates.extend(values.into_iter().map(|value| Some(fact(None, value))));
+        candidates
+    }
+
+    fn fire(&self, rule: &RuleWithClausesAndEffects, memory: &mut WorkingMemory) {
+        memory.fired_rules.push(rule.id);
+        let certainty_factor =
+            rule.certainty_factor * Self::premise_certainty_factor(rule, &memory.facts);
+
+        rule.rule_question_answer_ids.iter().for_each(|effect| {
+            if memory.facts.contains_key(&effect.question_id) {
+                return;
+            }
+            let value = self
+                .answer_body(effect.question_id, effect.answer_id)
+                .unwrap_or_default()
+                .to_owned();
+            memory.facts.insert(
+                effect.question_id,
+                Fact {
+                    answer_id: Some(effect.answer_id),
+                    value,
+                    certainty_factor,
+                },
+            );
+            memory.derived_answers.push(DerivedAnswerModel {
+                question_id: effect.question_id,
+                answer_id: effect.answer_id,
+                rule_id: rule.id,
+                certainty_factor,
+            });
+        });
+
+        rule.rule_attribute_attributevalue_ids
+            .iter()
+            .for_each(|effect| {
+                match memory.attribute_values.iter_mut().find(|derived| {
+                    derived.attribute_id == effect.attribute_id
+                        && derived.attribute_value_id == effect.attribute_value_id
+                }) {
+                    Some(derived) => {
+                        derived.certainty_factor =
+                            combine_certainty_factors(derived.certainty_factor, certainty_factor)
+                    }
+                    None => memory.attribute_values.push(DerivedAttributeValueModel {
+                        attribute_id: effect.attribute_id,
+                        attribute_value_id: effect.attribute_value_id,
+                        rule_id: rule.id,
+                        certainty_factor,
+                    }),
+                }
+            });
+    }
+
+    pub fn run(&self, facts: HashMap<i32, Fact>) -> WorkingMemory {
+        let mut memory = WorkingMemory {
+            facts,
+            ..Default::default()
+        };
+        let mut fired: HashSet<i32> = HashSet::new();
+
+        loop {
+            let ready = self.rules.iter().find(|rule| {
+                !fired.contains(&rule.id) && Self::rule_holds(rule, &memory.facts) == Some(true)
+            });
+            match ready {
+                Some(rule) => {
+                    fired.insert(rule.id);
+                    self.fire(rule, &mut memory);
+                }
+                None => break,
+            }
+        }
+
+        memory
+    }
+
+    pub fn rank_objects(&self, memory: &WorkingMemory) -> Vec<ObjectScoreModel> {
+        let derived: HashMap<(i32, i32), f64> = memory
+            .attribute_values
+            .iter()
+            .filter(|value| value.certainty_factor > 0.0)
+            .map(|value| {
+                (
+                    (value.attribute_id, value.attribute_value_id),
+                    value.certainty_factor,
+                )
+            })
+            .collect();
+
+        let mut result: Vec<ObjectScoreModel> = self
+            .objects
+            .iter()
+            .map(|object| {
+                let total = object.object_attribute_attributevalue_ids.len();
+                let certainty_factors: Vec<f64> = object
+                    .object_attribute_attributevalue_ids
+                    .iter()
+                    .filter_map(|value| {
+                        derived
+                            .get(&(value.attribute_id, value.attribute_value_id))
+                            .copied()
+                    })
+                    .collect();
+                let percent = if total == 0 {
+                    0
+                } else {
+                    (certainty_factors.iter().sum::<f64>() * 100.0 / total as f64).round() as u8
+                };
+                ObjectScoreModel {
+                    object_id: object.id,
+                    name: object.name.clone(),
+                    matched: certainty_factors.len(),
+                    total,
+                    percent,
+                }
+            })
+            .collect();
+        result.sort_by(|a, b| {
+            b.percent
+                .cmp(&a.percent)
+                .then(a.object_id.cmp(&b.object_id))
+        });
+
+        result
+    }
+
+    pub fn next_question(&self, memory: &WorkingMemory) -> Option<&QuestionWithAnswersModel> {
+        self.questions
+            .iter()
+            .find(|question| !memory.facts.contains_key(&question.id))
+    }
+
+    pub fn goal_values(
+        &self,
+        goal_object_id: Option<i32>,
+        goal_attribute_value_id: Option<i32>,
+    ) -> Vec<i32> {
+        match (goal_object_id, goal_attribute_value_id) {
+            (Some(object_id), _) => self
+                .objects
+                .iter()
+                .find(|object| object.id == object_id)
+                .map(|object| {
+                    object
+                        .object_attribute_attributevalue_ids
+                        .iter()
+                        .map(|value| value.attribute_value_id)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            (None, Some(attribute_value_id)) => vec![attribute_value_id],
+            (None, None) => Vec::new(),
+        }
+    }
+
+    fn is_derived(memory: &WorkingMemory, attribute_value_id: i32) -> bool {
+        memory.attribute_values.iter().any(|derived| {
+            derived.attribute_value_id == attribute_value_id && derived.certainty_factor > 0.0
+        })
+    }
+
+    fn concluding_rules(
+        &self,
+        attribute_value_id: i32,
+    ) -> impl Iterator<Item = &RuleWithClausesAndEffects> {
+        self.rules.iter().filter(move |rule| {
+            rule.rule_attribute_attributevalue_ids
+                .iter()
+                .any(|effect| effect.attribute_value_id == attribute_value_id)
+        })
+    }
+
+    pub fn goal_status(&self, goal: &[i32], memory: &WorkingMemory) -> GoalStatus {
+        if goal.is_empty() {
+            return GoalStatus::RuledOut;
+        }
+        if goal.iter().all(|value| Self::is_derived(memory, *value)) {
+            return GoalStatus::Confirmed;
+        }
+
+        let unreachable = goal.iter().any(|value| {
+            !Self::is_derived(memory, *value)
+                && self
+                    .concluding_rules(*value)
+                    .all(|rule| Self::rule_holds(rule, &memory.facts) == Some(false))
+        });
+        if unreachable {
+            GoalStatus::RuledOut
+        } else {
+            GoalStatus::Open
+        }
+    }
+
+    fn seek_rule(
+        &self,
+        rule: &RuleWithClausesAndEffects,
+        memory: &WorkingMemory,
+        visited: &mut HashSet<i32>,
+    ) -> Option<i32> {
+        let condition = Self::rule_condition(rule)?;
+
+        Self::unknown_clauses(rule, &condition, &memory.facts)
+            .into_iter()
+            .find_map(|clause| self.seek_question(clause.question_id, memory, visited))
+    }
+
+    // Если ответ на вопрос может быть выведен другим правилом, сначала спрашиваем его условия
+    fn seek_question(
+        &self,
+        question_id: i32,
+        memory: &WorkingMemory,
+        visited: &mut HashSet<i32>,
+    ) -> Option<i32> {
+        if !visited.insert(question_id) {
+            return None;
+        }
+
+        self.rules
+            .iter()
+            .filter(|rule| {
+                rule.rule_question_answer_ids
+                    .iter()
+                    .any(|effect| effect.question_id == question_id)
+                    && Self::rule_holds(rule, &memory.facts).is_none()
+            })
+            .find_map(|rule| self.seek_rule(rule, memory, visited))
+            .or(Some(question_id))
+    }
+
+    pub fn next_goal_question(
+        &self,
+        goal: &[i32],
+        memory: &WorkingMemory,
+    ) -> Option<&QuestionWithAnswersModel> {
+        let mut visited = HashSet::new();
+
+        goal.iter()
+            .filter(|value| !Self::is_derived(memory, **value))
+            .find_map(|value| {
+                self.concluding_rules(*value)
+                    .filter(|rule| Self::rule_holds(rule, &memory.facts).is_none())
+                    .find_map(|rule| self.seek_rule(rule, memory, &mut visited))
+            })
+            .and_then(|question_id| {
+                self.questions
+                    .iter()
+                    .find(|question| question.id == question_id)
+            })
+    }
+
+    pub fn result(&self, memory: WorkingMemory) -> InferenceResultModel {
+        let objects = self.rank_objects(&memory);
+
+        InferenceResultModel {
+            fired_rules: memory.fired_rules,
+            derived_answers: memory.derived_answers,
+            attribute_values: memory.attribute_values,
+            objects,
+        }
+    }
+
+    pub fn infer(&self, given: &[GivenAnswerModel]) -> InferenceResultModel {
+        self.result(self.run(self.given_facts(given)))
+    }
+
+    // Факты только добавляются, поэтому выполненная при срабатывании группа условий остается выполненной
+    fn rule_trace(rule: &RuleWithClausesAndEffects, memory: &WorkingMemory) -> RuleTraceModel {
+        let satisfied_clauses = Self::rule_condition(rule)
+            .map(|condition| Self::supporting_clauses(rule, &condition, &memory.facts, false))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(clause, negated)| {
+                let fact = memory.facts.get(&clause.question_id)?;
+                Some(SatisfiedClauseModel {
+                    clause_id: clause.id,
+                    question_id: clause.question_id,
+                    operator: clause.operator.clone(),
+                    compared_value: clause.compared_value.clone(),
+                    value_type: clause.value_type,
+                    logical_group: clause.logical_group.clone(),
+                    answer_id: fact.answer_id,
+                    value: fact.value.clone(),
+                    certainty_factor: fact.certainty_factor,
+                    negated,
+                    derived_by_rule_id: memory
+                        .derived_answers
+                        .iter()
+                        .find(|derived| derived.question_id == clause.question_id)
+                        .map(|derived| derived.rule_id),
+                })
+            })
+            .collect();
+        let premise_certainty_factor = Self::premise_certainty_factor(rule, &memory.facts);
+
+        RuleTraceModel {
+            rule_id: rule.id,
+            certainty_factor: rule.certainty_factor,
+            premise_certainty_factor,
+            satisfied_clauses,
+            derived_answers: memory
+                .derived_answers
+                .iter()
+                .filter(|derived| derived.rule_id == rule.id)
+                .cloned()
+                .collect(),
+            // Вклад самого правила, до комбинирования с другими правилами
+            attribute_values: rule
+                .rule_attribute_attributevalue_ids
+                .iter()
+                .map(|effect| DerivedAttributeValueModel {
+                    attribute_id: effect.attribute_id,
+                    attribute_value_id: effect.attribute_value_id,
+                    rule_id: rule.id,
+                    certainty_factor: rule.certainty_factor * premise_certainty_factor,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn explain(&self, given: &[GivenAnswerModel]) -> ExplanationModel {
+        let memory = self.run(self.given_facts(given));
+
+        let rules = memory
+            .fired_rules
+            .iter()
+            .filter_map(|rule_id| self.rules.iter().find(|rule| rule.id == *rule_id))
+            .map(|rule| Self::rule_trace(rule, &memory))
+            .collect();
+
+        let objects = self
+            .rank_objects(&memory)
+            .into_iter()
+            .filter_map(|score| {
+                let object = self
+                    .objects
+                    .iter()
+                    .find(|object| object.id == score.object_id)?;
+                let values = object
+                    .object_attribute_attributevalue_ids
+                    .iter()
+                    .map(|value| {
+                        let derived = memory.attribute_values.iter().find(|derived| {
+                            derived.attribute_id == value.attribute_id
+                                && derived.attribute_value_id == value.attribute_value_id
+                        });
+                        ObjectValueTraceModel {
+                            attribute_id: value.attribute_id,
+                            attribute_value_id: value.attribute_value_id,
+                            matched: derived.is_some_and(|derived| derived.certainty_factor > 0.0),
+                            rule_id: derived.map(|derived| derived.rule_id),
+                            certainty_factor: derived.map(|derived| derived.certainty_factor),
+                        }
+                    })
+                    .collect();
+                Some(ObjectTraceModel {
+                    object_id: score.object_id,
+                    name: score.name,
+                    matched: score.matched,
+                    total: score.total,
+                    percent: score.percent,
+                    values,
+                })
+            })
+            .collect();
+
+        ExplanationModel {
+            given_answers: given.to_vec(),
+            rules,
+            objects,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::{
+        clause, concludes_answer, concludes_value, object, question, rule,
+    };
+
+    fn facts(values: &[(i32, &str, f64)]) -> HashMap<i32, Fact> {
+        values
+            .iter()
+            .map(|(question_id, value, certainty_factor)| {
+                (
+                    *question_id,
+                    Fact {
+                        answer_id: value.parse().ok(),
+                        value: value.to_string(),
+                        certainty_factor: *certainty_factor,
+                    },
+                )
+            })
+            .collect()
+    }
+
+    fn close(first: f64, second: f64) -> bool {
+        (first - second).abs() < 1e-9
+    }
+
+    #[test]
+    fn clause_is_unknown_until_question_is_answered() {
+        let above = clause(1, 1, Operatorenum::Above, Valuetypeenum::Decimal, "37,5");
+
+        assert_eq!(InferenceEngine::clause_holds(&above, &HashMap::new()), None);
+        assert_eq!(
+            InferenceEngine::clause_holds(&above, &facts(&[(1, "38", 1.0)])),
+            Some(true)
+        );
+        assert_eq!(
+            InferenceEngine::clause_holds(&above, &facts(&[(1, "абв", 1.0)])),
+            Some(false)
+        );
+
+        let boolean = clause(2, 1, Operatorenum::Equal, Valuetypeenum::Boolean, "true");
+        assert_eq!(
+            InferenceEngine::clause_holds(&boolean, &facts(&[(1, "Да", 1.0)])),
+            Some(true)
+        );
+
+        let answer = clause(3, 1, Operatorenum::NotEqual, Valuetypeenum::Answer, "11");
+        assert_eq!(
+            InferenceEngine::clause_holds(&answer, &facts(&[(1, "12", 1.0)])),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn groups_are_joined_with_or() {
+        let rule = rule(
+            1,
+            vec![
+                clause(1, 1, Operatorenum::Equal, Valuetypeenum::Text, "a"),
+                clause(2, 2, Operatorenum::Equal, Valuetypeenum::Text, "b"),
+                ClauseModel {
+                    logical_group: "2".to_string(),
+                    ..clause(3, 3, Operatorenum::Equal, Valuetypeenum::Text, "c")
+                },
+            ],
+        );
+        let holds =
+            |values: &[(i32, &str, f64)]| InferenceEngine::rule_holds(&rule, &facts(values));
+
+        assert_eq!(holds(&[(1, "a", 1.0), (2, "b", 1.0)]), Some(true));
+        assert_eq!(holds(&[(3, "c", 1.0)]), Some(true));
+        // Первая группа еще может выполниться
+        assert_eq!(holds(&[(1, "a", 1.0), (3, "x", 1.0)]), None);
+        assert_eq!(holds(&[(1, "x", 1.0), (3, "x", 1.0)]), Some(false));
+    }
+
+    #[test]
+    fn nodes_use_three_valued_logic() {
+        let rule = rule(
+            1,
+            vec![
+                clause(1, 1, Operatorenum::Equal, Valuetypeenum::Text, "a"),
+                clause(2, 1, Operatorenum::Equal, Valuetypeenum::Text, "b"),
+                clause(3, 2, Operatorenum::Equal, Valuetypeenum::Text, "c"),
+            ],
+        );
+        let facts = facts(&[(1, "a", 1.0)]);
+        let holds = |node: ConditionNode| InferenceEngine::node_holds(&rule, &node, &facts);
+        let (t, f, u) = (
+            ConditionNode::Clause(1),
+            ConditionNode::Clause(2),
+            ConditionNode::Clause(3),
+        );
+
+        assert_eq!(
+            holds(ConditionNode::And(vec![f.clone(), u.clone()])),
+            Some(false)
+        );
+        assert_eq!(holds(ConditionNode::And(vec![t.clone(), u.clone()])), None);
+        assert_eq!(
+            holds(ConditionNode::Or(vec![t.clone(), u.clone()])),
+            Some(true)
+        );
+        assert_eq!(holds(ConditionNode::Or(vec![f.clone(), u.clone()])), None);
+        assert_eq!(holds(ConditionNode::Not(Box::new(u))), None);
+        assert_eq!(holds(ConditionNode::Not(Box::new(f))), Some(true));
+        // Лист на условие другого правила не вычислим
+        assert_eq!(holds(ConditionNode::Clause(99)), None);
+    }
+
+    #[test]
+    fn rule_without_clauses_never_holds() {
+        let rule = rule(1, vec![]);
+
+        assert_eq!(
+            InferenceEngine::rule_holds(&rule, &HashMap::new()),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn fired_rules_combine_certainty_factors() {
+        let first = concludes_value(
+            RuleWithClausesAndEffects {
+                certainty_factor: 0.6,
+                ..rule(
+                    1,
+                    vec![
+                        clause(1, 1, Operatorenum::Equal, Valuetypeenum::Text, "a"),
+                        clause(2, 2, Operatorenum::Equal, Valuetypeenum::Text, "b"),
+                    ],
+                )
+            },
+            1,
+            10,
+        );
+        let second = concludes_value(
+            RuleWithClausesAndEffects {
+                certainty_factor: 0.5,
+                ..rule(
+                    2,
+                    vec![clause(3, 1, Operatorenum::Equal, Valuetypeenum::Text, "a")],
+                )
+            },
+            1,
+            10,
+        );
+        let against = concludes_value(
+            RuleWithClausesAndEffects {
+                certainty_factor: -0.4,
+                ..rule(
+                    3,
+                    vec![clause(4, 2, Operatorenum::Equal, Valuetypeenum::Text, "b")],
+                )
+            },
+            1,
+            20,
+        );
+        let engine = InferenceEngine::new(vec![first, second, against], vec![], vec![]);
+
+        let memory = engine.run(facts(&[(1, "a", 1.0), (2, "b", 0.5)]));
+
+        assert_eq!(memory.fired_rules, vec![1, 2, 3]);
+        let value = |attribute_value_id: i32| {
+            memory
+                .attribute_values
+                .iter()
+                .find(|value| value.attribute_value_id == attribute_value_id)
+                .map(|value| value.certainty_factor)
+                .unwrap()
+        };
+        // И берет минимум уверенности фактов: 0.6 * 0.5 = 0.3, затем 0.3 + 0.5 * (1 - 0.3)
+        assert!(close(value(10), 0.65));
+        assert!(close(value(20), -0.2));
+        assert!(close(combine_certainty_factors(0.6, -0.4), 0.2 / 0.6));
+        assert!(close(combine_certainty_factors(-0.5, -0.5), -0.75));
+    }
+
+    #[test]
+    fn derived_answers_keep_given_facts() {
+        let derive = concludes_answer(
+            RuleWithClausesAndEffects {
+                certainty_factor: 0.8,
+                ..rule(
+                    1,
+                    vec![clause(1, 1, Operatorenum::Equal, Valuetypeenum::Text, "a")],
+                )
+            },
+            2,
+            21,
+        );
+        let engine = InferenceEngine::new(
+            vec![derive],
+            vec![question(2, "Вопрос 2", &[(21, "Ответ 21")])],
+            vec![],
+        );
+
+        let memory = engine.run(facts(&[(1, "a", 1.0)]));
+        assert_eq!(memory.facts[&2].value, "Ответ 21");
+        assert!(close(memory.facts[&2].certainty_factor, 0.8));
+
+        let memory = engine.run(facts(&[(1, "a", 1.0), (2, "другое", 1.0)]));
+        assert_eq!(memory.facts[&2].value, "другое");
+        assert!(memory.derived_answers.is_empty());
+    }
+
+    #[test]
+    fn chained_rules_rank_objects() {
+        // Ответ на вопрос 2 выводится из вопроса 1, значение атрибута - из вопроса 2
+        let derive = concludes_answer(
+            rule(
+                1,
+                vec![clause(1, 1, Operatorenum::Equal, Valuetypeenum::Text, "a")],
+            ),
+            2,
+            21,
+        );
+        let conclude = concludes_value(
+            rule(
+                2,
+                vec![clause(
+                    2,
+                    2,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "21",
+                )],
+            ),
+            1,
+            10,
+        );
+        let engine = InferenceEngine::new(
+            vec![conclude, derive],
+            vec![
+                question(1, "Вопрос 1", &[]),
+                question(2, "Вопрос 2", &[(21, "Ответ 21")]),
+            ],
+            vec![object(1, &[(1, 10), (2, 20)]), object(2, &[(1, 11)])],
+        );
+
+        let result = engine.infer(&[GivenAnswerModel {
+            question_id: 1,
+            answer_id: None,
+            value: Some("a".to_string()),
+            certainty_factor: None,
+        }]);
+
+        assert_eq!(result.fired_rules, vec![1, 2]);
+        let percents: Vec<(i32, u8)> = result
+            .objects
+            .iter()
+            .map(|object| (object.object_id, object.percent))
+            .collect();
+        assert_eq!(percents, vec![(1, 50), (2, 0)]);
+    }
+
+    #[test]
+    fn goal_question_follows_derivable_questions() {
+        // Цель выводится из вопроса 2, а ответ на него - правилом из вопроса 3
+        let goal_rule = concludes_value(
+            rule(
+                1,
+                vec![clause(
+                    1,
+                    2,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "21",
+                )],
+            ),
+            1,
+            10,
+        );
+        let derive = concludes_answer(
+            rule(
+                2,
+                vec![clause(2, 3, Operatorenum::Equal, Valuetypeenum::Text, "да")],
+            ),
+            2,
+            21,
+        );
+        let engine = InferenceEngine::new(
+            vec![goal_rule, derive],
+            vec![
+                question(1, "Вопрос 1", &[]),
+                question(2, "Вопрос 2", &[(21, "Ответ 21")]),
+                question(3, "Вопрос 3", &[]),
+            ],
+            vec![],
+        );
+        let goal = engine.goal_values(None, Some(10));
+
+        let memory = engine.run(HashMap::new());
+        assert_eq!(engine.goal_status(&goal, &memory), GoalStatus::Open);
+        assert_eq!(
+            engine
+                .next_goal_question(&goal, &memory)
+                .map(|question| question.id),
+            Some(3)
+        );
+
+        let memory = engine.run(facts(&[(3, "да", 1.0)]));
+        assert_eq!(engine.goal_status(&goal, &memory), GoalStatus::Confirmed);
+
+        let memory = engine.run(facts(&[(2, "22", 1.0)]));
+        assert_eq!(engine.goal_status(&goal, &memory), GoalStatus::RuledOut);
+        assert!(engine.next_goal_question(&goal, &memory).is_none());
+    }
+}
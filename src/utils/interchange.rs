@@ -0,0 +1,602 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    error::CustomErrors,
+    models::{
+        inference::GivenAnswerModel,
+        interchange::{
+            AnswerDocumentModel, AttributeDocumentModel, AttributeValueDocumentModel,
+            ClauseDocumentModel, GivenAnswerDocumentModel, InterchangeFormat, ObjectDocumentModel,
+            QuestionDocumentModel, RuleDocumentModel, SystemDocumentModel, TestCaseDocumentModel,
+            INTERCHANGE_FORMAT_VERSION,
+        },
+    },
+};
+use entity::{
+    backup::{
+        BackupAnswerModel, BackupAttributeModel, BackupAttributeValueModel, BackupClauseModel,
+        BackupObjectAttributeAttributeValueModel, BackupObjectModel, BackupQuestionModel,
+        BackupRuleAttributeAttributeValueModel, BackupRuleModel, BackupRuleQuestionAnswerModel,
+        BackupSystemModel, BackupTestCaseModel, SystemBackupModel,
+    },
+    rules::{ConditionNode, NewConditionNode},
+    sea_orm_active_enums::Valuetypeenum,
+};
+use http::StatusCode;
+use serde_json::json;
+
+// Ключ ограничен по длине, чтобы длинные формулировки вопросов не раздували документ
+const MAX_KEY_LENGTH: usize = 40;
+
+fn document_error(error: String) -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::BAD_REQUEST,
+        error,
+    }
+}
+
+pub fn document_to_string(
+    document: &SystemDocumentModel,
+    format: InterchangeFormat,
+) -> Result<String, CustomErrors> {
+    let result = match format {
+        InterchangeFormat::Json => {
+            serde_json::to_string_pretty(document).map_err(|err| err.to_string())
+        }
+        InterchangeFormat::Yaml => serde_yaml::to_string(document).map_err(|err| err.to_string()),
+    };
+    result.map_err(document_error)
+}
+
+fn check_format_version(version: Option<u64>) -> Result<(), CustomErrors> {
+    if version == Some(INTERCHANGE_FORMAT_VERSION as u64) {
+        return Ok(());
+    }
+    Err(document_error(format!(
+        "Неподдерживаемая версия формата {}, ожидалась {}",
+        version.map_or("(не указана)".to_string(), |version| version
+            .to_string()),
+        INTERCHANGE_FORMAT_VERSION
+    )))
+}
+
+// Версию проверяем до разбора структуры, иначе вместо нее пользователь увидит ошибку полей
+pub fn document_from_str(
+    source: &str,
+    format: InterchangeFormat,
+) -> Result<SystemDocumentModel, CustomErrors> {
+    let parse_error = |err: String| document_error(format!("Ошибка разбора документа: {}", err));
+
+    match format {
+        InterchangeFormat::Json => {
+            let value: serde_json::Value =
+                serde_json::from_str(source).map_err(|err| parse_error(err.to_string()))?;
+            check_format_version(
+                value
+                    .get("format_version")
+                    .and_then(|version| version.as_u64()),
+            )?;
+            serde_json::from_value(value).map_err(|err| parse_error(err.to_string()))
+        }
+        InterchangeFormat::Yaml => {
+            let value: serde_yaml::Value =
+                serde_yaml::from_str(source).map_err(|err| parse_error(err.to_string()))?;
+            check_format_version(
+                value
+                    .get("format_version")
+                    .and_then(|version| version.as_u64()),
+            )?;
+            serde_yaml::from_value(value).map_err(|err| parse_error(err.to_string()))
+        }
+    }
+}
+
+#[derive(Default)]
+struct KeyGenerator {
+    used: HashSet<String>,
+}
+
+impl KeyGenerator {
+    fn slug(name: &str, fallback: &str) -> String {
+        let mut slug = String::new();
+        for char in name.trim().to_lowercase().chars() {
+            if char.is_alphanumeric() {
+                slug.push(char);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        let slug: String = slug
+            .trim_end_matches('-')
+            .chars()
+            .take(MAX_KEY_LENGTH)
+            .collect();
+        let slug = slug.trim_end_matches('-');
+        if slug.is_empty() {
+            fallback.to_string()
+        } else {
+            slug.to_string()
+        }
+    }
+
+    fn key(&mut self, prefix: Option<&str>, name: &str, fallback: &str) -> String {
+        let slug = Self::slug(name, fallback);
+        let base = match prefix {
+            Some(prefix) => format!("{}/{}", prefix, slug),
+            None => slug,
+        };
+
+        let mut key = base.clone();
+        let mut suffix = 2;
+        while self.used.contains(&key) {
+            key = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        self.used.insert(key.clone());
+        key
+    }
+}
+
+// Ключи выводятся из текста сущностей, поэтому не меняются при повторной выгрузке
+pub fn document_from_backup(backup: &SystemBackupModel) -> SystemDocumentModel {
+    let mut keys = KeyGenerator::default();
+
+    let mut questions: Vec<&BackupQuestionModel> = backup.questions.iter().collect();
+    questions.sort_by_key(|question| question.id);
+    let mut answers: Vec<&BackupAnswerModel> = backup.answers.iter().collect();
+    answers.sort_by_key(|answer| answer.id);
+    let mut attributes: Vec<&BackupAttributeModel> = backup.attributes.iter().collect();
+    attributes.sort_by_key(|attribute| attribute.id);
+    let mut values: Vec<&BackupAttributeValueModel> = backup.attributes_values.iter().collect();
+    values.sort_by_key(|value| value.id);
+    let mut objects: Vec<&BackupObjectModel> = backup.objects.iter().collect();
+    objects.sort_by_key(|object| object.id);
+
+    let mut question_keys: HashMap<i32, String> = HashMap::new();
+    let mut answer_keys: HashMap<i32, String> = HashMap::new();
+    let question_documents = questions
+        .iter()
+        .map(|question| {
+            let key = keys.key(None, &question.body, "question");
+            question_keys.insert(question.id, key.clone());
+            QuestionDocumentModel {
+                answers: answers
+                    .iter()
+                    .filter(|answer| answer.question_id == question.id)
+                    .map(|answer| {
+                        let answer_key = keys.key(Some(&key), &answer.body, "answer");
+                        answer_keys.insert(answer.id, answer_key.clone());
+                        AnswerDocumentModel {
+                            key: answer_key,
+                            body: answer.body.clone(),
+                        }
+                    })
+                    .collect(),
+                key,
+                body: question.body.clone(),
+                with_chooses: question.with_chooses,
+            }
+        })
+        .collect();
+
+    let mut value_keys: HashMap<i32, String> = HashMap::new();
+    let attribute_documents = attributes
+        .iter()
+        .map(|attribute| {
+            let key = keys.key(None, &attribute.name, "attribute");
+            AttributeDocumentModel {
+                values: values
+                    .iter()
+                    .filter(|value| value.attribute_id == attribute.id)
+                    .map(|value| {
+                        let value_key = keys.key(Some(&key), &value.value, "value");
+                        value_keys.insert(value.id, value_key.clone());
+                        AttributeValueDocumentModel {
+                            key: value_key,
+                            value: value.value.clone(),
+                        }
+                    })
+                    .collect(),
+                key,
+                name: attribute.name.clone(),
+            }
+        })
+        .collect();
+
+    // Ссылки, которых нет в документе, оставляем числом - импорт сообщит о них
+    let key_of =
+        |map: &HashMap<i32, String>, id: i32| map.get(&id).cloned().unwrap_or(id.to_string());
+
+    let mut object_keys: HashMap<i32, String> = HashMap::new();
+    let object_documents = objects
+        .iter()
+        .map(|object| {
+            let key = keys.key(None, &object.name, "object");
+            object_keys.insert(object.id, key.clone());
+            let mut links: Vec<&BackupObjectAttributeAttributeValueModel> = backup
+                .object_attribute_attributevalue
+                .iter()
+                .filter(|link| link.object_id == object.id)
+                .collect();
+            links.sort_by_key(|link| link.id);
+            ObjectDocumentModel {
+                key,
+                name: object.name.clone(),
+                attribute_values: links
+                    .iter()
+                    .map(|link| key_of(&value_keys, link.attribute_value_id))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut rules: Vec<&BackupRuleModel> = backup.rules.iter().collect();
+    rules.sort_by_key(|rule| rule.id);
+    let rule_documents = rules
+        .iter()
+        .map(|rule| {
+            let mut clauses: Vec<&BackupClauseModel> = backup
+                .clauses
+                .iter()
+                .filter(|clause| clause.rule_id == rule.id)
+                .collect();
+            clauses.sort_by_key(|clause| clause.id);
+            let clause_indexes: HashMap<i32, usize> = clauses
+                .iter()
+                .enumerate()
+                .map(|(index, clause)| (clause.id, index))
+                .collect();
+            let mut answer_effects: Vec<&BackupRuleQuestionAnswerModel> = backup
+                .rule_question_answer
+                .iter()
+                .filter(|effect| effect.rule_id == rule.id)
+                .collect();
+            answer_effects.sort_by_key(|effect| effect.id);
+            let mut value_effects: Vec<&BackupRuleAttributeAttributeValueModel> = backup
+                .rule_attribute_attributevalue
+                .iter()
+                .filter(|effect| effect.rule_id == rule.id)
+                .collect();
+            value_effects.sort_by_key(|effect| effect.id);
+
+            RuleDocumentModel {
+                attribute_rule: rule.attribute_rule,
+                certainty_factor: rule.certainty_factor,
+                condition: rule
+                    .condition
+                    .as_ref()
+                    .and_then(|condition| serde_json::from_str::<ConditionNode>(condition).ok())
+                    .and_then(|condition| {
+                        let live_clause_ids: Vec<i32> = clause_indexes.keys().copied().collect();
+                        condition.prune(&live_clause_ids)
+                    })
+                    .and_then(|condition| {
+                        NewConditionNode::from_condition(&condition, &|clause_id| {
+                            clause_indexes.get(&clause_id).copied()
+                        })
+                    })
+                    .map(|condition| json!(condition)),
+                clauses: clauses
+                    .iter()
+                    .map(|clause| ClauseDocumentModel {
+                        question: key_of(&question_keys, clause.question_id),
+                        operator: clause.operator.clone(),
+                        value_type: clause.value_type,
+                        value: match clause.value_type {
+                            Valuetypeenum::Answer => clause
+                                .compared_value
+                                .trim()
+                                .parse::<i32>()
+                                .ok()
+                                .and_then(|answer_id| answer_keys.get(&answer_id).cloned())
+                                .unwrap_or(clause.compared_value.clone()),
+                            _ => clause.compared_value.clone(),
+                        },
+                        logical_group: clause.logical_group.clone(),
+                    })
+                    .collect(),
+                answers: answer_effects
+                    .iter()
+                    .map(|effect| key_of(&answer_keys, effect.answer_id))
+                    .collect(),
+                attribute_values: value_effects
+                    .iter()
+                    .map(|effect| key_of(&value_keys, effect.attribute_value_id))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    let mut test_cases: Vec<&BackupTestCaseModel> = backup.test_cases.iter().collect();
+    test_cases.sort_by_key(|test_case| test_case.id);
+    let test_case_documents = test_cases
+        .iter()
+        .map(|test_case| {
+            let ids = |raw: &str| serde_json::from_str::<Vec<i32>>(raw).unwrap_or_default();
+            TestCaseDocumentModel {
+                name: test_case.name.clone(),
+                answers: serde_json::from_str::<Vec<GivenAnswerModel>>(&test_case.answers)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|answer| GivenAnswerDocumentModel {
+                        question: key_of(&question_keys, answer.question_id),
+                        answer: answer
+                            .answer_id
+                            .map(|answer_id| key_of(&answer_keys, answer_id)),
+                        value: answer.value,
+                        certainty_factor: answer.certainty_factor,
+                    })
+                    .collect(),
+                expected_objects: ids(&test_case.expected_object_ids)
+                    .into_iter()
+                    .map(|object_id| key_of(&object_keys, object_id))
+                    .collect(),
+                expected_attribute_values: ids(&test_case.expected_attribute_value_ids)
+                    .into_iter()
+                    .map(|attribute_value_id| key_of(&value_keys, attribute_value_id))
+                    .collect(),
+            }
+        })
+        .collect();
+
+    SystemDocumentModel {
+        format_version: INTERCHANGE_FORMAT_VERSION,
+        name: backup.system.name.clone(),
+        about: backup.system.about.clone(),
+        private: backup.system.private,
+        questions: question_documents,
+        attributes: attribute_documents,
+        objects: object_documents,
+        rules: rule_documents,
+        test_cases: test_case_documents,
+    }
+}
+
+// Сопоставляет ключам документа id, по которым затем работает общий код восстановления
+#[derive(Default)]
+struct KeyRegistry {
+    ids: HashMap<String, i32>,
+    next_id: i32,
+}
+
+impl KeyRegistry {
+    fn register(&mut self, key: &str) -> Result<i32, CustomErrors> {
+        if self.ids.contains_key(key) {
+            return Err(document_error(format!(
+                "Ключ \"{}\" встречается в документе несколько раз",
+                key
+            )));
+        }
+        self.next_id += 1;
+        self.ids.insert(key.to_string(), self.next_id);
+        Ok(self.next_id)
+    }
+
+    fn resolve(&self, key: &str, what: &str) -> Result<i32, CustomErrors> {
+        self.ids.get(key).copied().ok_or(document_error(format!(
+            "Не найден ключ \"{}\" ({})",
+            key, what
+        )))
+    }
+}
+
+pub fn backup_from_document(
+    document: SystemDocumentModel,
+    user_id: i32,
+) -> Result<SystemBackupModel, CustomErrors> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let mut questions = KeyRegistry::default();
+    let mut answers = KeyRegistry::default();
+    let mut attributes = KeyRegistry::default();
+    let mut values = KeyRegistry::default();
+    let mut objects = KeyRegistry::default();
+    let mut answer_questions: HashMap<i32, i32> = HashMap::new();
+    let mut value_attributes: HashMap<i32, i32> = HashMap::new();
+
+    let mut backup = SystemBackupModel {
+        system: BackupSystemModel {
+            id: 0,
+            user_id,
+            about: document.about,
+            created_at: now,
+            updated_at: now,
+            name: document.name,
+            private: document.private,
+            image_uri: None,
+            stars: 0,
+        },
+        objects: Vec::new(),
+        object_attribute_attributevalue: Vec::new(),
+        attributes: Vec::new(),
+        attributes_values: Vec::new(),
+        rules: Vec::new(),
+        rule_attribute_attributevalue: Vec::new(),
+        clauses: Vec::new(),
+        questions: Vec::new(),
+        answers: Vec::new(),
+        rule_question_answer: Vec::new(),
+        test_cases: Vec::new(),
+        media: Vec::new(),
+    };
+
+    for question in document.questions {
+        let question_id = questions.register(&question.key)?;
+        for answer in question.answers {
+            let answer_id = answers.register(&answer.key)?;
+            answer_questions.insert(answer_id, question_id);
+            backup.answers.push(BackupAnswerModel {
+                id: answer_id,
+                question_id,
+                body: answer.body,
+            });
+        }
+        backup.questions.push(BackupQuestionModel {
+            id: question_id,
+            system_id: 0,
+            body: question.body,
+            with_chooses: question.with_chooses,
+        });
+    }
+
+    for attribute in document.attributes {
+        let attribute_id = attributes.register(&attribute.key)?;
+        for value in attribute.values {
+            let value_id = values.register(&value.key)?;
+            value_attributes.insert(value_id, attribute_id);
+            backup.attributes_values.push(BackupAttributeValueModel {
+                id: value_id,
+                attribute_id,
+                value: value.value,
+            });
+        }
+        backup.attributes.push(BackupAttributeModel {
+            id: attribute_id,
+            system_id: 0,
+            name: attribute.name,
+        });
+    }
+
+    for object in document.objects {
+        let object_id = objects.register(&object.key)?;
+        for value_key in object.attribute_values {
+            let attribute_value_id = values.resolve(&value_key, "значение атрибута")?;
+            backup
+                .object_attribute_attributevalue
+                .push(BackupObjectAttributeAttributeValueModel {
+                    id: backup.object_attribute_attributevalue.len() as i32 + 1,
+                    object_id,
+                    attribute_value_id,
+                    attribute_id: value_attributes[&attribute_value_id],
+                });
+        }
+        backup.objects.push(BackupObjectModel {
+            id: object_id,
+            system_id: 0,
+            name: object.name,
+        });
+    }
+
+    for (index, rule) in document.rules.into_iter().enumerate() {
+        let rule_id = index as i32 + 1;
+        if !(-1.0..=1.0).contains(&rule.certainty_factor) {
+            return Err(document_error(
+                "Коэффициент уверенности правила должен быть от -1 до 1".to_string(),
+            ));
+        }
+
+        let first_clause_id = backup.clauses.len() as i32 + 1;
+        for clause in &rule.clauses {
+            let question_id = questions.resolve(&clause.question, "вопрос")?;
+            let compared_value = match clause.value_type {
+                Valuetypeenum::Answer => {
+                    let answer_id = answers.resolve(&clause.value, "ответ")?;
+                    if answer_questions[&answer_id] != question_id {
+                        return Err(document_error(format!(
+                            "Ответ \"{}\" не относится к вопросу \"{}\"",
+                            clause.value, clause.question
+                        )));
+                    }
+                    answer_id.to_string()
+                }
+                _ => clause.value.clone(),
+            };
+            backup.clauses.push(BackupClauseModel {
+                id: backup.clauses.len() as i32 + 1,
+                rule_id,
+                compared_value,
+                logical_group: clause.logical_group.clone(),
+                operator: clause.operator.clone(),
+                question_id,
+                value_type: clause.value_type,
+            });
+        }
+
+        let clause_ids: Vec<i32> =
+            (first_clause_id..first_clause_id + rule.clauses.len() as i32).collect();
+        let condition = match rule.condition {
+            Some(condition) => Some(
+                serde_json::from_value::<NewConditionNode>(condition)
+                    .map_err(|err| {
+                        document_error(format!("Ошибка в дереве условий правила: {}", err))
+                    })?
+                    .resolve(&clause_ids)
+                    .ok_or(document_error(
+                        "Дерево условий ссылается на несуществующее условие".to_string(),
+                    ))?,
+            ),
+            None => None,
+        };
+
+        for answer_key in rule.answers {
+            let answer_id = answers.resolve(&answer_key, "ответ")?;
+            backup
+                .rule_question_answer
+                .push(BackupRuleQuestionAnswerModel {
+                    id: backup.rule_question_answer.len() as i32 + 1,
+                    answer_id,
+                    rule_id,
+                    question_id: answer_questions[&answer_id],
+                });
+        }
+        for value_key in rule.attribute_values {
+            let attribute_value_id = values.resolve(&value_key, "значение атрибута")?;
+            backup
+                .rule_attribute_attributevalue
+                .push(BackupRuleAttributeAttributeValueModel {
+                    id: backup.rule_attribute_attributevalue.len() as i32 + 1,
+                    attribute_value_id,
+                    rule_id,
+                    attribute_id: value_attributes[&attribute_value_id],
+                });
+        }
+
+        backup.rules.push(BackupRuleModel {
+            id: rule_id,
+            system_id: 0,
+            attribute_rule: rule.attribute_rule,
+            certainty_factor: rule.certainty_factor,
+            condition: condition.map(|condition| json!(condition).to_string()),
+        });
+    }
+
+    for (index, test_case) in document.test_cases.into_iter().enumerate() {
+        let given = test_case
+            .answers
+            .into_iter()
+            .map(|answer| {
+                Ok(GivenAnswerModel {
+                    question_id: questions.resolve(&answer.question, "вопрос")?,
+                    answer_id: match answer.answer {
+                        Some(answer_key) => Some(answers.resolve(&answer_key, "ответ")?),
+                        None => None,
+                    },
+                    value: answer.value,
+                    certainty_factor: answer.certainty_factor,
+                })
+            })
+            .collect::<Result<Vec<GivenAnswerModel>, CustomErrors>>()?;
+        let expected_object_ids = test_case
+            .expected_objects
+            .iter()
+            .map(|key| objects.resolve(key, "объект"))
+            .collect::<Result<Vec<i32>, CustomErrors>>()?;
+        let expected_attribute_value_ids = test_case
+            .expected_attribute_values
+            .iter()
+            .map(|key| values.resolve(key, "значение атрибута"))
+            .collect::<Result<Vec<i32>, CustomErrors>>()?;
+
+        backup.test_cases.push(BackupTestCaseModel {
+            id: index as i32 + 1,
+            system_id: 0,
+            name: test_case.name,
+            answers: json!(given).to_string(),
+            expected_object_ids: json!(expected_object_ids).to_string(),
+            expected_attribute_value_ids: json!(expected_attribute_value_ids).to_string(),
+            created_at: now,
+        });
+    }
+
+    Ok(backup)
+}
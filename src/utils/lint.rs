@@ -0,0 +1,534 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    models::lint::{LintIssueCode, LintIssueModel},
+    utils::inference::{Fact, InferenceEngine},
+};
+use entity::{
+    attributes::AttributeWithAttributeValuesModel,
+    clauses::ClauseModel,
+    objects::ObjectWithAttributesValuesModel,
+    questions::QuestionWithAnswersModel,
+    rules::{ConditionNode, RuleWithClausesAndEffects},
+    sea_orm_active_enums::Valuetypeenum,
+};
+
+// Больше комбинаций ответов не перебираем, такое правило считаем достижимым
+const MAX_ASSIGNMENTS: usize = 4096;
+
+pub struct Linter<'a> {
+    rules: &'a [RuleWithClausesAndEffects],
+    questions: &'a [QuestionWithAnswersModel],
+    objects: &'a [ObjectWithAttributesValuesModel],
+    attributes: &'a [AttributeWithAttributeValuesModel],
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(
+        rules: &'a [RuleWithClausesAndEffects],
+        questions: &'a [QuestionWithAnswersModel],
+        objects: &'a [ObjectWithAttributesValuesModel],
+        attributes: &'a [AttributeWithAttributeValuesModel],
+    ) -> Self {
+        Linter {
+            rules,
+            questions,
+            objects,
+            attributes,
+        }
+    }
+
+    pub fn lint(&self) -> Vec<LintIssueModel> {
+        let mut issues = Vec::new();
+        issues.extend(self.unused_questions());
+        issues.extend(self.foreign_references());
+        issues.extend(self.unused_attribute_values());
+        issues.extend(self.empty_objects());
+        issues.extend(self.contradictory_rules());
+        issues.extend(self.unreachable_rules());
+        issues
+    }
+
+    fn question(&self, question_id: i32) -> Option<&QuestionWithAnswersModel> {
+        self.questions
+            .iter()
+            .find(|question| question.id == question_id)
+    }
+
+    fn answer_ids(&self) -> HashSet<i32> {
+        self.questions
+            .iter()
+            .flat_map(|question| question.answers.iter().map(|answer| answer.id))
+            .collect()
+    }
+
+    fn unused_questions(&self) -> Vec<LintIssueModel> {
+        let used: HashSet<i32> = self
+            .rules
+            .iter()
+            .flat_map(|rule| rule.clauses.iter().map(|clause| clause.question_id))
+            .collect();
+
+        self.questions
+            .iter()
+            .filter(|question| !used.contains(&question.id))
+            .map(|question| LintIssueModel {
+                question_id: Some(question.id),
+                ..LintIssueModel::new(
+                    LintIssueCode::UnusedQuestion,
+                    "Вопрос не используется ни в одном условии",
+                )
+            })
+            .collect()
+    }
+
+    fn foreign_references(&self) -> Vec<LintIssueModel> {
+        let answer_ids = self.answer_ids();
+        let mut issues = Vec::new();
+
+        for rule in self.rules {
+            for clause in &rule.clauses {
+                if self.question(clause.question_id).is_none() {
+                    issues.push(LintIssueModel {
+                        rule_ids: vec![rule.id],
+                        clause_id: Some(clause.id),
+                        question_id: Some(clause.question_id),
+                        ..LintIssueModel::new(
+                            LintIssueCode::ForeignQuestion,
+                            "Условие ссылается на вопрос другой системы",
+                        )
+                    });
+                }
+                if clause.value_type == Valuetypeenum::Answer {
+                    let answer_id = clause.compared_value.trim().parse::<i32>().ok();
+                    if !answer_id.is_some_and(|answer_id| answer_ids.contains(&answer_id)) {
+                        issues.push(LintIssueModel {
+                            rule_ids: vec![rule.id],
+                            clause_id: Some(clause.id),
+                            question_id: Some(clause.question_id),
+                            answer_id,
+                            ..LintIssueModel::new(
+                                LintIssueCode::ForeignAnswer,
+                                "Условие ссылается на ответ другой системы",
+                            )
+                        });
+                    }
+                }
+            }
+
+            for effect in &rule.rule_question_answer_ids {
+                if self.question(effect.question_id).is_none() {
+                    issues.push(LintIssueModel {
+                        rule_ids: vec![rule.id],
+                        question_id: Some(effect.question_id),
+                        ..LintIssueModel::new(
+                            LintIssueCode::ForeignQuestion,
+                            "Правило выводит ответ на вопрос другой системы",
+                        )
+                    });
+                } else if !answer_ids.contains(&effect.answer_id) {
+                    issues.push(LintIssueModel {
+                        rule_ids: vec![rule.id],
+                        question_id: Some(effect.question_id),
+                        answer_id: Some(effect.answer_id),
+                        ..LintIssueModel::new(
+                            LintIssueCode::ForeignAnswer,
+                            "Правило выводит ответ другой системы",
+                        )
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    fn unused_attribute_values(&self) -> Vec<LintIssueModel> {
+        let used: HashSet<i32> = self
+            .rules
+            .iter()
+            .flat_map(|rule| {
+                rule.rule_attribute_attributevalue_ids
+                    .iter()
+                    .map(|effect| effect.attribute_value_id)
+            })
+            .chain(self.objects.iter().flat_map(|object| {
+                object
+                    .object_attribute_attributevalue_ids
+                    .iter()
+                    .map(|value| value.attribute_value_id)
+            }))
+            .collect();
+
+        self.attributes
+            .iter()
+            .flat_map(|attribute| attribute.values.iter())
+            .filter(|value| !used.contains(&value.id))
+            .map(|value| LintIssueModel {
+                attribute_value_id: Some(value.id),
+                ..LintIssueModel::new(
+                    LintIssueCode::UnusedAttributeValue,
+                    "Значение атрибута не используется ни правилами, ни объектами",
+                )
+            })
+            .collect()
+    }
+
+    fn empty_objects(&self) -> Vec<LintIssueModel> {
+        self.objects
+            .iter()
+            .filter(|object| object.object_attribute_attributevalue_ids.is_empty())
+            .map(|object| LintIssueModel {
+                object_id: Some(object.id),
+                ..LintIssueModel::new(
+                    LintIssueCode::EmptyObject,
+                    "У объекта нет значений атрибутов",
+                )
+            })
+            .collect()
+    }
+
+    // Запись условия, не зависящая от id условий и порядка потомков
+    fn canonical_condition(rule: &RuleWithClausesAndEffects, node: &ConditionNode) -> String {
+        let children = |nodes: &[ConditionNode]| {
+            let mut children: Vec<String> = nodes
+                .iter()
+                .map(|node| Self::canonical_condition(rule, node))
+                .collect();
+            children.sort();
+            children.dedup();
+            children.join(",")
+        };
+
+        match node {
+            ConditionNode::And(nodes) => format!("And({})", children(nodes)),
+            ConditionNode::Or(nodes) => format!("Or({})", children(nodes)),
+            ConditionNode::Not(node) => format!("Not({})", Self::canonical_condition(rule, node)),
+            ConditionNode::Clause(clause_id) => rule
+                .clauses
+                .iter()
+                .find(|clause| clause.id == *clause_id)
+                .map(|clause| {
+                    format!(
+                        "{}:{:?}:{:?}:{}",
+                        clause.question_id,
+                        clause.operator,
+                        clause.value_type,
+                        clause.compared_value.trim()
+                    )
+                })
+                .unwrap_or_default(),
+        }
+    }
+
+    fn conflicting_effects(
+        first: &RuleWithClausesAndEffects,
+        second: &RuleWithClausesAndEffects,
+    ) -> bool {
+        let attribute_conflict = first
+            .rule_attribute_attributevalue_ids
+            .iter()
+            .any(|effect| {
+                second
+                    .rule_attribute_attributevalue_ids
+                    .iter()
+                    .any(|other| {
+                        other.attribute_id == effect.attribute_id
+                            && other.attribute_value_id != effect.attribute_value_id
+                    })
+            });
+        let answer_conflict = first.rule_question_answer_ids.iter().any(|effect| {
+            second.rule_question_answer_ids.iter().any(|other| {
+                other.question_id == effect.question_id && other.answer_id != effect.answer_id
+            })
+        });
+        // Одинаковые выводы с уверенностью разного знака опровергают друг друга
+        let certainty_conflict = first.certainty_factor * second.certainty_factor < 0.0
+            && first
+                .rule_attribute_attributevalue_ids
+                .iter()
+                .any(|effect| {
+                    second
+                        .rule_attribute_attributevalue_ids
+                        .iter()
+                        .any(|other| other.attribute_value_id == effect.attribute_value_id)
+                });
+
+        attribute_conflict || answer_conflict || certainty_conflict
+    }
+
+    fn contradictory_rules(&self) -> Vec<LintIssueModel> {
+        let conditions: Vec<(&RuleWithClausesAndEffects, String)> = self
+            .rules
+            .iter()
+            .filter_map(|rule| {
+                InferenceEngine::rule_condition(rule)
+                    .map(|condition| (rule, Self::canonical_condition(rule, &condition)))
+            })
+            .collect();
+
+        let mut issues = Vec::new();
+        for (index, (first, first_condition)) in conditions.iter().enumerate() {
+            for (second, second_condition) in &conditions[index + 1..] {
+                if first_condition == second_condition && Self::conflicting_effects(first, second) {
+                    issues.push(LintIssueModel {
+                        rule_ids: vec![first.id, second.id],
+                        ..LintIssueModel::new(
+                            LintIssueCode::ContradictoryRules,
+                            "Правила с одинаковыми условиями дают противоречащие выводы",
+                        )
+                    });
+                }
+            }
+        }
+
+        issues
+    }
+
+    // Правило недостижимо, если его условие не выполняется ни при каких ответах
+    fn is_reachable(&self, rule: &RuleWithClausesAndEffects, condition: &ConditionNode) -> bool {
+        let mut question_ids: Vec<i32> = condition
+            .clause_ids()
+            .into_iter()
+            .filter_map(|clause_id| rule.clauses.iter().find(|clause| clause.id == clause_id))
+            .map(|clause| clause.question_id)
+            .collect();
+        question_ids.sort();
+        question_ids.dedup();
+
+        let candidates: Vec<(i32, Vec<Option<Fact>>)> = question_ids
+            .into_iter()
+            .map(|question_id| {
+                let clauses: Vec<&ClauseModel> = rule
+                    .clauses
+                    .iter()
+                    .filter(|clause| clause.question_id == question_id)
+                    .collect();
+                (
+                    question_id,
+                    InferenceEngine::candidate_facts(self.question(question_id), &clauses),
+                )
+            })
+            .collect();
+
+        let total = candidates
+            .iter()
+            .try_fold(1usize, |total, (_, facts)| total.checked_mul(facts.len()));
+        let Some(total) = total.filter(|total| *total <= MAX_ASSIGNMENTS) else {
+            return true;
+        };
+
+        (0..total).any(|mut assignment| {
+            let mut facts: HashMap<i32, Fact> = HashMap::new();
+            for (question_id, question_facts) in &candidates {
+                if let Some(fact) = &question_facts[assignment % question_facts.len()] {
+                    facts.insert(*question_id, fact.clone());
+                }
+                assignment /= question_facts.len();
+            }
+            InferenceEngine::node_holds(rule, condition, &facts) == Some(true)
+        })
+    }
+
+    fn unreachable_rules(&self) -> Vec<LintIssueModel> {
+        self.rules
+            .iter()
+            .filter_map(|rule| {
+                let message = match InferenceEngine::rule_condition(rule) {
+                    None => "У правила нет условий, оно никогда не сработает",
+                    Some(condition) if !self.is_reachable(rule, &condition) => {
+                        "Условие правила не выполняется ни при каких ответах"
+                    }
+                    Some(_) => return None,
+                };
+                Some(LintIssueModel {
+                    rule_ids: vec![rule.id],
+                    ..LintIssueModel::new(LintIssueCode::UnreachableRule, message)
+                })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::{attribute, clause, concludes_value, object, question, rule};
+    use entity::sea_orm_active_enums::Operatorenum;
+
+    fn concluding(
+        id: i32,
+        clauses: Vec<ClauseModel>,
+        condition: Option<ConditionNode>,
+        attribute_value_id: i32,
+    ) -> RuleWithClausesAndEffects {
+        concludes_value(
+            RuleWithClausesAndEffects {
+                condition,
+                ..rule(id, clauses)
+            },
+            1,
+            attribute_value_id,
+        )
+    }
+
+    fn lint() -> Vec<LintIssueModel> {
+        let questions = vec![
+            question(1, "Вопрос 1", &[]),
+            question(2, "Вопрос 2", &[(21, "Ответ 21"), (22, "Ответ 22")]),
+            question(3, "Вопрос 3", &[]),
+        ];
+        let attributes = vec![attribute(
+            1,
+            "Атрибут",
+            &[
+                (10, "Значение 10"),
+                (11, "Значение 11"),
+                (12, "Значение 12"),
+            ],
+        )];
+        let objects = vec![object(1, &[(1, 10)]), object(2, &[])];
+        let both = |first: i32, second: i32| {
+            Some(ConditionNode::And(vec![
+                ConditionNode::Clause(first),
+                ConditionNode::Clause(second),
+            ]))
+        };
+        let rules = vec![
+            concluding(
+                1,
+                vec![clause(
+                    10,
+                    2,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "21",
+                )],
+                None,
+                10,
+            ),
+            // То же условие с другим id выводит другое значение того же атрибута
+            concluding(
+                2,
+                vec![clause(
+                    20,
+                    2,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "21",
+                )],
+                None,
+                11,
+            ),
+            concluding(
+                3,
+                vec![
+                    clause(30, 1, Operatorenum::Above, Valuetypeenum::Integer, "5"),
+                    clause(31, 1, Operatorenum::Below, Valuetypeenum::Integer, "3"),
+                ],
+                both(30, 31),
+                10,
+            ),
+            concluding(
+                4,
+                vec![
+                    clause(40, 1, Operatorenum::Above, Valuetypeenum::Integer, "5"),
+                    clause(41, 1, Operatorenum::Below, Valuetypeenum::Decimal, "5.5"),
+                ],
+                both(40, 41),
+                10,
+            ),
+            concluding(
+                5,
+                vec![clause(
+                    50,
+                    9,
+                    Operatorenum::Equal,
+                    Valuetypeenum::Answer,
+                    "99",
+                )],
+                None,
+                10,
+            ),
+            concluding(6, vec![], None, 10),
+            concluding(
+                7,
+                vec![
+                    clause(70, 2, Operatorenum::Equal, Valuetypeenum::Answer, "21"),
+                    clause(71, 2, Operatorenum::Equal, Valuetypeenum::Answer, "21"),
+                ],
+                Some(ConditionNode::And(vec![
+                    ConditionNode::Clause(70),
+                    ConditionNode::Not(Box::new(ConditionNode::Clause(71))),
+                ])),
+                10,
+            ),
+        ];
+
+        Linter::new(&rules, &questions, &objects, &attributes).lint()
+    }
+
+    fn issues_of(issues: &[LintIssueModel], code: LintIssueCode) -> Vec<&LintIssueModel> {
+        issues.iter().filter(|issue| issue.code == code).collect()
+    }
+
+    #[test]
+    fn reports_unused_and_empty_entities() {
+        let issues = lint();
+
+        let unused_questions: Vec<Option<i32>> = issues_of(&issues, LintIssueCode::UnusedQuestion)
+            .iter()
+            .map(|issue| issue.question_id)
+            .collect();
+        assert_eq!(unused_questions, vec![Some(3)]);
+
+        let unused_values: Vec<Option<i32>> =
+            issues_of(&issues, LintIssueCode::UnusedAttributeValue)
+                .iter()
+                .map(|issue| issue.attribute_value_id)
+                .collect();
+        assert_eq!(unused_values, vec![Some(12)]);
+
+        let empty_objects: Vec<Option<i32>> = issues_of(&issues, LintIssueCode::EmptyObject)
+            .iter()
+            .map(|issue| issue.object_id)
+            .collect();
+        assert_eq!(empty_objects, vec![Some(2)]);
+    }
+
+    #[test]
+    fn reports_foreign_references() {
+        let issues = lint();
+
+        let foreign_questions = issues_of(&issues, LintIssueCode::ForeignQuestion);
+        assert_eq!(foreign_questions.len(), 1);
+        assert_eq!(foreign_questions[0].clause_id, Some(50));
+
+        let foreign_answers = issues_of(&issues, LintIssueCode::ForeignAnswer);
+        assert_eq!(foreign_answers.len(), 1);
+        assert_eq!(foreign_answers[0].answer_id, Some(99));
+    }
+
+    #[test]
+    fn reports_contradictory_rules_regardless_of_clause_ids() {
+        let issues = lint();
+
+        let contradictory: Vec<Vec<i32>> = issues_of(&issues, LintIssueCode::ContradictoryRules)
+            .iter()
+            .map(|issue| issue.rule_ids.clone())
+            .collect();
+        assert_eq!(contradictory, vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn reports_rules_no_answers_can_fire() {
+        let issues = lint();
+
+        let unreachable: Vec<i32> = issues_of(&issues, LintIssueCode::UnreachableRule)
+            .iter()
+            .flat_map(|issue| issue.rule_ids.clone())
+            .collect();
+        // Правило 4 срабатывает на значениях между границами, например 5.25
+        assert_eq!(unreachable, vec![3, 5, 6, 7]);
+    }
+}
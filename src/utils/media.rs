@@ -0,0 +1,118 @@
+use crate::{constants::IMAGE_DIR, error::CustomErrors};
+use http::StatusCode;
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+};
+
+const IMAGE_URI_PREFIX: &str = "/images/";
+
+// Имя файла из копии приходит извне, поэтому оставляем только безопасные символы
+fn sanitize_file_name(file_name: &str) -> String {
+    let base_name = file_name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let sanitized: String = base_name
+        .chars()
+        .filter(|symbol| symbol.is_alphanumeric() || ['.', '_', '-'].contains(symbol))
+        .collect();
+    match sanitized.trim_start_matches('.') {
+        "" => "image".to_string(),
+        name => name.chars().take(100).collect(),
+    }
+}
+
+// Имя файла в IMAGE_DIR из image_uri, без выхода за пределы каталога
+fn image_file_name(image_uri: &str) -> Option<&str> {
+    image_uri
+        .strip_prefix(IMAGE_URI_PREFIX)
+        .filter(|file_name| !file_name.is_empty())
+        .filter(|file_name| !file_name.starts_with('.') && !file_name.contains(['/', '\\']))
+}
+
+// Файл картинки по image_uri, None если uri пустой или файла уже нет
+pub async fn read_image(image_uri: &str) -> Option<(String, Vec<u8>)> {
+    let file_name = image_file_name(image_uri)?;
+    let content = fs::read(format!("{IMAGE_DIR}/{}", file_name)).await.ok()?;
+    // Отметку времени из имени не сохраняем, при записи будет новая
+    let original_name = file_name
+        .split_once('_')
+        .filter(|(timestamp, _)| timestamp.chars().all(|symbol| symbol.is_ascii_digit()))
+        .map_or(file_name, |(_, name)| name);
+    Some((original_name.to_string(), content))
+}
+
+// Новый image_uri для картинки: отметка времени и очищенное исходное имя
+pub fn fresh_image_uri(file_name: &str) -> String {
+    format!(
+        "{IMAGE_URI_PREFIX}{}_{}",
+        chrono::Utc::now().timestamp_millis(),
+        sanitize_file_name(file_name)
+    )
+}
+
+pub async fn write_image(image_uri: &str, content: &[u8]) -> Result<(), CustomErrors> {
+    let error = || CustomErrors::StringError {
+        status: StatusCode::INTERNAL_SERVER_ERROR,
+        error: "Невозможно сохранить лого".to_string(),
+    };
+    let file_name = image_file_name(image_uri).ok_or_else(error)?;
+
+    let _ = fs::create_dir_all(IMAGE_DIR).await;
+    let mut file = File::create(format!("{IMAGE_DIR}/{}", file_name))
+        .await
+        .map_err(|_| error())?;
+    file.write_all(content).await.map_err(|_| error())
+}
+
+pub async fn remove_image(image_uri: &str) {
+    if let Some(file_name) = image_file_name(image_uri) {
+        let _ = fs::remove_file(format!("{IMAGE_DIR}/{}", file_name)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized_names_stay_inside_image_dir() {
+        let cases = [
+            ("../../etc/passwd", "passwd"),
+            ("..\\..\\windows\\system.ini", "system.ini"),
+            ("/images/../.env", "env"),
+            ("..%2f..%2fsecret.png", "2f..2fsecret.png"),
+            ("...", "image"),
+            ("", "image"),
+            ("dir/", "image"),
+            ("фото кота.png", "фотокота.png"),
+        ];
+
+        for (file_name, expected) in cases {
+            assert_eq!(sanitize_file_name(file_name), expected, "{}", file_name);
+        }
+        assert_eq!(sanitize_file_name(&"a".repeat(300)).len(), 100);
+    }
+
+    #[test]
+    fn image_uri_points_only_at_files_in_image_dir() {
+        assert_eq!(image_file_name("/images/1_logo.png"), Some("1_logo.png"));
+
+        for image_uri in [
+            "/images/",
+            "/images/../config.toml",
+            "/images/.env",
+            "/images/a/b.png",
+            "/images/a\\b.png",
+            "/static/logo.png",
+        ] {
+            assert_eq!(image_file_name(image_uri), None, "{}", image_uri);
+        }
+    }
+
+    #[test]
+    fn fresh_uri_from_hostile_name_is_readable_back() {
+        let image_uri = fresh_image_uri("../../etc/passwd");
+
+        let file_name = image_file_name(&image_uri).unwrap();
+        assert!(file_name.ends_with("_passwd"), "{}", file_name);
+    }
+}
@@ -1,5 +1,18 @@
 pub mod auth;
+pub mod backup_format;
+pub mod clock;
 pub mod copy;
 pub mod crypto;
+pub mod decision_tree;
+#[cfg(test)]
+pub mod fixtures;
 pub mod generate_random_string;
+pub mod induction;
+pub mod inference;
+pub mod interchange;
+pub mod lint;
+pub mod media;
+pub mod policy;
+pub mod rule_dsl;
 pub mod topological_sort;
+pub mod transfer;
@@ -0,0 +1,231 @@
+use std::collections::HashSet;
+
+use crate::error::CustomErrors;
+use entity::{
+    answers::{Column as AnswerColumn, Entity as AnswerEntity},
+    attributes::{Column as AttributeColumn, Entity as AttributeEntity},
+    attributesvalues::{Column as AttributeValueColumn, Entity as AttributeValueEntity},
+    clauses::{Column as ClauseColumn, Entity as ClauseEntity},
+    object_attribute_attributevalue::{
+        Column as ObjectAttributeAttributeValueColumn,
+        Entity as ObjectAttributeAttributeValueEntity,
+    },
+    objects::{Column as ObjectColumn, Entity as ObjectEntity},
+    questions::{Column as QuestionColumn, Entity as QuestionEntity},
+    rule_attribute_attributevalue::{
+        Column as RuleAttributeAttributeValueColumn, Entity as RuleAttributeAttributeValueEntity,
+    },
+    rule_question_answer::{
+        Column as RuleQuestionAnswerColumn, Entity as RuleQuestionAnswerEntity,
+    },
+    rules::{Column as RuleColumn, Entity as RuleEntity},
+    systems::{Column as SystemColumn, Entity as SystemEntity},
+    test_cases::{Column as TestCaseColumn, Entity as TestCaseEntity},
+};
+use http::StatusCode;
+use sea_orm::{ColumnTrait, ConnectionTrait, EntityTrait, QueryFilter, QuerySelect};
+
+// Сущность, права на которую определяются владельцем ее системы
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Resource {
+    System(i32),
+    Question(i32),
+    Answer(i32),
+    Attribute(i32),
+    AttributeValue(i32),
+    Object(i32),
+    Rule(i32),
+    Clause(i32),
+    ObjectAttributeAttributeValue(i32),
+    RuleAttributeAttributeValue(i32),
+    RuleQuestionAnswer(i32),
+    TestCase(i32),
+}
+
+pub fn forbidden() -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::FORBIDDEN,
+        error: "Действие доступно только владельцу системы".to_string(),
+    }
+}
+
+fn db_error(err: sea_orm::DbErr) -> CustomErrors {
+    CustomErrors::SeaORMError {
+        error: err,
+        message: None,
+    }
+}
+
+// id родителей для набора id одной таблицы. Если какой-то записи нет - 404
+macro_rules! parent_ids {
+    ($db:expr, $entity:ident, $id:expr, $parent:expr, $ids:expr) => {{
+        let ids: HashSet<i32> = $ids.iter().copied().collect();
+        let parents: Vec<(i32, i32)> = if ids.is_empty() {
+            Vec::new()
+        } else {
+            $entity::find()
+                .select_only()
+                .column($id)
+                .column($parent)
+                .filter($id.is_in(ids.iter().copied()))
+                .into_tuple()
+                .all($db)
+                .await
+                .map_err(db_error)?
+        };
+        if parents.len() != ids.len() {
+            return Err(not_found());
+        }
+        parents.into_iter().map(|(_, parent)| parent)
+    }};
+}
+
+fn not_found() -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::NOT_FOUND,
+        error: "Запись не найдена".to_string(),
+    }
+}
+
+// Владельцы систем, к которым относятся сущности
+pub async fn resolve_owners<C>(db: &C, resources: &[Resource]) -> Result<HashSet<i32>, CustomErrors>
+where
+    C: ConnectionTrait,
+{
+    let mut system_ids = Vec::new();
+    let mut question_ids = Vec::new();
+    let mut answer_ids = Vec::new();
+    let mut attribute_ids = Vec::new();
+    let mut attribute_value_ids = Vec::new();
+    let mut object_ids = Vec::new();
+    let mut rule_ids = Vec::new();
+    let mut clause_ids = Vec::new();
+    let mut object_attribute_attributevalue_ids = Vec::new();
+    let mut rule_attribute_attributevalue_ids = Vec::new();
+    let mut rule_question_answer_ids = Vec::new();
+    let mut test_case_ids = Vec::new();
+
+    for resource in resources {
+        match *resource {
+            Resource::System(id) => system_ids.push(id),
+            Resource::Question(id) => question_ids.push(id),
+            Resource::Answer(id) => answer_ids.push(id),
+            Resource::Attribute(id) => attribute_ids.push(id),
+            Resource::AttributeValue(id) => attribute_value_ids.push(id),
+            Resource::Object(id) => object_ids.push(id),
+            Resource::Rule(id) => rule_ids.push(id),
+            Resource::Clause(id) => clause_ids.push(id),
+            Resource::ObjectAttributeAttributeValue(id) => {
+                object_attribute_attributevalue_ids.push(id)
+            }
+            Resource::RuleAttributeAttributeValue(id) => rule_attribute_attributevalue_ids.push(id),
+            Resource::RuleQuestionAnswer(id) => rule_question_answer_ids.push(id),
+            Resource::TestCase(id) => test_case_ids.push(id),
+        }
+    }
+
+    // Связи и дочерние записи сводятся к родителям, родители - к системам
+    rule_ids.extend(parent_ids!(
+        db,
+        ClauseEntity,
+        ClauseColumn::Id,
+        ClauseColumn::RuleId,
+        clause_ids
+    ));
+    rule_ids.extend(parent_ids!(
+        db,
+        RuleAttributeAttributeValueEntity,
+        RuleAttributeAttributeValueColumn::Id,
+        RuleAttributeAttributeValueColumn::RuleId,
+        rule_attribute_attributevalue_ids
+    ));
+    rule_ids.extend(parent_ids!(
+        db,
+        RuleQuestionAnswerEntity,
+        RuleQuestionAnswerColumn::Id,
+        RuleQuestionAnswerColumn::RuleId,
+        rule_question_answer_ids
+    ));
+    object_ids.extend(parent_ids!(
+        db,
+        ObjectAttributeAttributeValueEntity,
+        ObjectAttributeAttributeValueColumn::Id,
+        ObjectAttributeAttributeValueColumn::ObjectId,
+        object_attribute_attributevalue_ids
+    ));
+    question_ids.extend(parent_ids!(
+        db,
+        AnswerEntity,
+        AnswerColumn::Id,
+        AnswerColumn::QuestionId,
+        answer_ids
+    ));
+    attribute_ids.extend(parent_ids!(
+        db,
+        AttributeValueEntity,
+        AttributeValueColumn::Id,
+        AttributeValueColumn::AttributeId,
+        attribute_value_ids
+    ));
+
+    system_ids.extend(parent_ids!(
+        db,
+        RuleEntity,
+        RuleColumn::Id,
+        RuleColumn::SystemId,
+        rule_ids
+    ));
+    system_ids.extend(parent_ids!(
+        db,
+        ObjectEntity,
+        ObjectColumn::Id,
+        ObjectColumn::SystemId,
+        object_ids
+    ));
+    system_ids.extend(parent_ids!(
+        db,
+        QuestionEntity,
+        QuestionColumn::Id,
+        QuestionColumn::SystemId,
+        question_ids
+    ));
+    system_ids.extend(parent_ids!(
+        db,
+        AttributeEntity,
+        AttributeColumn::Id,
+        AttributeColumn::SystemId,
+        attribute_ids
+    ));
+    system_ids.extend(parent_ids!(
+        db,
+        TestCaseEntity,
+        TestCaseColumn::Id,
+        TestCaseColumn::SystemId,
+        test_case_ids
+    ));
+
+    Ok(parent_ids!(
+        db,
+        SystemEntity,
+        SystemColumn::Id,
+        SystemColumn::UserId,
+        system_ids
+    )
+    .collect())
+}
+
+// Изменять систему и все, что к ней относится, может только ее владелец
+pub async fn authorize_owner<C>(
+    db: &C,
+    user_id: i32,
+    resources: &[Resource],
+) -> Result<(), CustomErrors>
+where
+    C: ConnectionTrait,
+{
+    let owners = resolve_owners(db, resources).await?;
+    if owners.iter().any(|owner_id| *owner_id != user_id) {
+        return Err(forbidden());
+    }
+    Ok(())
+}
@@ -0,0 +1,887 @@
+use entity::{
+    attributes::AttributeWithAttributeValuesModel,
+    clauses::{ClauseModel, NewClauseWithoutRule},
+    questions::QuestionWithAnswersModel,
+    rule_attribute_attributevalue::NewRuleAttributeAttributeValueWithoutRuleModel,
+    rule_question_answer::NewRuleQuestionAnswerWithoutRuleModel,
+    rules::{
+        ConditionNode, NewConditionNode, NewRuleWithClausesAndEffects, RuleWithClausesAndEffects,
+    },
+    sea_orm_active_enums::{Operatorenum, Valuetypeenum},
+};
+use sea_orm::DbErr;
+
+use crate::utils::inference::InferenceEngine;
+
+// Текстовая запись правил:
+//
+//   # комментарий до конца строки
+//   IF "Температура" ABOVE 38 AND NOT ("Кашель?" = "нет" OR "Насморк?" = "да")
+//   THEN "Диагноз" = "Грипп" CF 0.8
+//
+// Слева от оператора - текст вопроса, справа - текст ответа для вопросов с вариантами,
+// число, TRUE/FALSE или строка для свободного ввода. После THEN - пары "атрибут" = "значение"
+// либо "вопрос" = "ответ", соединенные AND. Ключевые слова не зависят от регистра.
+// Правило без условий записывается как IF FALSE и никогда не срабатывает.
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Text(String),
+    Number(String),
+    Word(String),
+    Symbol(&'static str),
+}
+
+struct Lexeme {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+const SYMBOLS: [&str; 9] = ["!=", "<>", ">=", "<=", "=", ">", "<", "(", ")"];
+
+fn syntax_error(line: usize, column: usize, message: &str) -> DbErr {
+    DbErr::Custom(format!("Строка {}, позиция {}: {}", line, column, message))
+}
+
+fn tokenize(source: &str) -> Result<Vec<Lexeme>, DbErr> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut lexemes = Vec::new();
+    let (mut index, mut line, mut column) = (0, 1, 1);
+
+    while index < chars.len() {
+        let current = chars[index];
+        let (start_line, start_column) = (line, column);
+
+        if current == '\n' {
+            index += 1;
+            line += 1;
+            column = 1;
+            continue;
+        }
+        if current.is_whitespace() {
+            index += 1;
+            column += 1;
+            continue;
+        }
+        if current == '#' {
+            while index < chars.len() && chars[index] != '\n' {
+                index += 1;
+            }
+            continue;
+        }
+
+        let token = if current == '"' {
+            let mut text = String::new();
+            index += 1;
+            column += 1;
+            loop {
+                match chars.get(index) {
+                    None | Some('\n') => {
+                        return Err(syntax_error(start_line, start_column, "незакрытая кавычка"))
+                    }
+                    Some('"') => break,
+                    Some('\\') if index + 1 < chars.len() => {
+                        text.push(chars[index + 1]);
+                        index += 2;
+                        column += 2;
+                    }
+                    Some(char) => {
+                        text.push(*char);
+                        index += 1;
+                        column += 1;
+                    }
+                }
+            }
+            index += 1;
+            column += 1;
+            Token::Text(text)
+        } else if current.is_ascii_digit()
+            || (current == '-' && chars.get(index + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = index;
+            index += 1;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.') {
+                index += 1;
+            }
+            column += index - start;
+            Token::Number(chars[start..index].iter().collect())
+        } else if current.is_alphabetic() || current == '_' {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            column += index - start;
+            Token::Word(
+                chars[start..index]
+                    .iter()
+                    .collect::<String>()
+                    .to_uppercase(),
+            )
+        } else {
+            let rest: String = chars[index..chars.len().min(index + 2)].iter().collect();
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or(syntax_error(
+                    line,
+                    column,
+                    &format!("неожиданный символ '{}'", current),
+                ))?;
+            index += symbol.chars().count();
+            column += symbol.chars().count();
+            Token::Symbol(symbol)
+        };
+
+        lexemes.push(Lexeme {
+            token,
+            line: start_line,
+            column: start_column,
+        });
+    }
+
+    Ok(lexemes)
+}
+
+pub struct RuleDslParser<'a> {
+    system_id: i32,
+    questions: &'a [QuestionWithAnswersModel],
+    attributes: &'a [AttributeWithAttributeValuesModel],
+    lexemes: Vec<Lexeme>,
+    position: usize,
+}
+
+impl<'a> RuleDslParser<'a> {
+    pub fn new(
+        system_id: i32,
+        questions: &'a [QuestionWithAnswersModel],
+        attributes: &'a [AttributeWithAttributeValuesModel],
+    ) -> Self {
+        RuleDslParser {
+            system_id,
+            questions,
+            attributes,
+            lexemes: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn parse(mut self, source: &str) -> Result<Vec<NewRuleWithClausesAndEffects>, DbErr> {
+        self.lexemes = tokenize(source)?;
+        self.position = 0;
+
+        let mut rules = Vec::new();
+        while self.peek().is_some() {
+            rules.push(self.rule()?);
+        }
+        Ok(rules)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.lexemes.get(self.position).map(|lexeme| &lexeme.token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.position += 1;
+        token
+    }
+
+    fn error(&self, message: &str) -> DbErr {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, position: usize, message: &str) -> DbErr {
+        match self.lexemes.get(position) {
+            Some(lexeme) => syntax_error(lexeme.line, lexeme.column, message),
+            None => {
+                let (line, column) = self
+                    .lexemes
+                    .last()
+                    .map(|lexeme| (lexeme.line, lexeme.column))
+                    .unwrap_or((1, 1));
+                syntax_error(
+                    line,
+                    column,
+                    &format!("{} (неожиданный конец текста)", message),
+                )
+            }
+        }
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        self.peek() == Some(&Token::Word(word.to_string()))
+    }
+
+    fn expect_word(&mut self, word: &str) -> Result<(), DbErr> {
+        if !self.is_word(word) {
+            return Err(self.error(&format!("ожидалось {}", word)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn expect_text(&mut self, what: &str) -> Result<String, DbErr> {
+        match self.peek() {
+            Some(Token::Text(text)) => {
+                let text = text.clone();
+                self.position += 1;
+                Ok(text)
+            }
+            _ => Err(self.error(&format!("ожидался {} в кавычках", what))),
+        }
+    }
+
+    fn rule(&mut self) -> Result<NewRuleWithClausesAndEffects, DbErr> {
+        self.expect_word("IF")?;
+        let mut clauses = Vec::new();
+        let condition = if self.is_word("FALSE") {
+            self.position += 1;
+            None
+        } else {
+            Some(self.or_node(&mut clauses)?)
+        };
+
+        self.expect_word("THEN")?;
+        let mut rule_question_answer_ids = Vec::new();
+        let mut rule_attribute_attributevalue_ids = Vec::new();
+        // Правило без выводов допустимо, тогда после THEN сразу идет CF или следующее правило
+        while matches!(self.peek(), Some(Token::Text(_))) {
+            self.effect(
+                &mut rule_question_answer_ids,
+                &mut rule_attribute_attributevalue_ids,
+            )?;
+            if !self.is_word("AND") {
+                break;
+            }
+            self.position += 1;
+        }
+        if !rule_question_answer_ids.is_empty() && !rule_attribute_attributevalue_ids.is_empty() {
+            return Err(self.error(
+                "правило не может одновременно выводить значения атрибутов и ответы на вопросы",
+            ));
+        }
+
+        let mut certainty_factor = 1.0;
+        if self.is_word("CF") {
+            self.position += 1;
+            certainty_factor = match self.next() {
+                Some(Token::Number(number)) => number.parse::<f64>().ok(),
+                _ => None,
+            }
+            .filter(|value| (-1.0..=1.0).contains(value))
+            .ok_or_else(|| {
+                self.position -= 1;
+                self.error("коэффициент уверенности должен быть числом от -1 до 1")
+            })?;
+        }
+
+        Ok(NewRuleWithClausesAndEffects {
+            system_id: self.system_id,
+            attribute_rule: !rule_attribute_attributevalue_ids.is_empty(),
+            certainty_factor,
+            condition,
+            clauses,
+            rule_question_answer_ids,
+            rule_attribute_attributevalue_ids,
+        })
+    }
+
+    fn or_node(
+        &mut self,
+        clauses: &mut Vec<NewClauseWithoutRule>,
+    ) -> Result<NewConditionNode, DbErr> {
+        let mut nodes = vec![self.and_node(clauses)?];
+        while self.is_word("OR") {
+            self.position += 1;
+            nodes.push(self.and_node(clauses)?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            NewConditionNode::Or(nodes)
+        })
+    }
+
+    fn and_node(
+        &mut self,
+        clauses: &mut Vec<NewClauseWithoutRule>,
+    ) -> Result<NewConditionNode, DbErr> {
+        let mut nodes = vec![self.factor(clauses)?];
+        while self.is_word("AND") {
+            self.position += 1;
+            nodes.push(self.factor(clauses)?);
+        }
+        Ok(if nodes.len() == 1 {
+            nodes.remove(0)
+        } else {
+            NewConditionNode::And(nodes)
+        })
+    }
+
+    fn factor(
+        &mut self,
+        clauses: &mut Vec<NewClauseWithoutRule>,
+    ) -> Result<NewConditionNode, DbErr> {
+        if self.is_word("NOT") {
+            self.position += 1;
+            return Ok(NewConditionNode::Not(Box::new(self.factor(clauses)?)));
+        }
+        if self.peek() == Some(&Token::Symbol("(")) {
+            self.position += 1;
+            let node = self.or_node(clauses)?;
+            if self.next() != Some(Token::Symbol(")")) {
+                self.position -= 1;
+                return Err(self.error("ожидалась закрывающая скобка"));
+            }
+            return Ok(node);
+        }
+
+        clauses.push(self.clause()?);
+        Ok(NewConditionNode::Clause(clauses.len() - 1))
+    }
+
+    fn operator(&mut self) -> Result<Operatorenum, DbErr> {
+        let operator = match self.peek() {
+            Some(Token::Symbol("=")) => Operatorenum::Equal,
+            Some(Token::Symbol("!=" | "<>")) => Operatorenum::NotEqual,
+            Some(Token::Symbol(">")) => Operatorenum::Above,
+            Some(Token::Symbol("<")) => Operatorenum::Below,
+            Some(Token::Symbol(">=")) => Operatorenum::NoLessThan,
+            Some(Token::Symbol("<=")) => Operatorenum::NoMoreThan,
+            Some(Token::Word(word)) => match word.as_str() {
+                "EQUAL" => Operatorenum::Equal,
+                "NOT_EQUAL" => Operatorenum::NotEqual,
+                "ABOVE" => Operatorenum::Above,
+                "BELOW" => Operatorenum::Below,
+                "NO_LESS_THAN" => Operatorenum::NoLessThan,
+                "NO_MORE_THAN" => Operatorenum::NoMoreThan,
+                _ => return Err(self.error("ожидался оператор сравнения")),
+            },
+            _ => return Err(self.error("ожидался оператор сравнения")),
+        };
+        self.position += 1;
+        Ok(operator)
+    }
+
+    fn question(&self, body: &str) -> Result<&'a QuestionWithAnswersModel, String> {
+        let mut found = self
+            .questions
+            .iter()
+            .filter(|question| same_name(&question.body, body));
+        match (found.next(), found.next()) {
+            (Some(question), None) => Ok(question),
+            (Some(_), Some(_)) => Err(format!("несколько вопросов \"{}\"", body)),
+            (None, _) => Err(format!("вопрос \"{}\" не найден", body)),
+        }
+    }
+
+    fn answer(&self, question: &QuestionWithAnswersModel, body: &str) -> Result<i32, DbErr> {
+        question
+            .answers
+            .iter()
+            .find(|answer| same_name(&answer.body, body))
+            .map(|answer| answer.id)
+            .ok_or(self.error(&format!(
+                "у вопроса \"{}\" нет ответа \"{}\"",
+                question.body, body
+            )))
+    }
+
+    fn clause(&mut self) -> Result<NewClauseWithoutRule, DbErr> {
+        let body = self.expect_text("текст вопроса")?;
+        let question = self
+            .question(&body)
+            .map_err(|message| self.error_at(self.position - 1, &message))?;
+        let operator = self.operator()?;
+
+        let (value_type, compared_value) = match (question.with_chooses, self.peek().cloned()) {
+            (true, Some(Token::Text(text))) => {
+                if !matches!(operator, Operatorenum::Equal | Operatorenum::NotEqual) {
+                    return Err(self.error("ответы с вариантами сравниваются только через = и !="));
+                }
+                (
+                    Valuetypeenum::Answer,
+                    self.answer(question, &text)?.to_string(),
+                )
+            }
+            (true, _) => return Err(self.error("ожидался текст ответа в кавычках")),
+            (false, Some(Token::Number(number))) => {
+                if number.parse::<f64>().is_err() {
+                    return Err(self.error(&format!("некорректное число {}", number)));
+                }
+                let value_type = if number.contains('.') {
+                    Valuetypeenum::Decimal
+                } else {
+                    Valuetypeenum::Integer
+                };
+                (value_type, number)
+            }
+            (false, Some(Token::Word(word))) if word == "TRUE" || word == "FALSE" => {
+                (Valuetypeenum::Boolean, word.to_lowercase())
+            }
+            (false, Some(Token::Text(text))) => (Valuetypeenum::Text, text),
+            (false, _) => return Err(self.error("ожидалось значение для сравнения")),
+        };
+        self.position += 1;
+
+        Ok(NewClauseWithoutRule {
+            compared_value,
+            logical_group: "1".to_string(),
+            operator,
+            question_id: question.id,
+            value_type,
+        })
+    }
+
+    // Имя слева ищем сначала среди атрибутов, затем среди вопросов
+    fn effect(
+        &mut self,
+        answers: &mut Vec<NewRuleQuestionAnswerWithoutRuleModel>,
+        attribute_values: &mut Vec<NewRuleAttributeAttributeValueWithoutRuleModel>,
+    ) -> Result<(), DbErr> {
+        let name_position = self.position;
+        let name = self.expect_text("атрибут или вопрос")?;
+        if self.next() != Some(Token::Symbol("=")) {
+            self.position -= 1;
+            return Err(self.error("ожидалось ="));
+        }
+        let value = self.expect_text("значение")?;
+        self.position -= 1;
+
+        if let Some(attribute) = self
+            .attributes
+            .iter()
+            .find(|attribute| same_name(&attribute.name, &name))
+        {
+            let attribute_value = attribute
+                .values
+                .iter()
+                .find(|attribute_value| same_name(&attribute_value.value, &value))
+                .ok_or(self.error(&format!(
+                    "у атрибута \"{}\" нет значения \"{}\"",
+                    attribute.name, value
+                )))?;
+            attribute_values.push(NewRuleAttributeAttributeValueWithoutRuleModel {
+                attribute_value_id: attribute_value.id,
+                attribute_id: attribute.id,
+            });
+        } else {
+            let question = self.question(&name).map_err(|_| {
+                self.error_at(
+                    name_position,
+                    &format!("атрибут или вопрос \"{}\" не найден", name),
+                )
+            })?;
+            answers.push(NewRuleQuestionAnswerWithoutRuleModel {
+                answer_id: self.answer(question, &value)?,
+                question_id: question.id,
+            });
+        }
+        self.position += 1;
+
+        Ok(())
+    }
+}
+
+fn same_name(first: &str, second: &str) -> bool {
+    first.trim().to_lowercase() == second.trim().to_lowercase()
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub struct RuleDslPrinter<'a> {
+    questions: &'a [QuestionWithAnswersModel],
+    attributes: &'a [AttributeWithAttributeValuesModel],
+}
+
+impl<'a> RuleDslPrinter<'a> {
+    pub fn new(
+        questions: &'a [QuestionWithAnswersModel],
+        attributes: &'a [AttributeWithAttributeValuesModel],
+    ) -> Self {
+        RuleDslPrinter {
+            questions,
+            attributes,
+        }
+    }
+
+    pub fn print(&self, rules: &[RuleWithClausesAndEffects]) -> String {
+        rules
+            .iter()
+            .map(|rule| self.rule(rule))
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    fn question(&self, question_id: i32) -> Option<&'a QuestionWithAnswersModel> {
+        self.questions
+            .iter()
+            .find(|question| question.id == question_id)
+    }
+
+    // Ссылки на чужие вопросы и ответы печатаем как id, разбор такого текста вернет ошибку
+    fn answer_body(&self, question_id: i32, answer_id: &str) -> String {
+        self.question(question_id)
+            .and_then(|question| {
+                question
+                    .answers
+                    .iter()
+                    .find(|answer| answer.id.to_string() == answer_id.trim())
+            })
+            .map(|answer| answer.body.clone())
+            .unwrap_or(answer_id.trim().to_string())
+    }
+
+    fn rule(&self, rule: &RuleWithClausesAndEffects) -> String {
+        let condition = InferenceEngine::rule_condition(rule)
+            .map(|condition| self.node(rule, &condition, 0))
+            .unwrap_or("FALSE".to_string());
+
+        let effects = rule
+            .rule_attribute_attributevalue_ids
+            .iter()
+            .map(|effect| {
+                let attribute = self
+                    .attributes
+                    .iter()
+                    .find(|attribute| attribute.id == effect.attribute_id);
+                let value = attribute
+                    .and_then(|attribute| {
+                        attribute
+                            .values
+                            .iter()
+                            .find(|value| value.id == effect.attribute_value_id)
+                    })
+                    .map(|value| value.value.clone())
+                    .unwrap_or(effect.attribute_value_id.to_string());
+                format!(
+                    "{} = {}",
+                    quote(
+                        &attribute
+                            .map(|attribute| attribute.name.clone())
+                            .unwrap_or(effect.attribute_id.to_string())
+                    ),
+                    quote(&value)
+                )
+            })
+            .chain(rule.rule_question_answer_ids.iter().map(|effect| {
+                format!(
+                    "{} = {}",
+                    quote(&self.question_body(effect.question_id)),
+                    quote(&self.answer_body(effect.question_id, &effect.answer_id.to_string()))
+                )
+            }))
+            .collect::<Vec<String>>()
+            .join(" AND ");
+
+        let certainty_factor = if rule.certainty_factor == 1.0 {
+            String::new()
+        } else {
+            format!(" CF {}", rule.certainty_factor)
+        };
+
+        format!(
+            "IF {}\nTHEN{}{}\n",
+            condition,
+            if effects.is_empty() {
+                String::new()
+            } else {
+                format!(" {}", effects)
+            },
+            certainty_factor
+        )
+    }
+
+    fn question_body(&self, question_id: i32) -> String {
+        self.question(question_id)
+            .map(|question| question.body.clone())
+            .unwrap_or(question_id.to_string())
+    }
+
+    // precedence: 0 - верхний уровень, 1 - внутри OR, 2 - внутри AND или NOT
+    fn node(
+        &self,
+        rule: &RuleWithClausesAndEffects,
+        node: &ConditionNode,
+        precedence: u8,
+    ) -> String {
+        let join = |nodes: &[ConditionNode], separator: &str, own: u8| {
+            let text = nodes
+                .iter()
+                .map(|node| self.node(rule, node, own))
+                .collect::<Vec<String>>()
+                .join(separator);
+            if precedence > own && nodes.len() > 1 {
+                format!("({})", text)
+            } else {
+                text
+            }
+        };
+
+        match node {
+            ConditionNode::Or(nodes) => join(nodes, " OR ", 1),
+            ConditionNode::And(nodes) => join(nodes, " AND ", 2),
+            ConditionNode::Not(node) => format!("NOT {}", self.node(rule, node, 3)),
+            ConditionNode::Clause(clause_id) => rule
+                .clauses
+                .iter()
+                .find(|clause| clause.id == *clause_id)
+                .map(|clause| self.clause(clause))
+                .unwrap_or_default(),
+        }
+    }
+
+    fn clause(&self, clause: &ClauseModel) -> String {
+        let operator = match clause.operator {
+            Operatorenum::Equal => "=",
+            Operatorenum::NotEqual => "!=",
+            Operatorenum::Above => "ABOVE",
+            Operatorenum::Below => "BELOW",
+            Operatorenum::NoLessThan => "NO_LESS_THAN",
+            Operatorenum::NoMoreThan => "NO_MORE_THAN",
+        };
+        let raw = clause.compared_value.trim();
+        let number = raw.replace(',', ".").parse::<f64>().ok();
+
+        let value = match clause.value_type {
+            Valuetypeenum::Answer => quote(&self.answer_body(clause.question_id, raw)),
+            Valuetypeenum::Integer => number
+                .filter(|number| number.fract() == 0.0)
+                .map(|number| format!("{}", number))
+                .unwrap_or(quote(raw)),
+            // Display печатает без экспоненты, точка нужна, чтобы при разборе тип остался дробным
+            Valuetypeenum::Decimal => number
+                .filter(|number| number.is_finite())
+                .map(|number| match number.to_string() {
+                    text if text.contains('.') => text,
+                    text => format!("{}.0", text),
+                })
+                .unwrap_or(quote(raw)),
+            Valuetypeenum::Boolean => match raw.to_lowercase().as_str() {
+                "true" | "yes" | "1" | "да" => "TRUE".to_string(),
+                "false" | "no" | "0" | "нет" => "FALSE".to_string(),
+                _ => quote(raw),
+            },
+            Valuetypeenum::Text => quote(raw),
+        };
+
+        format!(
+            "{} {} {}",
+            quote(&self.question_body(clause.question_id)),
+            operator,
+            value
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::fixtures::{
+        attribute, clause, concludes_answer, concludes_value, question, rule,
+    };
+    use entity::{
+        rule_attribute_attributevalue::RuleAttributeAttributeValueModel,
+        rule_question_answer::RuleQuestionAnswerModel,
+    };
+
+    fn questions() -> Vec<QuestionWithAnswersModel> {
+        vec![
+            question(1, "Температура", &[]),
+            question(2, "Кашель?", &[(21, "да"), (22, "нет")]),
+        ]
+    }
+
+    fn attributes() -> Vec<AttributeWithAttributeValuesModel> {
+        vec![attribute(5, "Диагноз", &[(51, "Грипп")])]
+    }
+
+    fn rules() -> Vec<RuleWithClausesAndEffects> {
+        vec![
+            concludes_value(
+                RuleWithClausesAndEffects {
+                    certainty_factor: 0.8,
+                    condition: Some(ConditionNode::And(vec![
+                        ConditionNode::Clause(100),
+                        ConditionNode::Not(Box::new(ConditionNode::Or(vec![
+                            ConditionNode::Clause(101),
+                            ConditionNode::Clause(102),
+                        ]))),
+                    ])),
+                    ..rule(
+                        1,
+                        vec![
+                            clause(
+                                100,
+                                1,
+                                Operatorenum::Above,
+                                Valuetypeenum::Decimal,
+                                "0.0000001",
+                            ),
+                            clause(101, 2, Operatorenum::Equal, Valuetypeenum::Answer, "22"),
+                            clause(
+                                102,
+                                1,
+                                Operatorenum::NoMoreThan,
+                                Valuetypeenum::Integer,
+                                "40",
+                            ),
+                        ],
+                    )
+                },
+                5,
+                51,
+            ),
+            concludes_answer(
+                RuleWithClausesAndEffects {
+                    condition: Some(ConditionNode::Or(vec![
+                        ConditionNode::Clause(200),
+                        ConditionNode::Clause(201),
+                    ])),
+                    ..rule(
+                        2,
+                        vec![
+                            clause(200, 1, Operatorenum::Equal, Valuetypeenum::Boolean, "true"),
+                            clause(
+                                201,
+                                1,
+                                Operatorenum::NotEqual,
+                                Valuetypeenum::Text,
+                                "выс\"окая",
+                            ),
+                        ],
+                    )
+                },
+                2,
+                21,
+            ),
+            concludes_answer(
+                RuleWithClausesAndEffects {
+                    certainty_factor: -0.5,
+                    ..rule(3, vec![])
+                },
+                2,
+                22,
+            ),
+        ]
+    }
+
+    // Сохраняет разобранные правила так же, как create_rule: индексы условий становятся id
+    fn created(rules: Vec<NewRuleWithClausesAndEffects>) -> Vec<RuleWithClausesAndEffects> {
+        rules
+            .into_iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let rule_id = index as i32 + 1;
+                RuleWithClausesAndEffects {
+                    id: rule_id,
+                    system_id: rule.system_id,
+                    attribute_rule: rule.attribute_rule,
+                    certainty_factor: rule.certainty_factor,
+                    condition: rule.condition.map(|condition| {
+                        let clause_ids: Vec<i32> = (0..rule.clauses.len() as i32)
+                            .map(|clause_index| rule_id * 100 + clause_index)
+                            .collect();
+                        condition.resolve(&clause_ids).unwrap()
+                    }),
+                    clauses: rule
+                        .clauses
+                        .into_iter()
+                        .enumerate()
+                        .map(|(clause_index, clause)| ClauseModel {
+                            id: rule_id * 100 + clause_index as i32,
+                            rule_id,
+                            compared_value: clause.compared_value,
+                            logical_group: clause.logical_group,
+                            operator: clause.operator,
+                            question_id: clause.question_id,
+                            value_type: clause.value_type,
+                        })
+                        .collect(),
+                    rule_question_answer_ids: rule
+                        .rule_question_answer_ids
+                        .into_iter()
+                        .map(|effect| RuleQuestionAnswerModel {
+                            id: 0,
+                            answer_id: effect.answer_id,
+                            rule_id,
+                            question_id: effect.question_id,
+                        })
+                        .collect(),
+                    rule_attribute_attributevalue_ids: rule
+                        .rule_attribute_attributevalue_ids
+                        .into_iter()
+                        .map(|effect| RuleAttributeAttributeValueModel {
+                            id: 0,
+                            attribute_value_id: effect.attribute_value_id,
+                            rule_id,
+                            attribute_id: effect.attribute_id,
+                        })
+                        .collect(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn printed_rules_parse_back_unchanged() {
+        let (questions, attributes, rules) = (questions(), attributes(), rules());
+        let printer = RuleDslPrinter::new(&questions, &attributes);
+        let text = printer.print(&rules);
+
+        let parsed = created(
+            RuleDslParser::new(1, &questions, &attributes)
+                .parse(&text)
+                .unwrap(),
+        );
+
+        assert_eq!(parsed.len(), rules.len());
+        for (parsed, rule) in parsed.iter().zip(&rules) {
+            assert_eq!(parsed.attribute_rule, rule.attribute_rule);
+            assert_eq!(parsed.certainty_factor, rule.certainty_factor);
+            assert_eq!(parsed.condition, rule.condition);
+            assert_eq!(parsed.clauses.len(), rule.clauses.len());
+            for (parsed, clause) in parsed.clauses.iter().zip(&rule.clauses) {
+                assert_eq!(parsed.question_id, clause.question_id);
+                assert_eq!(parsed.operator, clause.operator);
+                assert_eq!(parsed.value_type, clause.value_type);
+                assert_eq!(parsed.compared_value, clause.compared_value);
+            }
+        }
+        assert_eq!(printer.print(&parsed), text);
+    }
+
+    #[test]
+    fn decimals_are_printed_without_exponent() {
+        let (questions, attributes) = (questions(), attributes());
+        let text = RuleDslPrinter::new(&questions, &attributes).print(&rules()[..1]);
+
+        assert!(text.contains("ABOVE 0.0000001"), "{}", text);
+        assert!(!text.contains("e-"), "{}", text);
+    }
+
+    #[test]
+    fn rule_without_clauses_is_printed_as_false() {
+        let (questions, attributes) = (questions(), attributes());
+        let text = RuleDslPrinter::new(&questions, &attributes).print(&rules()[2..]);
+
+        assert_eq!(text, "IF FALSE\nTHEN \"Кашель?\" = \"нет\" CF -0.5\n");
+    }
+
+    #[test]
+    fn reports_position_of_unknown_question() {
+        let (questions, attributes) = (questions(), attributes());
+        let error = RuleDslParser::new(1, &questions, &attributes)
+            .parse("IF \"Температура\" > 1\nTHEN \"Рост\" = \"да\"")
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            DbErr::Custom(message)
+                if message == "Строка 2, позиция 6: атрибут или вопрос \"Рост\" не найден"
+        ));
+    }
+}
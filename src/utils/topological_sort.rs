@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 
-pub fn topological_sort(graph: &HashMap<i32, HashSet<i32>>) -> Vec<i32> {
+// При наличии цикла возвращает его вершины в порядке обхода ребер
+pub fn topological_sort(graph: &HashMap<i32, HashSet<i32>>) -> Result<Vec<i32>, Vec<i32>> {
     let mut in_degree = HashMap::new();
     let mut sorted = Vec::with_capacity(graph.len());
     let mut queue = VecDeque::new();
@@ -33,9 +34,102 @@ pub fn topological_sort(graph: &HashMap<i32, HashSet<i32>>) -> Vec<i32> {
     }
 
     sorted.reverse();
-    if sorted.len() == graph.len() {
-        sorted
+    if sorted.len() == in_degree.len() {
+        Ok(sorted)
     } else {
-        Vec::new()
+        let remaining: HashSet<i32> = in_degree
+            .into_iter()
+            .filter(|(_, degree)| *degree > 0)
+            .map(|(node, _)| node)
+            .collect();
+        Err(find_cycle(graph, &remaining))
+    }
+}
+
+// У каждой оставшейся вершины есть входящее ребро из оставшейся вершины,
+// поэтому, идя по ним назад, мы обязательно замкнем цикл
+fn find_cycle(graph: &HashMap<i32, HashSet<i32>>, remaining: &HashSet<i32>) -> Vec<i32> {
+    let Some(&start) = remaining.iter().min() else {
+        return Vec::new();
+    };
+    let mut path = vec![start];
+    let mut node = start;
+
+    loop {
+        let Some(previous) = graph
+            .iter()
+            .filter(|(from, neighbors)| remaining.contains(from) && neighbors.contains(&node))
+            .map(|(&from, _)| from)
+            .min()
+        else {
+            return Vec::new();
+        };
+
+        if let Some(position) = path.iter().position(|&visited| visited == previous) {
+            let mut cycle = path.split_off(position);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(previous);
+        node = previous;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(i32, i32)]) -> HashMap<i32, HashSet<i32>> {
+        let mut graph: HashMap<i32, HashSet<i32>> = HashMap::new();
+        edges.iter().for_each(|(from, to)| {
+            graph.entry(*from).or_default().insert(*to);
+        });
+        graph
+    }
+
+    fn is_cycle(graph: &HashMap<i32, HashSet<i32>>, cycle: &[i32]) -> bool {
+        !cycle.is_empty()
+            && cycle
+                .iter()
+                .zip(cycle.iter().cycle().skip(1))
+                .all(|(from, to)| {
+                    graph
+                        .get(from)
+                        .is_some_and(|neighbors| neighbors.contains(to))
+                })
+    }
+
+    #[test]
+    fn dependencies_come_first() {
+        let graph = graph(&[(1, 2), (1, 3), (2, 3), (4, 1)]);
+
+        let sorted = topological_sort(&graph).unwrap();
+
+        assert_eq!(sorted.len(), 4);
+        let position = |node: i32| sorted.iter().position(|&sorted| sorted == node).unwrap();
+        graph.iter().for_each(|(from, neighbors)| {
+            neighbors
+                .iter()
+                .for_each(|to| assert!(position(*to) < position(*from)))
+        });
+    }
+
+    #[test]
+    fn reports_cycle_in_edge_order() {
+        // 4 ведет в цикл, но сам в него не входит
+        let graph = graph(&[(4, 1), (1, 2), (2, 3), (3, 1), (3, 5)]);
+
+        let mut cycle = topological_sort(&graph).unwrap_err();
+
+        assert!(is_cycle(&graph, &cycle), "{:?}", cycle);
+        cycle.sort();
+        assert_eq!(cycle, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn reports_self_loop() {
+        let graph = graph(&[(1, 2), (2, 2)]);
+
+        assert_eq!(topological_sort(&graph), Err(vec![2]));
     }
 }
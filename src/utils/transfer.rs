@@ -0,0 +1,135 @@
+use crate::error::CustomErrors;
+use axum::body::Bytes;
+use futures::{stream, Stream, StreamExt};
+use http::StatusCode;
+use std::{
+    io::{self, Read, Write},
+    pin::pin,
+};
+use tokio::sync::mpsc;
+
+pub fn too_large(limit: usize) -> CustomErrors {
+    CustomErrors::StringError {
+        status: StatusCode::PAYLOAD_TOO_LARGE,
+        error: format!("Файл больше {} МБ", limit / (1024 * 1024)),
+    }
+}
+
+// Читает поток по частям и прерывается, как только размер превысит limit,
+// не дожидаясь конца загрузки
+pub async fn read_limited<S, E>(stream: S, limit: usize) -> Result<Vec<u8>, CustomErrors>
+where
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    let mut stream = pin!(stream);
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|_| CustomErrors::StringError {
+            status: StatusCode::BAD_REQUEST,
+            error: "Ошибка при загрузке файла".to_string(),
+        })?;
+        if data.len() + chunk.len() > limit {
+            return Err(too_large(limit));
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+pub fn receiver_stream<T>(receiver: mpsc::Receiver<T>) -> impl Stream<Item = T> {
+    stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+// Запись из синхронного кода в spawn_blocking в асинхронный поток, например в тело ответа.
+// Если поток закрыт (клиент отключился), запись завершается ошибкой
+pub struct ChannelWriter(mpsc::Sender<io::Result<Bytes>>);
+
+impl ChannelWriter {
+    pub fn new(sender: mpsc::Sender<io::Result<Bytes>>) -> Self {
+        ChannelWriter(sender)
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(Ok(Bytes::copy_from_slice(data)))
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+// Чтение в синхронном коде частей, которые присылает асинхронный; закрытый канал - конец данных
+pub struct ChannelReader {
+    receiver: mpsc::Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    position: usize,
+}
+
+impl ChannelReader {
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        ChannelReader {
+            receiver,
+            chunk: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.chunk.len() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => {
+                    self.chunk = chunk;
+                    self.position = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let count = buffer.len().min(self.chunk.len() - self.position);
+        buffer[..count].copy_from_slice(&self.chunk[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// filename - ASCII-запасной вариант для старых клиентов, filename* - полное имя в UTF-8
+pub fn attachment_disposition(file_name: &str) -> String {
+    let mut ascii_name = String::new();
+    for symbol in file_name.chars() {
+        match symbol {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '_' => ascii_name.push(symbol),
+            _ if !ascii_name.ends_with('-') => ascii_name.push('-'),
+            _ => {}
+        }
+    }
+    let ascii_name = match ascii_name.trim_matches('-') {
+        name if name.trim_start_matches('.').is_empty() => "download".to_string(),
+        name => name.to_string(),
+    };
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        ascii_name,
+        percent_encode(file_name)
+    )
+}